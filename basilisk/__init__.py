@@ -50,6 +50,9 @@ Alias = Annotated[T, None]
 Tuple = tuple
 Vec = list
 
+# Return annotation for several Candid return values: Returns[text, nat64]
+Returns = tuple
+
 Record = TypedDict
 Variant = TypedDict
 
@@ -76,7 +79,15 @@ GuardType = Callable[..., GuardResult]
 
 
 def query(
-    _func: Optional[Callable[..., Any]] = None, *, guard: Optional[GuardType] = None
+    _func: Optional[Callable[..., Any]] = None,
+    *,
+    guard: Optional[GuardType] = None,
+    err_on_exception: bool = False,
+    raise_on_reject: bool = False,
+    preserve_opt: Optional[bool] = None,
+    decoding_quota: Optional[int] = None,
+    skipping_quota: Optional[int] = None,
+    zero_sized_quota: Optional[int] = None,
 ) -> Callable[..., Any]:
     def decorator(func: Callable[..., Any]):
         return func
@@ -88,7 +99,35 @@ def query(
 
 
 def update(
-    _func: Optional[Callable[..., Any]] = None, *, guard: Optional[GuardType] = None
+    _func: Optional[Callable[..., Any]] = None,
+    *,
+    guard: Optional[GuardType] = None,
+    err_on_exception: bool = False,
+    raise_on_reject: bool = False,
+    preserve_opt: Optional[bool] = None,
+    decoding_quota: Optional[int] = None,
+    skipping_quota: Optional[int] = None,
+    zero_sized_quota: Optional[int] = None,
+) -> Callable[..., Any]:
+    def decorator(func: Callable[..., Any]):
+        return func
+
+    if _func is None:
+        return decorator
+    else:
+        return decorator(_func)
+
+
+def composite_query(
+    _func: Optional[Callable[..., Any]] = None,
+    *,
+    guard: Optional[GuardType] = None,
+    err_on_exception: bool = False,
+    raise_on_reject: bool = False,
+    preserve_opt: Optional[bool] = None,
+    decoding_quota: Optional[int] = None,
+    skipping_quota: Optional[int] = None,
+    zero_sized_quota: Optional[int] = None,
 ) -> Callable[..., Any]:
     def decorator(func: Callable[..., Any]):
         return func
@@ -155,9 +194,9 @@ Oneway = Callable
 
 class CallResult(Generic[T]):
     Ok: T
-    Err: Optional[str]
+    Err: Optional["CallError"]
 
-    def __init__(self, Ok: T, Err: str):
+    def __init__(self, Ok: T, Err: "CallError"):
         self.Ok = Ok
         self.Err = Err
 
@@ -167,6 +206,102 @@ class CallResult(Generic[T]):
 
     def with_cycles128(self, cycles: nat) -> "CallResult[T]": ...
 
+    def with_timeout(self, seconds: int) -> "CallResult[T]": ...
+
+    @property
+    def timed_out(self) -> bool:
+        return isinstance(self.Err, CallTimeout)
+
+    @property
+    def cycles_refunded(self) -> Optional[nat]:
+        """Cycles refunded for this call, or None for a result no call produced."""
+        ...
+
+    @property
+    def instructions_before(self) -> Optional[nat64]:
+        """ic.performance_counter(1) when the call was made."""
+        ...
+
+    @property
+    def instructions_after(self) -> Optional[nat64]:
+        """ic.performance_counter(1) when the call's response was handed back."""
+        ...
+
+
+class CallError(str):
+    """The Err of a rejected cross-canister call.
+
+    It reads as the string "Rejection code N, message" and carries the parts
+    as attributes, so callers don't need to parse it.
+    """
+
+    code_number: int
+    reject_code: "RejectionCode"  # the same value ic.reject_code() returns
+    reject_message: str
+    error_code: Optional[str]  # e.g. "IC0503", when the message names one
+
+    def __new__(
+        cls,
+        code_number: int,
+        code_name: str,
+        reject_message: str,
+        error_code: Optional[str] = None,
+    ) -> "CallError":
+        self = super().__new__(cls, f"Rejection code {code_number}, {reject_message}")
+        self.code_number = code_number
+        self.reject_code = {code_name: None}  # type: ignore
+        self.reject_message = reject_message
+        self.error_code = error_code
+        return self
+
+    @property
+    def code_name(self) -> str:
+        return next(iter(self.reject_code))
+
+
+class CallTimeout(CallError):
+    """The Err of a bounded-wait call that got no response in time.
+
+    Made by calls set up with `with_timeout(seconds)`. The callee may or may
+    not have acted on such a call.
+    """
+
+
+class CallRejected(Exception):
+    """Raised where a method declared with `raise_on_reject=True` yields or
+    awaits a service call that was rejected.
+
+    `result` is the call's CallResult and `error` its CallError. For a list of
+    calls (or `gather`), the first rejection is raised and `results` holds all
+    of the CallResults.
+    """
+
+    result: "CallResult[Any]"
+    error: CallError
+    results: Optional[list["CallResult[Any]"]]
+
+    def __init__(
+        self,
+        result: "CallResult[Any]",
+        results: Optional[list["CallResult[Any]"]] = None,
+    ):
+        self.result = result
+        self.error = result.Err  # type: ignore
+        self.results = results
+        super().__init__(str(self.error))
+
+    @property
+    def reject_code(self) -> "RejectionCode":
+        return self.error.reject_code
+
+    @property
+    def reject_message(self) -> str:
+        return self.error.reject_message
+
+    @property
+    def timed_out(self) -> bool:
+        return isinstance(self.error, CallTimeout)
+
 
 class RejectionCode(Variant, total=False):
     NoError: null
@@ -184,6 +319,15 @@ class NotifyResult(Variant, total=False):
     Err: RejectionCode
 
 
+class BackgroundError(Record):
+    """A timer callback, heartbeat or background generator that raised."""
+
+    callback: str
+    exception_type: str
+    message: str
+    time: nat64  # ic.time() when it failed
+
+
 class StableMemoryError(Variant, total=False):
     OutOfMemory: null
     OutOfBounds: null
@@ -202,6 +346,30 @@ class Stable64GrowResult(Variant, total=False):
 FuncTuple = tuple[Principal, str]
 
 
+class FuncRef(FuncTuple):
+    """A Candid func value: (principal, method) plus the func type's signature
+    text when known. Func values received by a canister decode to FuncRef;
+    calling one yields a call to the referenced method."""
+
+    signature: Optional[str]
+
+    def __new__(cls, principal: Principal, method: str, signature: Optional[str] = None):
+        ref = super().__new__(cls, (principal, method))
+        ref.signature = signature
+        return ref
+
+    @property
+    def principal(self) -> Principal:
+        return self[0]
+
+    @property
+    def method(self) -> str:
+        return self[1]
+
+    def __call__(self, *args: Any) -> Any:
+        return AsyncInfo("call", [self[0], self[1], *args])
+
+
 def Func(callable: Callable[..., Any]) -> type[FuncTuple]:
     return FuncTuple
 
@@ -233,6 +401,12 @@ class ic(Generic[T]):
     def arg_data_raw_size() -> nat32:
         return _basilisk_ic.arg_data_raw_size()  # type: ignore
 
+    @staticmethod
+    def background_errors() -> Vec["BackgroundError"]:
+        """The most recent failures of timer callbacks, the heartbeat and
+        background generators, oldest first (kept across upgrades)."""
+        return _basilisk_ic.background_errors()  # type: ignore
+
     @staticmethod
     def call_raw(
         canister_id: Principal, method: str, args_raw: blob, payment: nat64
@@ -292,6 +466,42 @@ class ic(Generic[T]):
         """
         return _basilisk_ic.candid_decode(candid_encoded)  # type: ignore
 
+    @staticmethod
+    def candid_encode_values(types: str, values: Any) -> blob:
+        """Encodes Python values as Candid, the way method results are encoded.
+
+        Args:
+            types (str): a Candid type such as "vec nat", or a type list
+                such as "(text, opt nat)"
+            values (Any): the value of a single type, or a tuple with one
+                value per type of a type list
+
+        Returns:
+            (blob): the Candid-encoded message
+
+        Raises:
+            ValueError: a type is not a known Candid type, or the values do not match the types.
+        """
+        return _basilisk_ic.candid_encode_values(types, values)  # type: ignore
+
+    @staticmethod
+    def candid_decode_values(candid_encoded: blob, types: str) -> Any:
+        """Decodes Candid bytes into Python values, the way method arguments are decoded.
+
+        Args:
+            candid_encoded (blob): a Candid-encoded message
+            types (str): a Candid type such as "vec nat", or a type list
+                such as "(text, opt nat)"
+
+        Returns:
+            (Any): the value for a single type, or a tuple of values for a type list
+
+        Raises:
+            ValueError: a type is not a known Candid type, or the bytes are
+                not a valid message of the given types.
+        """
+        return _basilisk_ic.candid_decode_values(candid_encoded, types)  # type: ignore
+
     @staticmethod
     def canister_balance() -> nat64:
         return _basilisk_ic.canister_balance()  # type: ignore
@@ -361,6 +571,21 @@ class ic(Generic[T]):
     def reject(x: str):
         _basilisk_ic.reject(x)  # type: ignore
 
+    @staticmethod
+    def register_field_names(*names: str):
+        """Make these record field / variant case names recoverable when
+        decoding values whose Candid type the canister does not declare."""
+        for name in names:
+            _basilisk_ic.register_field_name(name)  # type: ignore
+
+    @staticmethod
+    def preserve_opt_nesting(enabled: bool = True):
+        """Decode present opt values as Opt(value) instead of the bare value,
+        so `opt opt T` and `opt null` keep their nesting. Applies to methods
+        without their own preserve_opt= setting and to inter-canister call
+        results."""
+        _basilisk_ic.preserve_opt_nesting(enabled)  # type: ignore
+
     @staticmethod
     def reject_code() -> RejectionCode:
         return _basilisk_ic.reject_code()  # type: ignore
@@ -382,6 +607,12 @@ class ic(Generic[T]):
     def set_certified_data(data: blob):
         _basilisk_ic.set_certified_data(data)  # type: ignore
 
+    @staticmethod
+    def set_error_handler(handler: Optional[Callable[["BackgroundError"], Any]]) -> None:
+        """Call handler with each failure ic.background_errors() records;
+        None unregisters it. Exceptions from the handler are only printed."""
+        _basilisk_ic.set_error_handler(handler)  # type: ignore
+
     @staticmethod
     def set_timer(delay: Duration, func: Callable[[], Any]) -> TimerId:
         return _basilisk_ic.set_timer(delay, func)  # type: ignore
@@ -448,10 +679,12 @@ P = ParamSpec("P")
 class AsyncInfo:
     name: str
     args: list[Any]
+    timeout: Optional[int]
 
     def __init__(self, name: str, args: list[Any]):
         self.name = name
         self.args = args
+        self.timeout = None
 
     def with_cycles(self, cycles: nat64) -> "AsyncInfo":
         return AsyncInfo("call_with_payment", [*self.args, cycles])
@@ -459,6 +692,13 @@ class AsyncInfo:
     def with_cycles128(self, cycles: nat) -> "AsyncInfo":
         return AsyncInfo("call_with_payment128", [*self.args, cycles])
 
+    def with_timeout(self, seconds: int) -> "AsyncInfo":
+        """Make this a bounded-wait call: if no response arrives within
+        `seconds` (capped by the IC at 300), its CallResult has a CallTimeout
+        Err."""
+        self.timeout = seconds
+        return self
+
     def notify(self) -> NotifyResult:
         qualname: str = self.args[1]
         with_payment = (
@@ -472,6 +712,25 @@ class AsyncInfo:
 
         return getattr(_basilisk_ic, notify_function_name)(self.args)  # type: ignore
 
+    def __await__(self) -> Generator["AsyncInfo", Any, "CallResult[Any]"]:
+        return (yield self)
+
+
+class gather:
+    """Several service calls made concurrently.
+
+    `await gather(a, b)` in an async def method (like `yield [a, b]` in a
+    generator method) returns the calls' CallResults in order.
+    """
+
+    calls: list[AsyncInfo]
+
+    def __init__(self, *calls: AsyncInfo):
+        self.calls = list(calls)
+
+    def __await__(self) -> Generator[list[AsyncInfo], Any, list[CallResult[Any]]]:
+        return (yield self.calls)
+
 
 # TODO this decorator is removing the static type checking of the self parameter for instance methods
 # TODO watch out for *kwargs
@@ -643,9 +902,75 @@ class CandidError(Error):
     pass
 
 
+class Reject(Exception):
+    """Raise from a canister method to reject the call with this message.
+
+    Unlike other uncaught exceptions, which trap, the caller sees a
+    CANISTER_REJECT with the given message.
+    """
+
+    pass
+
+
+class LockBusy(Reject):
+    """Raised by `Lock.acquire` when another call holds the lock.
+
+    Uncaught, it rejects the call like `Reject`.
+    """
+
+    pass
+
+
 # endregion Exceptions
 
 
+class Lock:
+    """A named lock a method can hold across yields and awaits.
+
+    Other messages run while a method waits on a cross-canister call. Holding
+    a lock keeps calls that take the same lock from interleaving with it::
+
+        @update
+        def withdraw(amount: nat) -> Async[text]:
+            with Lock.for_caller("withdraw"):
+                yield ledger.transfer(...)
+
+    Acquiring never waits: if another call holds the lock, `acquire` raises
+    `LockBusy`, which rejects the call unless caught. The lock is released on
+    leaving the `with` block, by `release`, or when the method holding it
+    finishes, raises, or is dropped after a trap.
+    """
+
+    key: str
+
+    def __init__(self, key: str):
+        self.key = str(key)
+
+    @classmethod
+    def for_caller(cls, scope: str = "") -> "Lock":
+        """A lock per caller: one call at a time from each principal."""
+        return cls(f"{scope}@{_basilisk_ic.caller()}")  # type: ignore
+
+    def acquire(self) -> "Lock":
+        if not _basilisk_ic.lock_acquire(self.key):  # type: ignore
+            raise LockBusy(f"Lock '{self.key}' is held by another call; try again later")
+        return self
+
+    def release(self) -> bool:
+        return _basilisk_ic.lock_release(self.key)  # type: ignore
+
+    @property
+    def locked(self) -> bool:
+        return _basilisk_ic.lock_held(self.key)  # type: ignore
+
+    def __enter__(self) -> "Lock":
+        return self.acquire()
+
+    def __exit__(self, *exc_info: Any) -> bool:
+        self.release()
+        return False
+
+
 def run(path):
     """Execute a Python file from the canister filesystem.
 
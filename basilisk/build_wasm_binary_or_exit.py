@@ -156,7 +156,7 @@ def build_with_template(
             "name": "__shell__",
             "method_type": "update",
             "params": [{"name": "code", "candid_type": "text"}],
-            "returns": "text",
+            "returns": ["text"],
             "guard": "__basilisk_controller_guard__",
         })
         if verbose:
@@ -169,7 +169,7 @@ def build_with_template(
             "name": "__browse__",
             "method_type": "query",
             "params": [{"name": "query", "candid_type": "text"}],
-            "returns": "text",
+            "returns": ["text"],
         })
         if verbose:
             print(f"  Injected built-in __browse__ (query, {len(stable_structures)} stable structure(s))")
@@ -189,7 +189,7 @@ def build_with_template(
             "name": "_basilisk_post_upgrade_wrapper",
             "method_type": "post_upgrade",
             "params": [],
-            "returns": "void",
+            "returns": [],
         }
         if verbose:
             print("  Injected post_upgrade with schema upgrade check")
@@ -198,28 +198,26 @@ def build_with_template(
     methods = [m for m in methods if m["method_type"] != "_guard"]
 
     # 3d. Add __get_candid_interface_tmp_hack built-in query method.
-    # The Candid UI calls this to fetch the .did interface at runtime.
+    # The Candid UI calls this to fetch the .did interface at runtime; the
+    # template answers it itself from the injected method metadata.
     hack_method = {
         "name": "__get_candid_interface_tmp_hack",
         "method_type": "query",
         "params": [],
-        "returns": "text",
+        "returns": ["text"],
     }
     methods.append(hack_method)
 
-    # Generate .did content (including the hack method) so we can embed it
+    # Generate .did content (including the hack method) for dfx
     candid_content = generate_candid_from_methods(methods, type_defs, lifecycle)
 
-    # Append a Python function that returns the .did content
-    python_source += (
-        "\ndef __get_candid_interface_tmp_hack() -> str:\n"
-        "    return " + repr(candid_content) + "\n"
-    )
-
     # 4. Inject Python source + method metadata into template wasm
     output_wasm = f"{paths['canister']}/{canister_name}.wasm"
     os.makedirs(os.path.dirname(output_wasm), exist_ok=True)
-    manipulate_wasm(template_wasm_path, output_wasm, python_source, methods, type_defs, lifecycle)
+    manipulate_wasm(
+        template_wasm_path, output_wasm, python_source, methods, type_defs, lifecycle,
+        decoding_quotas=_decoding_quotas_from_env(),
+    )
 
     # 5. Skip wasi2ic and wasm-opt: the downloaded template is already post-processed.
     # Running wasm-opt again would strip the passive data segments we just injected.
@@ -232,6 +230,28 @@ def build_with_template(
         print(candid_content)
 
 
+def _decoding_quotas_from_env() -> dict[str, int]:
+    """Canister-wide Candid decoding quotas from the BASILISK_*_QUOTA env vars.
+
+    Methods can override each quota with the decoding_quota, skipping_quota
+    and zero_sized_quota decorator kwargs.
+    """
+    quotas = {}
+    for env_var, quota in [
+        ("BASILISK_DECODING_QUOTA", "decoding"),
+        ("BASILISK_SKIPPING_QUOTA", "skipping"),
+        ("BASILISK_ZERO_SIZED_QUOTA", "zero_sized"),
+    ]:
+        value = os.environ.get(env_var)
+        if value:
+            try:
+                quotas[quota] = int(value)
+            except ValueError:
+                print(red(f"{env_var} must be an integer, got {value!r}"))
+                sys.exit(1)
+    return quotas
+
+
 def find_template_wasm(paths: Paths) -> str | None:
     """Locate the pre-built CPython canister template wasm.
 
@@ -24,7 +24,7 @@ ic-cdk-timers = "0.7.0"
 candid = {{ version = "0.10.6", features = ["value"] }}
 candid_parser = "0.1.4"
 basilisk-vm-value-derive = {{ path = "./basilisk_vm_value_derive" }}
-basilisk_cpython = {{ path = "./basilisk_cpython" }}
+basilisk_cpython = {{ path = "./basilisk_cpython", features = ["num-bigint"] }}
 num-bigint = "0.4"
 
 serde = {{ version = "1.0.137", default-features = false, features = [] }}
@@ -94,7 +94,7 @@ macro_rules! impl_signed_int {
     };
 }
 
-impl_signed_int!(i8, i16, i32, i64, i128);
+impl_signed_int!(i8, i16, i32, i64);
 
 // Unsigned integers
 macro_rules! impl_unsigned_int {
@@ -117,7 +117,83 @@ macro_rules! impl_unsigned_int {
     };
 }
 
-impl_unsigned_int!(u8, u16, u32, u64, u128);
+impl_unsigned_int!(u8, u16, u32, u64);
+
+// 128-bit integers (e.g. cycle amounts) go through the byte-array long API
+impl TryIntoPyObject for u128 {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        PyObjectRef::from_u128(self).map_err(|e| TryIntoVmValueError(e.to_rust_err_string()))
+    }
+}
+
+impl TryFromPyObject for u128 {
+    fn try_from_py_object(obj: PyObjectRef) -> Result<Self, PyError> {
+        obj.extract_u128()
+    }
+}
+
+impl TryIntoPyObject for i128 {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        PyObjectRef::from_i128(self).map_err(|e| TryIntoVmValueError(e.to_rust_err_string()))
+    }
+}
+
+impl TryFromPyObject for i128 {
+    fn try_from_py_object(obj: PyObjectRef) -> Result<Self, PyError> {
+        obj.extract_i128()
+    }
+}
+
+// Arbitrary-precision integers (Candid nat / int)
+#[cfg(feature = "num-bigint")]
+impl TryIntoPyObject for &num_bigint::BigUint {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        match u64::try_from(self) {
+            Ok(v) => PyObjectRef::from_u64(v),
+            Err(_) => PyObjectRef::from_le_bytes(&self.to_bytes_le(), false),
+        }
+        .map_err(|e| TryIntoVmValueError(e.to_rust_err_string()))
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl TryIntoPyObject for num_bigint::BigUint {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        (&self).try_into_py_object()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl TryFromPyObject for num_bigint::BigUint {
+    fn try_from_py_object(obj: PyObjectRef) -> Result<Self, PyError> {
+        Ok(num_bigint::BigUint::from_bytes_le(&obj.extract_le_bytes(false)?))
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl TryIntoPyObject for &num_bigint::BigInt {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        match i64::try_from(self) {
+            Ok(v) => PyObjectRef::from_i64(v),
+            Err(_) => PyObjectRef::from_le_bytes(&self.to_signed_bytes_le(), true),
+        }
+        .map_err(|e| TryIntoVmValueError(e.to_rust_err_string()))
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl TryIntoPyObject for num_bigint::BigInt {
+    fn try_into_py_object(self) -> Result<PyObjectRef, TryIntoVmValueError> {
+        (&self).try_into_py_object()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl TryFromPyObject for num_bigint::BigInt {
+    fn try_from_py_object(obj: PyObjectRef) -> Result<Self, PyError> {
+        Ok(num_bigint::BigInt::from_signed_bytes_le(&obj.extract_le_bytes(true)?))
+    }
+}
 
 // Floats
 impl TryIntoPyObject for f32 {
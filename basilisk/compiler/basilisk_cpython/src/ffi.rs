@@ -122,6 +122,7 @@ extern "C" {
     pub fn PyObject_SetItem(o: *mut PyObject, key: *mut PyObject, v: *mut PyObject) -> c_int;
     pub fn PyObject_Type(o: *mut PyObject) -> *mut PyObject;
     pub fn PyObject_RichCompareBool(o1: *mut PyObject, o2: *mut PyObject, opid: c_int) -> c_int;
+    pub fn PyObject_IsInstance(inst: *mut PyObject, cls: *mut PyObject) -> c_int;
     pub fn PyObject_CallMethodObjArgs(o: *mut PyObject, name: *mut PyObject, ...) -> *mut PyObject;
 }
 
@@ -186,6 +187,25 @@ extern "C" {
     pub fn PyLong_AsLongLong(o: *mut PyObject) -> core::ffi::c_longlong;
     pub fn PyLong_AsUnsignedLongLong(o: *mut PyObject) -> core::ffi::c_ulonglong;
     pub fn PyLong_Check(o: *mut PyObject) -> c_int;
+
+    // Arbitrary-precision conversion via base-256 byte arrays (cpython/longobject.h).
+    // `v` must be an exact int (or subclass); use PyNumber_Index first otherwise.
+    pub fn _PyLong_FromByteArray(
+        bytes: *const u8,
+        n: usize,
+        little_endian: c_int,
+        is_signed: c_int,
+    ) -> *mut PyObject;
+    pub fn _PyLong_AsByteArray(
+        v: *mut PyObject,
+        bytes: *mut u8,
+        n: usize,
+        little_endian: c_int,
+        is_signed: c_int,
+        with_exceptions: c_int,
+    ) -> c_int;
+    /// Number of bits needed for abs(v); 0 for 0.
+    pub fn _PyLong_NumBits(v: *mut PyObject) -> usize;
 }
 
 // === Float objects ===
@@ -328,6 +348,7 @@ extern "C" {
 extern "C" {
     pub fn PyNumber_Long(o: *mut PyObject) -> *mut PyObject;
     pub fn PyNumber_Float(o: *mut PyObject) -> *mut PyObject;
+    pub fn PyNumber_Index(o: *mut PyObject) -> *mut PyObject;
 }
 
 // === Iterator protocol ===
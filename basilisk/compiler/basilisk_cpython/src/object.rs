@@ -4,7 +4,7 @@
 //! to CPython object operations.
 
 use crate::ffi;
-use core::ffi::c_char;
+use core::ffi::{c_char, c_int};
 use core::fmt;
 use core::ptr;
 
@@ -243,6 +243,21 @@ impl PyObjectRef {
         unsafe { self.ptr == &mut ffi::_Py_NoneStruct as *mut ffi::PyObject }
     }
 
+    /// Check if the object is an instance of `cls` or a subclass of it.
+    /// Equivalent to `isinstance(obj, cls)`; an error counts as `false`.
+    pub fn is_instance(&self, cls: &PyObjectRef) -> bool {
+        unsafe {
+            match ffi::PyObject_IsInstance(self.ptr, cls.ptr) {
+                1 => true,
+                0 => false,
+                _ => {
+                    ffi::PyErr_Clear();
+                    false
+                }
+            }
+        }
+    }
+
     /// Check truthiness. Equivalent to `bool(obj)`.
     pub fn is_true(&self) -> bool {
         unsafe { ffi::PyObject_IsTrue(self.ptr) == 1 }
@@ -299,6 +314,29 @@ impl PyObjectRef {
         }
     }
 
+    /// Create a Python int from u128.
+    pub fn from_u128(v: u128) -> Result<PyObjectRef, PyError> {
+        Self::from_le_bytes(&v.to_le_bytes(), false)
+    }
+
+    /// Create a Python int from i128.
+    pub fn from_i128(v: i128) -> Result<PyObjectRef, PyError> {
+        Self::from_le_bytes(&v.to_le_bytes(), true)
+    }
+
+    /// Create a Python int of any size from little-endian bytes
+    /// (two's complement when `signed`).
+    pub fn from_le_bytes(bytes: &[u8], signed: bool) -> Result<PyObjectRef, PyError> {
+        unsafe {
+            let ptr = ffi::_PyLong_FromByteArray(bytes.as_ptr(), bytes.len(), 1, signed as c_int);
+            if ptr.is_null() {
+                Err(PyError::fetch())
+            } else {
+                Ok(PyObjectRef { ptr })
+            }
+        }
+    }
+
     /// Create a Python float from f64.
     pub fn from_f64(v: f64) -> Result<PyObjectRef, PyError> {
         unsafe {
@@ -373,6 +411,61 @@ impl PyObjectRef {
         }
     }
 
+    /// Extract as u128 (from Python int). Raises OverflowError if out of range.
+    pub fn extract_u128(&self) -> Result<u128, PyError> {
+        let mut buf = [0u8; 16];
+        self.extract_le_bytes_into(&mut buf, false)?;
+        Ok(u128::from_le_bytes(buf))
+    }
+
+    /// Extract as i128 (from Python int). Raises OverflowError if out of range.
+    pub fn extract_i128(&self) -> Result<i128, PyError> {
+        let mut buf = [0u8; 16];
+        self.extract_le_bytes_into(&mut buf, true)?;
+        Ok(i128::from_le_bytes(buf))
+    }
+
+    /// Extract a Python int of any size as little-endian bytes, using the
+    /// fewest bytes that hold the value (two's complement when `signed`).
+    /// Unsigned extraction of a negative int raises OverflowError.
+    pub fn extract_le_bytes(&self, signed: bool) -> Result<Vec<u8>, PyError> {
+        let index = self.to_index()?;
+        let bits = unsafe { ffi::_PyLong_NumBits(index.ptr) };
+        if bits == usize::MAX {
+            return Err(PyError::fetch());
+        }
+        // One extra bit for the sign of signed values.
+        let len = if signed { bits / 8 + 1 } else { bits.div_ceil(8).max(1) };
+        let mut buf = vec![0u8; len];
+        index.extract_le_bytes_into(&mut buf, signed)?;
+        Ok(buf)
+    }
+
+    /// Fill `buf` with the int's little-endian bytes; OverflowError if it does not fit.
+    fn extract_le_bytes_into(&self, buf: &mut [u8], signed: bool) -> Result<(), PyError> {
+        let index = self.to_index()?;
+        unsafe {
+            let rc = ffi::_PyLong_AsByteArray(index.ptr, buf.as_mut_ptr(), buf.len(), 1, signed as c_int, 1);
+            if rc < 0 {
+                Err(PyError::fetch())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// `operator.index(self)`: the object as an exact Python int.
+    fn to_index(&self) -> Result<PyObjectRef, PyError> {
+        unsafe {
+            let ptr = ffi::PyNumber_Index(self.ptr);
+            if ptr.is_null() {
+                Err(PyError::fetch())
+            } else {
+                Ok(PyObjectRef { ptr })
+            }
+        }
+    }
+
     /// Extract as f64 (from Python float).
     pub fn extract_f64(&self) -> Result<f64, PyError> {
         unsafe {
@@ -444,6 +537,9 @@ pub struct PyError {
     /// For StopIteration exceptions, this holds the `.value` attribute
     /// which carries the generator's return value.
     pub value: Option<PyObjectRef>,
+    /// The exception instance, when the error was fetched from Python
+    /// (so it can be raised again, e.g. thrown into a generator).
+    pub exception: Option<PyObjectRef>,
 }
 
 impl PyError {
@@ -515,17 +611,18 @@ impl PyError {
                     ffi::PyErr_Clear();
                     String::new()
                 };
-                ffi::Py_DecRef(pvalue);
                 (msg, stop_iter_value)
             } else {
                 (String::new(), None)
             };
+            // pvalue is an owned reference; keep it as the exception instance
+            let exception = PyObjectRef::from_owned(pvalue);
 
             if !ptraceback.is_null() {
                 ffi::Py_DecRef(ptraceback);
             }
 
-            PyError { type_name, message, value }
+            PyError { type_name, message, value, exception }
         }
     }
 
@@ -535,6 +632,7 @@ impl PyError {
             type_name: type_name.to_string(),
             message: message.to_string(),
             value: None,
+            exception: None,
         }
     }
 
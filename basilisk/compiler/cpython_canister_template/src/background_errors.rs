@@ -0,0 +1,125 @@
+//! Failures of code that runs with no caller to report to: timer callbacks,
+//! the heartbeat, and generators spawned by timers or lifecycle hooks.
+//!
+//! Such a failure used to be printed or trapped on, which nothing in the
+//! canister could observe (and a trap rolls back any record of it). Instead
+//! `report` keeps the most recent failures in a ring buffer that
+//! `ic.background_errors()` returns, and passes each one to the Python
+//! handler registered with `ic.set_error_handler(handler)`. The buffer lives
+//! on the heap between messages; across an upgrade, `save` and `restore`
+//! carry it through the stable file store (see `STABLE_KEY`).
+
+use std::collections::VecDeque;
+
+use basilisk_cpython::{PyError, PyObjectRef};
+
+/// How many failures the ring buffer keeps; older ones are dropped first.
+pub const MAX_BACKGROUND_ERRORS: usize = 64;
+
+static mut BACKGROUND_ERRORS: Option<VecDeque<BackgroundError>> = None;
+
+/// The Python callable registered with `ic.set_error_handler`.
+static mut ERROR_HANDLER: Option<PyObjectRef> = None;
+
+/// The stable memory of the file persistence store, which the shim reserves.
+const FILE_STORE_MEMORY_ID: u8 = 254;
+
+/// The file store entry holding the buffer from `pre_upgrade` until
+/// `post_upgrade`, which removes it before the store is restored to memfs.
+const STABLE_KEY: &[u8] = b"/.basilisk/background_errors";
+
+/// One failed background callback.
+#[derive(Clone, candid::CandidType, serde::Deserialize)]
+pub struct BackgroundError {
+    /// The Python function that raised (a timer callback, the heartbeat, or a hook).
+    pub callback: String,
+    pub exception_type: String,
+    pub message: String,
+    /// `ic.time()` when the failure was reported, in nanoseconds.
+    pub time: u64,
+}
+
+impl BackgroundError {
+    /// The error as the dict `ic.background_errors()` and the handler see.
+    pub fn to_python(&self) -> Result<PyObjectRef, PyError> {
+        let dict = basilisk_cpython::PyDict::new()?;
+        dict.set_item_str("callback", &PyObjectRef::from_str(&self.callback)?)?;
+        dict.set_item_str("exception_type", &PyObjectRef::from_str(&self.exception_type)?)?;
+        dict.set_item_str("message", &PyObjectRef::from_str(&self.message)?)?;
+        dict.set_item_str("time", &PyObjectRef::from_u64(self.time)?)?;
+        Ok(dict.into_object())
+    }
+}
+
+/// Record that `callback` raised `error`, print it, and pass it to the
+/// registered handler. An exception from the handler itself is only printed.
+pub fn report(callback: &str, error: &PyError) {
+    ic_cdk::println!("Error in background callback '{}': {}", callback, error.to_rust_err_string());
+    let background_error = BackgroundError {
+        callback: callback.to_string(),
+        exception_type: error.type_name.clone(),
+        message: error.message.clone(),
+        time: ic_cdk::api::time(),
+    };
+    let errors = unsafe { BACKGROUND_ERRORS.get_or_insert_with(VecDeque::new) };
+    if errors.len() == MAX_BACKGROUND_ERRORS {
+        errors.pop_front();
+    }
+    errors.push_back(background_error.clone());
+
+    let Some(handler) = (unsafe { ERROR_HANDLER.clone() }) else {
+        return;
+    };
+    let called = background_error
+        .to_python()
+        .and_then(|error| basilisk_cpython::PyTuple::new(vec![error]))
+        .and_then(|args| handler.call(&args.into_object(), None));
+    if let Err(e) = called {
+        ic_cdk::println!("Error in error handler: {}", e.to_rust_err_string());
+    }
+}
+
+/// The name to report for a timer callback: its `__qualname__`, since a
+/// callback object is stored under a generated global name (`fallback`).
+pub fn callback_name(func: &PyObjectRef, fallback: &str) -> String {
+    func.get_attr("__qualname__")
+        .and_then(|name| name.extract_str())
+        .unwrap_or_else(|_| fallback.to_string())
+}
+
+/// The recorded failures, oldest first.
+pub fn background_errors() -> Vec<BackgroundError> {
+    unsafe { BACKGROUND_ERRORS.as_ref() }
+        .map(|errors| errors.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Write the recorded failures to the stable file store, in `pre_upgrade`.
+pub fn save() {
+    let errors = background_errors();
+    if errors.is_empty() {
+        return;
+    }
+    let bytes = candid::encode_one(&errors).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!("Failed to encode background errors: {}", e));
+    });
+    crate::stable_structures::smap_init(FILE_STORE_MEMORY_ID);
+    crate::stable_structures::smap_insert(FILE_STORE_MEMORY_ID, STABLE_KEY.to_vec(), bytes);
+}
+
+/// Read back (and remove) the failures `save` wrote, in `post_upgrade`.
+pub fn restore() {
+    crate::stable_structures::smap_init(FILE_STORE_MEMORY_ID);
+    let Some(bytes) = crate::stable_structures::smap_remove(FILE_STORE_MEMORY_ID, STABLE_KEY) else {
+        return;
+    };
+    match candid::decode_one::<Vec<BackgroundError>>(&bytes) {
+        Ok(errors) => unsafe { BACKGROUND_ERRORS = Some(errors.into()) },
+        Err(e) => ic_cdk::println!("Failed to restore background errors: {}", e),
+    }
+}
+
+/// Register the Python error handler, or unregister it with `None`.
+pub fn set_error_handler(handler: Option<PyObjectRef>) {
+    unsafe { ERROR_HANDLER = handler };
+}
@@ -0,0 +1,142 @@
+//! Bounded-wait (best-effort response) inter-canister calls.
+//!
+//! ic-cdk 0.13 only makes guaranteed-response calls, whose callback may be
+//! held back indefinitely by a stuck callee. A bounded-wait call is the same
+//! `call_new` … `call_perform` sequence with `ic0.call_with_best_effort_response`
+//! in between, so this module carries a copy of ic-cdk's call future that
+//! adds that step. Once the timeout expires (or the system drops the
+//! response) the call is rejected with `SYS_UNKNOWN`, which ic-cdk's
+//! `RejectionCode` reports as `Unknown`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock, Weak};
+use std::task::{Context, Poll, Waker};
+
+use ic_cdk::api::call::{CallResult, RejectionCode};
+
+#[link(wasm_import_module = "ic0")]
+extern "C" {
+    fn call_with_best_effort_response(timeout_seconds: i32);
+}
+
+struct CallState {
+    result: Option<CallResult<Vec<u8>>>,
+    waker: Option<Waker>,
+    callee: candid::Principal,
+    method: String,
+    args: Vec<u8>,
+    payment: u128,
+    timeout_seconds: u32,
+}
+
+/// Signature of the callbacks handed to `ic0.call_new` / `ic0.call_on_cleanup`.
+type StateCallback = unsafe extern "C" fn(*const RwLock<CallState>);
+
+struct BoundedWaitCall {
+    state: Arc<RwLock<CallState>>,
+}
+
+/// Call `method` on `callee` with `args` and `payment` cycles, giving up on
+/// the response after `timeout_seconds` (the system caps this at its own
+/// maximum). A call that times out resolves to `RejectionCode::Unknown`.
+pub fn call_raw128_bounded(
+    callee: candid::Principal,
+    method: &str,
+    args: Vec<u8>,
+    payment: u128,
+    timeout_seconds: u32,
+) -> impl Future<Output = CallResult<Vec<u8>>> {
+    BoundedWaitCall {
+        state: Arc::new(RwLock::new(CallState {
+            result: None,
+            waker: None,
+            callee,
+            method: method.to_string(),
+            args,
+            payment,
+            timeout_seconds,
+        })),
+    }
+}
+
+impl Future for BoundedWaitCall {
+    type Output = CallResult<Vec<u8>>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_ref = Pin::into_inner(self);
+        let mut state = self_ref.state.write().unwrap();
+
+        if let Some(result) = state.result.take() {
+            return Poll::Ready(result);
+        }
+        if state.waker.is_none() {
+            let callee = state.callee.as_slice();
+            let method = &state.method;
+            let args = &state.args;
+            let state_ptr = Weak::into_raw(Arc::downgrade(&self_ref.state));
+            // Same contract as ic-cdk's CallFuture: `callback` and `cleanup`
+            // receive a pointer from Weak::into_raw, which fails to upgrade if
+            // this future has been dropped by the time the response arrives.
+            let callback_index = callback as StateCallback as usize as i32;
+            let err_code = unsafe {
+                ic0::call_new(
+                    callee.as_ptr() as i32,
+                    callee.len() as i32,
+                    method.as_ptr() as i32,
+                    method.len() as i32,
+                    callback_index,
+                    state_ptr as i32,
+                    callback_index,
+                    state_ptr as i32,
+                );
+                call_with_best_effort_response(state.timeout_seconds.min(i32::MAX as u32) as i32);
+                ic0::call_data_append(args.as_ptr() as i32, args.len() as i32);
+                if state.payment > 0 {
+                    ic0::call_cycles_add128((state.payment >> 64) as i64, state.payment as u64 as i64);
+                }
+                ic0::call_on_cleanup(cleanup as StateCallback as usize as i32, state_ptr as i32);
+                ic0::call_perform()
+            };
+            if err_code != 0 {
+                return Poll::Ready(Err((
+                    RejectionCode::from(err_code),
+                    "Couldn't send message".to_string(),
+                )));
+            }
+        }
+        state.waker = Some(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Reply and reject callback: store the result and wake the task.
+unsafe extern "C" fn callback(state_ptr: *const RwLock<CallState>) {
+    let state = unsafe { Weak::from_raw(state_ptr) };
+    if let Some(state) = state.upgrade() {
+        state.write().unwrap().result = Some(match ic_cdk::api::call::reject_code() {
+            RejectionCode::NoError => Ok(ic_cdk::api::call::arg_data_raw()),
+            code => Err((code, ic_cdk::api::call::reject_message())),
+        });
+        let waker = state.write().unwrap().waker.take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Called when `callback` trapped: drop the task without running it again,
+/// as ic-cdk does for its own calls.
+unsafe extern "C" fn cleanup(state_ptr: *const RwLock<CallState>) {
+    let state = unsafe { Weak::from_raw(state_ptr) };
+    if let Some(state) = state.upgrade() {
+        state.write().unwrap().result = Some(Err((RejectionCode::NoError, "cleanup".to_string())));
+        let waker = state.write().unwrap().waker.take();
+        if let Some(waker) = waker {
+            ic_cdk_executor::CLEANUP.store(true, Ordering::Relaxed);
+            waker.wake();
+            ic_cdk_executor::CLEANUP.store(false, Ordering::Relaxed);
+        }
+    }
+}
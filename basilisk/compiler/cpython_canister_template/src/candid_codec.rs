@@ -0,0 +1,1225 @@
+//! Direct Candid wire codec.
+//!
+//! Reads Candid argument bytes straight into Python objects and writes Python
+//! return values straight to Candid bytes, driven by the compiled types from
+//! `type_table`. This skips the intermediate `candid::IDLValue` tree, which for
+//! large `vec record { ... }` and `blob` payloads costs more than the Python
+//! conversion itself.
+//!
+//! The codec only handles the common, well-formed cases. Whenever it meets
+//! something it does not cover (a type mismatch, a malformed message, an
+//! unusual Python value) it gives up and returns `None`; callers then retry
+//! through the `IDLValue` path, which produces the detailed error messages.
+
+use crate::method_dispatch::{
+    field_name_to_label, idl_value_to_python, idl_value_to_python_typed, is_tuple_record, label_to_py_key,
+    python_to_idl_value_inner,
+};
+use crate::type_table::{candid_name_to_label, unroll};
+use crate::wasm_data::DecodingQuotas;
+use basilisk_cpython::{ffi, PyObjectRef, TryFromPyObject, TryIntoPyObject};
+use candid::types::internal::TypeInner;
+use candid::types::{Field, FuncMode, Label, Type};
+use candid::IDLValue;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const MAGIC: &[u8] = b"DIDL";
+
+// Type opcodes (SLEB128, negative).
+const NULL: i64 = -1;
+const BOOL: i64 = -2;
+const NAT: i64 = -3;
+const INT: i64 = -4;
+const NAT8: i64 = -5;
+const NAT16: i64 = -6;
+const NAT32: i64 = -7;
+const NAT64: i64 = -8;
+const INT8: i64 = -9;
+const INT16: i64 = -10;
+const INT32: i64 = -11;
+const INT64: i64 = -12;
+const FLOAT32: i64 = -13;
+const FLOAT64: i64 = -14;
+const TEXT: i64 = -15;
+const RESERVED: i64 = -16;
+const EMPTY: i64 = -17;
+const OPT: i64 = -18;
+const VEC: i64 = -19;
+const RECORD: i64 = -20;
+const VARIANT: i64 = -21;
+const FUNC: i64 = -22;
+const SERVICE: i64 = -23;
+const PRINCIPAL: i64 = -24;
+
+/// Nesting limit for decoding and encoding; deeper values go through the `IDLValue` path.
+const MAX_DEPTH: usize = 256;
+
+/// The user's `Record` / `Variant` subclasses, keyed by the field list of the
+/// compiled named type each one declares (set at init by `bind_record_classes`).
+static mut RECORD_CLASSES: Option<HashMap<*const Field, PyObjectRef>> = None;
+
+/// Replace the class bindings used by `new_record_object`.
+pub fn set_record_classes(classes: HashMap<*const Field, PyObjectRef>) {
+    unsafe {
+        RECORD_CLASSES = Some(classes);
+    }
+}
+
+/// An empty mapping to fill with a decoded record or variant: an instance of
+/// the Python class declared for that type, or a plain `dict`.
+pub fn new_record_object(fields: &[Field]) -> Option<PyObjectRef> {
+    if let Some(class) = unsafe { RECORD_CLASSES.as_ref() }.and_then(|c| c.get(&fields.as_ptr())) {
+        if let Ok(obj) = class.call_no_args() {
+            return Some(obj);
+        }
+    }
+    basilisk_cpython::PyDict::new().ok().map(|d| d.into_object())
+}
+
+/// The shim's `Opt` class (set at init by `bind_opt_class`).
+static mut OPT_CLASS: Option<PyObjectRef> = None;
+
+/// Whether `opt` values decode to `Opt` wrappers for methods that don't choose.
+static mut PRESERVE_OPT_DEFAULT: bool = false;
+
+pub fn set_opt_class(class: PyObjectRef) {
+    unsafe {
+        OPT_CLASS = Some(class);
+    }
+}
+
+/// Set the canister-wide opt decoding mode (`ic.preserve_opt_nesting`).
+pub fn set_preserve_opt_default(preserve: bool) {
+    unsafe {
+        PRESERVE_OPT_DEFAULT = preserve;
+    }
+}
+
+pub fn preserve_opt_default() -> bool {
+    unsafe { PRESERVE_OPT_DEFAULT }
+}
+
+/// Wrap a decoded `opt` payload as `Opt(value)`. Falls back to the bare value
+/// if the class is not bound.
+pub fn wrap_opt(value: PyObjectRef) -> Option<PyObjectRef> {
+    let Some(class) = (unsafe { OPT_CLASS.as_ref() }) else {
+        return Some(value);
+    };
+    let args = basilisk_cpython::PyTuple::new(vec![value]).ok()?;
+    class.call(&args.into_object(), None).ok()
+}
+
+/// The payload of an `Opt(value)` instance, or None for any other object.
+pub fn opt_payload(obj: &PyObjectRef) -> Option<PyObjectRef> {
+    let class = unsafe { OPT_CLASS.as_ref() }?;
+    let is_opt = unsafe {
+        let ty = ffi::PyObject_Type(obj.as_ptr());
+        let same = ty == class.as_ptr();
+        if !ty.is_null() {
+            ffi::Py_DecRef(ty);
+        }
+        same
+    };
+    if is_opt {
+        obj.get_attr("value").ok()
+    } else {
+        None
+    }
+}
+
+/// Whether `Opt(None)` encodes as `opt null` against an `opt` of this type.
+/// Only null-like payload types can hold it; elsewhere `Opt(None)` (and
+/// `Opt()`) keeps meaning an absent value.
+pub fn opt_holds_none(inner: &Type) -> bool {
+    allows_missing(inner)
+}
+
+/// (signed, width in bits; 0 = unbounded) for the numeric opcodes.
+fn wire_number_kind(code: i64) -> Option<(bool, u32)> {
+    Some(match code {
+        NAT => (false, 0),
+        NAT8 => (false, 8),
+        NAT16 => (false, 16),
+        NAT32 => (false, 32),
+        NAT64 => (false, 64),
+        INT => (true, 0),
+        INT8 => (true, 8),
+        INT16 => (true, 16),
+        INT32 => (true, 32),
+        INT64 => (true, 64),
+        _ => return None,
+    })
+}
+
+fn number_kind(ty: &TypeInner) -> Option<(bool, u32)> {
+    Some(match ty {
+        TypeInner::Nat => (false, 0),
+        TypeInner::Nat8 => (false, 8),
+        TypeInner::Nat16 => (false, 16),
+        TypeInner::Nat32 => (false, 32),
+        TypeInner::Nat64 => (false, 64),
+        TypeInner::Int => (true, 0),
+        TypeInner::Int8 => (true, 8),
+        TypeInner::Int16 => (true, 16),
+        TypeInner::Int32 => (true, 32),
+        TypeInner::Int64 => (true, 64),
+        _ => return None,
+    })
+}
+
+/// Lossless numeric widening, same rules as `subtyping::widen_number`.
+fn number_fits(wire: (bool, u32), expected: (bool, u32)) -> bool {
+    let ((signed, width), (target_signed, target_width)) = (wire, expected);
+    if target_width == 0 {
+        return target_signed || !signed;
+    }
+    if width == 0 {
+        return false;
+    }
+    match (signed, target_signed) {
+        (false, false) | (true, true) => width <= target_width,
+        (false, true) => width < target_width,
+        (true, false) => false,
+    }
+}
+
+// ─── Decoding ───────────────────────────────────────────────────────────────
+
+/// A compound entry of the message's type table. Fields and cases keep the
+/// wire order (sorted by label id), holding the id and the type reference.
+enum WireType {
+    Opt(i64),
+    Vec(i64),
+    Record(Vec<(u32, i64)>),
+    Variant(Vec<(u32, i64)>),
+    Func,
+    Service,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    table: Rc<[WireType]>,
+    /// Python dict keys per record/variant type, keyed by its field slice.
+    keys: HashMap<*const Field, Vec<PyObjectRef>>,
+    /// Decode present `opt` values as `Opt(value)` instead of the bare value.
+    preserve_opt: bool,
+    /// What is left of each decoding quota.
+    quotas: DecodingQuotas,
+    /// The quota that ran out, if any.
+    exhausted: Option<&'static str>,
+}
+
+/// Why `decode_args` produced no values.
+pub enum DecodeFailure {
+    /// The codec does not handle this message; retry through the `IDLValue` path.
+    Declined,
+    /// A decoding quota ran out (named here); the message must be rejected.
+    QuotaExceeded(&'static str),
+    /// The message is invalid Candid in a way the `IDLValue` path would not
+    /// catch either (described here); it must be rejected.
+    Malformed(&'static str),
+}
+
+/// Take `cost` from a remaining quota; false if there is not enough left.
+fn spend(left: &mut Option<usize>, cost: usize) -> bool {
+    match left {
+        Some(n) if *n < cost => {
+            *n = 0;
+            false
+        }
+        Some(n) => {
+            *n -= cost;
+            true
+        }
+        None => true,
+    }
+}
+
+/// Decode a Candid argument message into one Python object per expected type.
+/// Missing trailing arguments of opt/null/reserved type become None and extra
+/// arguments are ignored, as in `subtyping`. With `preserve_opt`, present
+/// `opt` values become `Opt(value)` so nesting survives.
+///
+/// Decoding cost is charged against `quotas` roughly as candid's decoder does:
+/// one unit per value, plus the length of texts, blobs and big numbers, three
+/// units per vector element and four per header byte. Skipped values charge
+/// the skipping quota the same way, and every null, reserved or empty record
+/// counts against the zero-sized quota.
+pub fn decode_args(
+    bytes: &[u8],
+    types: &[Type],
+    preserve_opt: bool,
+    quotas: &DecodingQuotas,
+) -> Result<Vec<PyObjectRef>, DecodeFailure> {
+    let Some((mut decoder, wire_args)) = Decoder::new(bytes, *quotas) else {
+        return Err(DecodeFailure::Declined);
+    };
+    if !decoder.field_ids_sorted() {
+        return Err(DecodeFailure::Malformed("record or variant field ids are not strictly increasing"));
+    }
+    decoder.preserve_opt = preserve_opt;
+    let header_cost = decoder.pos.saturating_mul(4);
+    let result = decoder.charge(header_cost).and_then(|()| decoder.args(&wire_args, types));
+    if let Some(quota) = decoder.exhausted {
+        unsafe { ffi::PyErr_Clear() };
+        return Err(DecodeFailure::QuotaExceeded(quota));
+    }
+    result.ok_or_else(|| {
+        unsafe { ffi::PyErr_Clear() };
+        DecodeFailure::Declined
+    })
+}
+
+/// Check a Candid message against the zero-sized quota alone, by walking its
+/// values without building them. The `IDLValue` path can only count zero-sized
+/// values once candid has decoded (and allocated) all of them, so it runs this
+/// first to refuse, say, a huge `vec null` up front.
+///
+/// Returns `Declined` for a message this decoder can't walk; candid then
+/// reports what is wrong with it.
+pub fn check_zero_sized(bytes: &[u8], limit: usize) -> Result<(), DecodeFailure> {
+    let quotas = DecodingQuotas { zero_sized: Some(limit), ..Default::default() };
+    let Some((mut decoder, wire_args)) = Decoder::new(bytes, quotas) else {
+        return Err(DecodeFailure::Declined);
+    };
+    let walked = wire_args.iter().try_for_each(|wire| decoder.skip(*wire, 0));
+    if let Some(quota) = decoder.exhausted {
+        return Err(DecodeFailure::QuotaExceeded(quota));
+    }
+    walked.ok_or(DecodeFailure::Declined)
+}
+
+impl<'a> Decoder<'a> {
+    /// Parse the header (magic, type table, argument types).
+    fn new(bytes: &'a [u8], quotas: DecodingQuotas) -> Option<(Self, Vec<i64>)> {
+        let mut decoder = Decoder {
+            bytes,
+            pos: 0,
+            table: Rc::from(Vec::new()),
+            keys: HashMap::new(),
+            preserve_opt: false,
+            quotas,
+            exhausted: None,
+        };
+        if decoder.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+        let table_len = decoder.uleb()? as usize;
+        if table_len > bytes.len() {
+            return None;
+        }
+        let mut table = Vec::with_capacity(table_len);
+        for _ in 0..table_len {
+            table.push(decoder.wire_type()?);
+        }
+        let arg_count = decoder.uleb()? as usize;
+        if arg_count > bytes.len() {
+            return None;
+        }
+        let wire_args = (0..arg_count).map(|_| decoder.sleb()).collect::<Option<Vec<_>>>()?;
+
+        let valid_ref = |r: i64| (PRINCIPAL..0).contains(&r) || (r >= 0 && (r as usize) < table_len);
+        let refs_ok = table.iter().all(|entry| match entry {
+            WireType::Opt(r) | WireType::Vec(r) => valid_ref(*r),
+            WireType::Record(fields) | WireType::Variant(fields) => fields.iter().all(|(_, r)| valid_ref(*r)),
+            WireType::Func | WireType::Service => true,
+        });
+        if !refs_ok || !wire_args.iter().all(|r| valid_ref(*r)) {
+            return None;
+        }
+        decoder.table = Rc::from(table);
+        Some((decoder, wire_args))
+    }
+
+    /// Whether every record and variant in the type table lists its field
+    /// ids in strictly increasing order, as Candid requires. Candid's own
+    /// decoder refuses other tables; decoding one here would let a repeated
+    /// field overwrite an earlier one.
+    fn field_ids_sorted(&self) -> bool {
+        self.table.iter().all(|entry| match entry {
+            WireType::Record(fields) | WireType::Variant(fields) => fields.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            _ => true,
+        })
+    }
+
+    fn wire_type(&mut self) -> Option<WireType> {
+        match self.sleb()? {
+            OPT => Some(WireType::Opt(self.sleb()?)),
+            VEC => Some(WireType::Vec(self.sleb()?)),
+            op @ (RECORD | VARIANT) => {
+                let len = self.uleb()? as usize;
+                let mut fields = Vec::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    let id = u32::try_from(self.uleb()?).ok()?;
+                    fields.push((id, self.sleb()?));
+                }
+                Some(if op == RECORD { WireType::Record(fields) } else { WireType::Variant(fields) })
+            }
+            FUNC => {
+                for _ in 0..2 {
+                    let count = self.uleb()?;
+                    for _ in 0..count {
+                        self.sleb()?;
+                    }
+                }
+                let annotations = self.uleb()? as usize;
+                self.take(annotations)?;
+                Some(WireType::Func)
+            }
+            SERVICE => {
+                let count = self.uleb()?;
+                for _ in 0..count {
+                    let name_len = self.uleb()? as usize;
+                    self.take(name_len)?;
+                    self.sleb()?;
+                }
+                Some(WireType::Service)
+            }
+            _ => None,
+        }
+    }
+
+    fn args(&mut self, wire_args: &[i64], types: &[Type]) -> Option<Vec<PyObjectRef>> {
+        let mut py_args = Vec::with_capacity(types.len());
+        for (i, ty) in types.iter().enumerate() {
+            let py_arg = match wire_args.get(i) {
+                Some(wire) => self.value(*wire, ty, 0)?,
+                None if allows_missing(ty) => PyObjectRef::none(),
+                None => return None,
+            };
+            py_args.push(py_arg);
+        }
+        for wire in wire_args.iter().skip(types.len()) {
+            self.skip(*wire, 0)?;
+        }
+        if self.pos != self.bytes.len() {
+            return None;
+        }
+        Some(py_args)
+    }
+
+    // --- Input primitives ---
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let mut rest = &self.bytes[self.pos..];
+        let value = candid::types::leb128::decode_nat(&mut rest).ok()?;
+        self.pos = self.bytes.len() - rest.len();
+        u64::try_from(value).ok()
+    }
+
+    fn sleb(&mut self) -> Option<i64> {
+        let mut rest = &self.bytes[self.pos..];
+        let value = candid::types::leb128::decode_int(&mut rest).ok()?;
+        self.pos = self.bytes.len() - rest.len();
+        i64::try_from(value).ok()
+    }
+
+    fn fixed<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn text(&mut self) -> Option<&'a str> {
+        let len = self.uleb()? as usize;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+
+    fn principal(&mut self) -> Option<candid::Principal> {
+        if self.byte()? != 1 {
+            return None;
+        }
+        let len = self.uleb()? as usize;
+        candid::Principal::try_from_slice(self.take(len)?).ok()
+    }
+
+    fn entry(&self, wire: i64) -> Option<&WireType> {
+        usize::try_from(wire).ok().and_then(|i| self.table.get(i))
+    }
+
+    // --- Quotas ---
+
+    fn charge(&mut self, cost: usize) -> Option<()> {
+        let ok = spend(&mut self.quotas.decoding, cost);
+        self.check(ok, "decoding")
+    }
+
+    fn charge_skipped(&mut self, cost: usize) -> Option<()> {
+        let ok = spend(&mut self.quotas.skipping, cost);
+        self.check(ok, "skipping")
+    }
+
+    fn charge_zero_sized(&mut self) -> Option<()> {
+        let ok = spend(&mut self.quotas.zero_sized, 1);
+        self.check(ok, "zero-sized values")
+    }
+
+    fn check(&mut self, ok: bool, quota: &'static str) -> Option<()> {
+        if ok {
+            Some(())
+        } else {
+            self.exhausted = Some(quota);
+            None
+        }
+    }
+
+    // --- Values ---
+
+    /// Decode one value of wire type `wire` as the expected type.
+    fn value(&mut self, wire: i64, expected: &Type, depth: usize) -> Option<PyObjectRef> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        if let TypeInner::Var(_) = expected.as_ref() {
+            return self.value(wire, &unroll(expected)?, depth);
+        }
+        self.charge(1)?;
+        let expected_inner = expected.as_ref();
+        if let Some(target) = number_kind(expected_inner) {
+            return if number_fits(wire_number_kind(wire)?, target) {
+                self.number(wire)
+            } else {
+                None
+            };
+        }
+        match expected_inner {
+            TypeInner::Reserved => {
+                self.skip(wire, depth)?;
+                Some(PyObjectRef::none())
+            }
+            TypeInner::Opt(inner) => self.opt(wire, inner, depth),
+            TypeInner::Null if wire == NULL => {
+                self.charge_zero_sized()?;
+                Some(PyObjectRef::none())
+            }
+            TypeInner::Bool if wire == BOOL => match self.byte()? {
+                0 => Some(PyObjectRef::from_bool(false)),
+                1 => Some(PyObjectRef::from_bool(true)),
+                _ => None,
+            },
+            TypeInner::Text if wire == TEXT => {
+                let text = self.text()?;
+                self.charge(text.len())?;
+                PyObjectRef::from_str(text).ok()
+            }
+            TypeInner::Float32 if wire == FLOAT32 => {
+                PyObjectRef::from_f64(f32::from_le_bytes(self.fixed()?) as f64).ok()
+            }
+            TypeInner::Float64 if wire == FLOAT32 => {
+                PyObjectRef::from_f64(f32::from_le_bytes(self.fixed()?) as f64).ok()
+            }
+            TypeInner::Float64 if wire == FLOAT64 => PyObjectRef::from_f64(f64::from_le_bytes(self.fixed()?)).ok(),
+            TypeInner::Principal if wire == PRINCIPAL => {
+                let principal = self.principal()?;
+                idl_value_to_python(&IDLValue::Principal(principal)).ok()
+            }
+            TypeInner::Vec(inner) => self.vec(wire, inner, depth),
+            TypeInner::Record(fields) => self.record(wire, fields, depth),
+            TypeInner::Variant(cases) => self.variant(wire, cases, depth),
+            TypeInner::Func(_) if matches!(self.entry(wire)?, WireType::Func) => {
+                if self.byte()? != 1 {
+                    return None;
+                }
+                let principal = self.principal()?;
+                let method = self.text()?.to_string();
+                idl_value_to_python_typed(&IDLValue::Func(principal, method), Some(expected), false).ok()
+            }
+            TypeInner::Service(_) if matches!(self.entry(wire)?, WireType::Service) => {
+                let principal = self.principal()?;
+                idl_value_to_python_typed(&IDLValue::Service(principal), Some(expected), false).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Decode a value into an expected `opt`: absent, null or non-matching
+    /// values become None, following Candid's opt rule.
+    fn opt(&mut self, wire: i64, inner: &Type, depth: usize) -> Option<PyObjectRef> {
+        if wire == NULL || wire == RESERVED {
+            return Some(PyObjectRef::none());
+        }
+        if let Some(WireType::Opt(wire_inner)) = self.entry(wire) {
+            let wire_inner = *wire_inner;
+            return match self.byte()? {
+                0 => Some(PyObjectRef::none()),
+                1 => self.some(wire_inner, inner, depth + 1),
+                _ => None,
+            };
+        }
+        if matches!(unroll(inner)?.as_ref(), TypeInner::Null | TypeInner::Reserved | TypeInner::Opt(_)) {
+            self.skip(wire, depth)?;
+            return Some(PyObjectRef::none());
+        }
+        self.some(wire, inner, depth + 1)
+    }
+
+    /// Decode the payload of a present `opt`, wrapped as `Opt(value)` when
+    /// nesting is preserved. A payload that does not fit makes the whole
+    /// `opt` None.
+    fn some(&mut self, wire: i64, inner: &Type, depth: usize) -> Option<PyObjectRef> {
+        if !self.preserve_opt {
+            return self.value_or_none(wire, inner, depth);
+        }
+        let start = self.pos;
+        if let Some(value) = self.value(wire, inner, depth) {
+            return wrap_opt(value);
+        }
+        unsafe { ffi::PyErr_Clear() };
+        self.pos = start;
+        self.skip(wire, depth)?;
+        Some(PyObjectRef::none())
+    }
+
+    /// Decode a value, or skip it and produce None if it does not fit.
+    fn value_or_none(&mut self, wire: i64, expected: &Type, depth: usize) -> Option<PyObjectRef> {
+        let start = self.pos;
+        if let Some(value) = self.value(wire, expected, depth) {
+            return Some(value);
+        }
+        unsafe { ffi::PyErr_Clear() };
+        self.pos = start;
+        self.skip(wire, depth)?;
+        Some(PyObjectRef::none())
+    }
+
+    fn number(&mut self, wire: i64) -> Option<PyObjectRef> {
+        let value = match wire {
+            NAT => {
+                let start = self.pos;
+                match self.uleb() {
+                    Some(n) => PyObjectRef::from_u64(n),
+                    None => {
+                        self.pos = start;
+                        let mut rest = &self.bytes[self.pos..];
+                        let n = candid::Nat::decode(&mut rest).ok()?;
+                        let end = self.bytes.len() - rest.len();
+                        self.charge(end - self.pos)?;
+                        self.pos = end;
+                        return (&n.0).try_into_py_object().ok();
+                    }
+                }
+            }
+            INT => {
+                let start = self.pos;
+                match self.sleb() {
+                    Some(n) => PyObjectRef::from_i64(n),
+                    None => {
+                        self.pos = start;
+                        let mut rest = &self.bytes[self.pos..];
+                        let n = candid::Int::decode(&mut rest).ok()?;
+                        let end = self.bytes.len() - rest.len();
+                        self.charge(end - self.pos)?;
+                        self.pos = end;
+                        return (&n.0).try_into_py_object().ok();
+                    }
+                }
+            }
+            NAT8 => PyObjectRef::from_u64(self.byte()? as u64),
+            NAT16 => PyObjectRef::from_u64(u16::from_le_bytes(self.fixed()?) as u64),
+            NAT32 => PyObjectRef::from_u64(u32::from_le_bytes(self.fixed()?) as u64),
+            NAT64 => PyObjectRef::from_u64(u64::from_le_bytes(self.fixed()?)),
+            INT8 => PyObjectRef::from_i64(self.byte()? as i8 as i64),
+            INT16 => PyObjectRef::from_i64(i16::from_le_bytes(self.fixed()?) as i64),
+            INT32 => PyObjectRef::from_i64(i32::from_le_bytes(self.fixed()?) as i64),
+            INT64 => PyObjectRef::from_i64(i64::from_le_bytes(self.fixed()?)),
+            _ => return None,
+        };
+        value.ok()
+    }
+
+    fn vec(&mut self, wire: i64, inner: &Type, depth: usize) -> Option<PyObjectRef> {
+        let elem = match self.entry(wire)? {
+            WireType::Vec(elem) => *elem,
+            _ => return None,
+        };
+        let len = self.uleb()? as usize;
+        if elem == NAT8 && matches!(inner.as_ref(), TypeInner::Nat8) {
+            self.charge(len)?;
+            return PyObjectRef::from_bytes(self.take(len)?).ok();
+        }
+        // Every element takes at least one byte except zero-sized ones;
+        // leave long runs of those to the IDLValue path and its quotas.
+        if len > self.remaining() {
+            return None;
+        }
+        self.charge(len.saturating_mul(3))?;
+        unsafe {
+            let list = PyObjectRef::from_owned(ffi::PyList_New(len as ffi::Py_ssize_t))?;
+            for i in 0..len {
+                let item = self.value(elem, inner, depth + 1)?;
+                ffi::PyList_SetItem(list.as_ptr(), i as ffi::Py_ssize_t, item.into_ptr());
+            }
+            Some(list)
+        }
+    }
+
+    fn record(&mut self, wire: i64, fields: &[Field], depth: usize) -> Option<PyObjectRef> {
+        let table = self.table.clone();
+        let wire_fields = match table.get(usize::try_from(wire).ok()?)? {
+            WireType::Record(wire_fields) => wire_fields,
+            _ => return None,
+        };
+        if wire_fields.is_empty() {
+            self.charge_zero_sized()?;
+        }
+
+        let mut values: Vec<Option<PyObjectRef>> = (0..fields.len()).map(|_| None).collect();
+        for (id, wire_ty) in wire_fields {
+            match fields.binary_search_by_key(id, |f| f.id.get_id()) {
+                Ok(i) => values[i] = Some(self.value(*wire_ty, &fields[i].ty, depth + 1)?),
+                Err(_) => self.skip(*wire_ty, depth + 1)?,
+            }
+        }
+        let values = values
+            .into_iter()
+            .zip(fields)
+            .map(|(value, field)| match value {
+                Some(value) => Some(value),
+                None if allows_missing(&field.ty) => Some(PyObjectRef::none()),
+                None => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if is_tuple_record(fields) {
+            return basilisk_cpython::PyTuple::new(values).ok().map(|t| t.into_object());
+        }
+        let record = new_record_object(fields)?;
+        let keys = self.keys(fields)?;
+        for (key, value) in keys.iter().zip(&values) {
+            record.set_item(key, value).ok()?;
+        }
+        Some(record)
+    }
+
+    fn variant(&mut self, wire: i64, cases: &[Field], depth: usize) -> Option<PyObjectRef> {
+        let table = self.table.clone();
+        let wire_cases = match table.get(usize::try_from(wire).ok()?)? {
+            WireType::Variant(wire_cases) => wire_cases,
+            _ => return None,
+        };
+        let (id, wire_ty) = *wire_cases.get(self.uleb()? as usize)?;
+        let case_index = cases.binary_search_by_key(&id, |c| c.id.get_id()).ok()?;
+        let value = self.value(wire_ty, &cases[case_index].ty, depth + 1)?;
+        let key = self.keys(cases)?[case_index].clone();
+        let variant = new_record_object(cases)?;
+        variant.set_item(&key, &value).ok()?;
+        Some(variant)
+    }
+
+    /// Python dict keys for a record's fields or a variant's cases.
+    fn keys(&mut self, fields: &[Field]) -> Option<&Vec<PyObjectRef>> {
+        match self.keys.entry(fields.as_ptr()) {
+            Entry::Occupied(entry) => Some(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let keys = fields
+                    .iter()
+                    .map(|f| PyObjectRef::from_str(&label_to_py_key(&f.id)).ok())
+                    .collect::<Option<Vec<_>>>()?;
+                Some(entry.insert(keys))
+            }
+        }
+    }
+
+    /// Consume one value of wire type `wire` without building anything.
+    fn skip(&mut self, wire: i64, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.charge_skipped(1)?;
+        match wire {
+            NULL | RESERVED => self.charge_zero_sized()?,
+            BOOL | NAT8 | INT8 => {
+                self.take(1)?;
+            }
+            NAT16 | INT16 => {
+                self.take(2)?;
+            }
+            NAT32 | INT32 | FLOAT32 => {
+                self.take(4)?;
+            }
+            NAT64 | INT64 | FLOAT64 => {
+                self.take(8)?;
+            }
+            NAT | INT => {
+                // LEB128 of any length: skip continuation bytes
+                while self.byte()? & 0x80 != 0 {}
+            }
+            TEXT => {
+                let len = self.uleb()? as usize;
+                self.charge_skipped(len)?;
+                self.take(len)?;
+            }
+            PRINCIPAL => {
+                self.principal()?;
+            }
+            EMPTY => return None,
+            _ => {
+                let table = self.table.clone();
+                match table.get(usize::try_from(wire).ok()?)? {
+                    WireType::Opt(inner) => match self.byte()? {
+                        0 => {}
+                        1 => self.skip(*inner, depth + 1)?,
+                        _ => return None,
+                    },
+                    WireType::Vec(elem) => {
+                        let len = self.uleb()? as usize;
+                        if *elem == NAT8 {
+                            self.charge_skipped(len)?;
+                            self.take(len)?;
+                        } else {
+                            // Elements that take no bytes are zero-sized, so a
+                            // zero-sized quota bounds the loop instead
+                            if len > self.remaining() && self.quotas.zero_sized.is_none() {
+                                return None;
+                            }
+                            for _ in 0..len {
+                                self.skip(*elem, depth + 1)?;
+                            }
+                        }
+                    }
+                    WireType::Record(fields) => {
+                        if fields.is_empty() {
+                            self.charge_zero_sized()?;
+                        }
+                        for (_, field) in fields {
+                            self.skip(*field, depth + 1)?;
+                        }
+                    }
+                    WireType::Variant(cases) => {
+                        let index = self.uleb()? as usize;
+                        self.skip(cases.get(index)?.1, depth + 1)?;
+                    }
+                    WireType::Func => {
+                        if self.byte()? != 1 {
+                            return None;
+                        }
+                        self.principal()?;
+                        self.text()?;
+                    }
+                    WireType::Service => {
+                        self.principal()?;
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Whether an absent argument or record field of this type decodes to None.
+fn allows_missing(ty: &Type) -> bool {
+    matches!(
+        unroll(ty).as_ref().map(|t| t.as_ref()),
+        Some(TypeInner::Opt(_) | TypeInner::Null | TypeInner::Reserved)
+    )
+}
+
+// ─── Encoding ───────────────────────────────────────────────────────────────
+
+/// Encoded message headers (magic, type table, argument types) by argument types.
+static mut HEADER_CACHE: Option<HashMap<Vec<Type>, Vec<u8>>> = None;
+
+/// Encode Python values as a Candid message with the given argument types.
+/// Returns None if a value does not fit its type (the caller then goes through
+/// the `IDLValue` path for a proper error).
+pub fn encode_args(values: &[PyObjectRef], types: &[Type]) -> Option<Vec<u8>> {
+    if values.len() != types.len() {
+        return None;
+    }
+    let cache = unsafe { HEADER_CACHE.get_or_insert_with(HashMap::new) };
+    let header = match cache.get(types) {
+        Some(header) => header,
+        None => {
+            let header = encode_header(types)?;
+            cache.entry(types.to_vec()).or_insert(header)
+        }
+    };
+
+    let mut encoder = Encoder {
+        out: header.clone(),
+        depth: 0,
+        keys: HashMap::new(),
+    };
+    for (value, ty) in values.iter().zip(types) {
+        if encoder.value(value, ty).is_none() {
+            unsafe { ffi::PyErr_Clear() };
+            return None;
+        }
+    }
+    Some(encoder.out)
+}
+
+fn encode_header(types: &[Type]) -> Option<Vec<u8>> {
+    let mut builder = TypeTableBuilder {
+        entries: Vec::new(),
+        indices: HashMap::new(),
+    };
+    let refs = types.iter().map(|ty| builder.type_ref(ty)).collect::<Option<Vec<_>>>()?;
+
+    let mut out = MAGIC.to_vec();
+    write_uleb(&mut out, builder.entries.len() as u64);
+    for entry in &builder.entries {
+        out.extend_from_slice(entry);
+    }
+    write_uleb(&mut out, refs.len() as u64);
+    for r in refs {
+        write_sleb(&mut out, r);
+    }
+    Some(out)
+}
+
+fn write_uleb(out: &mut Vec<u8>, value: u64) {
+    let _ = candid::types::leb128::encode_nat(out, value as u128);
+}
+
+fn write_sleb(out: &mut Vec<u8>, value: i64) {
+    let _ = candid::types::leb128::encode_int(out, value as i128);
+}
+
+/// Builds the type table of a message; compound types get one entry each.
+struct TypeTableBuilder {
+    entries: Vec<Vec<u8>>,
+    indices: HashMap<Type, i64>,
+}
+
+impl TypeTableBuilder {
+    fn type_ref(&mut self, ty: &Type) -> Option<i64> {
+        if let TypeInner::Var(_) = ty.as_ref() {
+            return self.type_ref(&unroll(ty)?);
+        }
+        let primitive = match ty.as_ref() {
+            TypeInner::Null => NULL,
+            TypeInner::Bool => BOOL,
+            TypeInner::Nat => NAT,
+            TypeInner::Int => INT,
+            TypeInner::Nat8 => NAT8,
+            TypeInner::Nat16 => NAT16,
+            TypeInner::Nat32 => NAT32,
+            TypeInner::Nat64 => NAT64,
+            TypeInner::Int8 => INT8,
+            TypeInner::Int16 => INT16,
+            TypeInner::Int32 => INT32,
+            TypeInner::Int64 => INT64,
+            TypeInner::Float32 => FLOAT32,
+            TypeInner::Float64 => FLOAT64,
+            TypeInner::Text => TEXT,
+            TypeInner::Reserved => RESERVED,
+            TypeInner::Empty => EMPTY,
+            TypeInner::Principal => PRINCIPAL,
+            _ => 0,
+        };
+        if primitive != 0 {
+            return Some(primitive);
+        }
+        if let Some(index) = self.indices.get(ty) {
+            return Some(*index);
+        }
+
+        // Reserve the slot first so self-references resolve to it.
+        let index = self.entries.len() as i64;
+        self.entries.push(Vec::new());
+        self.indices.insert(ty.clone(), index);
+
+        let mut entry = Vec::new();
+        match ty.as_ref() {
+            TypeInner::Opt(inner) => {
+                write_sleb(&mut entry, OPT);
+                let r = self.type_ref(inner)?;
+                write_sleb(&mut entry, r);
+            }
+            TypeInner::Vec(inner) => {
+                write_sleb(&mut entry, VEC);
+                let r = self.type_ref(inner)?;
+                write_sleb(&mut entry, r);
+            }
+            TypeInner::Record(fields) | TypeInner::Variant(fields) => {
+                let opcode = if matches!(ty.as_ref(), TypeInner::Record(_)) { RECORD } else { VARIANT };
+                write_sleb(&mut entry, opcode);
+                write_uleb(&mut entry, fields.len() as u64);
+                for field in fields {
+                    write_uleb(&mut entry, field.id.get_id() as u64);
+                    let r = self.type_ref(&field.ty)?;
+                    write_sleb(&mut entry, r);
+                }
+            }
+            TypeInner::Func(func) => {
+                write_sleb(&mut entry, FUNC);
+                for list in [&func.args, &func.rets] {
+                    write_uleb(&mut entry, list.len() as u64);
+                    for arg in list {
+                        let r = self.type_ref(arg)?;
+                        write_sleb(&mut entry, r);
+                    }
+                }
+                write_uleb(&mut entry, func.modes.len() as u64);
+                for mode in &func.modes {
+                    entry.push(match mode {
+                        FuncMode::Query => 1,
+                        FuncMode::Oneway => 2,
+                        FuncMode::CompositeQuery => 3,
+                    });
+                }
+            }
+            TypeInner::Service(methods) => {
+                write_sleb(&mut entry, SERVICE);
+                let mut methods: Vec<&(String, Type)> = methods.iter().collect();
+                methods.sort_by(|a, b| a.0.cmp(&b.0));
+                write_uleb(&mut entry, methods.len() as u64);
+                for (name, method_ty) in methods {
+                    write_uleb(&mut entry, name.len() as u64);
+                    entry.extend_from_slice(name.as_bytes());
+                    let r = self.type_ref(method_ty)?;
+                    write_sleb(&mut entry, r);
+                }
+            }
+            _ => return None,
+        }
+        self.entries[index as usize] = entry;
+        Some(index)
+    }
+}
+
+/// Python dict keys of a record type's fields: the Python key and, if
+/// different, the raw Candid field name (accepted as a fallback).
+type RecordKeys = Rc<Vec<(PyObjectRef, Option<PyObjectRef>)>>;
+
+struct Encoder {
+    out: Vec<u8>,
+    /// Nesting depth of the value being written (values can be self-referential).
+    depth: usize,
+    /// Record keys per record type, keyed by its field slice.
+    keys: HashMap<*const Field, RecordKeys>,
+}
+
+impl Encoder {
+    fn value(&mut self, obj: &PyObjectRef, ty: &Type) -> Option<()> {
+        if let TypeInner::Var(_) = ty.as_ref() {
+            return self.value(obj, &unroll(ty)?);
+        }
+        // Failures abort the whole encoding, so the depth is only restored on success.
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+        match ty.as_ref() {
+            TypeInner::Null | TypeInner::Reserved => {}
+            TypeInner::Bool => self.out.push(obj.extract_bool() as u8),
+            TypeInner::Text => unsafe {
+                let mut len: ffi::Py_ssize_t = 0;
+                let data = ffi::PyUnicode_AsUTF8AndSize(obj.as_ptr(), &mut len);
+                if data.is_null() {
+                    return None;
+                }
+                let bytes = std::slice::from_raw_parts(data as *const u8, len as usize);
+                write_uleb(&mut self.out, bytes.len() as u64);
+                self.out.extend_from_slice(bytes);
+            },
+            TypeInner::Nat => match obj.extract_u64() {
+                Ok(n) => write_uleb(&mut self.out, n),
+                Err(_) => {
+                    let n = num_bigint::BigUint::try_from_py_object(obj.clone()).ok()?;
+                    candid::Nat(n).encode(&mut self.out).ok()?;
+                }
+            },
+            TypeInner::Int => match obj.extract_i64() {
+                Ok(n) => write_sleb(&mut self.out, n),
+                Err(_) => {
+                    let n = num_bigint::BigInt::try_from_py_object(obj.clone()).ok()?;
+                    candid::Int(n).encode(&mut self.out).ok()?;
+                }
+            },
+            // Out-of-range fixed-width integers are declined; the IDLValue
+            // conversion reports them.
+            TypeInner::Nat8 => self.out.push(u8::try_from(obj.extract_u64().ok()?).ok()?),
+            TypeInner::Nat16 => self.out.extend_from_slice(&u16::try_from(obj.extract_u64().ok()?).ok()?.to_le_bytes()),
+            TypeInner::Nat32 => self.out.extend_from_slice(&u32::try_from(obj.extract_u64().ok()?).ok()?.to_le_bytes()),
+            TypeInner::Nat64 => self.out.extend_from_slice(&obj.extract_u64().ok()?.to_le_bytes()),
+            TypeInner::Int8 => self.out.extend_from_slice(&i8::try_from(obj.extract_i64().ok()?).ok()?.to_le_bytes()),
+            TypeInner::Int16 => self.out.extend_from_slice(&i16::try_from(obj.extract_i64().ok()?).ok()?.to_le_bytes()),
+            TypeInner::Int32 => self.out.extend_from_slice(&i32::try_from(obj.extract_i64().ok()?).ok()?.to_le_bytes()),
+            TypeInner::Int64 => self.out.extend_from_slice(&obj.extract_i64().ok()?.to_le_bytes()),
+            TypeInner::Float32 => self.out.extend_from_slice(&(obj.extract_f64().ok()? as f32).to_le_bytes()),
+            TypeInner::Float64 => self.out.extend_from_slice(&obj.extract_f64().ok()?.to_le_bytes()),
+            TypeInner::Opt(inner) => match opt_payload(obj) {
+                // `Opt(value)` is one explicit layer; anything else is wrapped implicitly.
+                Some(payload) if payload.is_none() && !opt_holds_none(inner) => self.out.push(0),
+                Some(payload) => {
+                    self.out.push(1);
+                    self.value(&payload, inner)?;
+                }
+                None if obj.is_none() => self.out.push(0),
+                None => {
+                    self.out.push(1);
+                    self.value(obj, inner)?;
+                }
+            },
+            TypeInner::Vec(inner) => self.vec(obj, inner)?,
+            TypeInner::Record(fields) => {
+                if is_tuple_record(fields) {
+                    self.tuple_record(obj, fields)?;
+                } else {
+                    self.dict_record(obj, fields)?;
+                }
+            }
+            TypeInner::Variant(cases) => self.variant(obj, cases)?,
+            // Principals and references are rare and need Python-side lookups;
+            // reuse the IDLValue conversion for them.
+            TypeInner::Principal | TypeInner::Func(_) | TypeInner::Service(_) => {
+                match python_to_idl_value_inner(obj, ty).ok()? {
+                    IDLValue::Principal(p) => self.principal(&p),
+                    IDLValue::Service(p) => self.principal(&p),
+                    IDLValue::Func(p, method) => {
+                        self.out.push(1);
+                        self.principal(&p);
+                        write_uleb(&mut self.out, method.len() as u64);
+                        self.out.extend_from_slice(method.as_bytes());
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn principal(&mut self, principal: &candid::Principal) {
+        let bytes = principal.as_slice();
+        self.out.push(1);
+        write_uleb(&mut self.out, bytes.len() as u64);
+        self.out.extend_from_slice(bytes);
+    }
+
+    fn vec(&mut self, obj: &PyObjectRef, inner: &Type) -> Option<()> {
+        unsafe {
+            // blob: take bytes objects as-is, anything else as a sequence
+            if matches!(inner.as_ref(), TypeInner::Nat8) {
+                let mut data: *const std::os::raw::c_char = std::ptr::null();
+                let mut len: ffi::Py_ssize_t = 0;
+                if ffi::PyBytes_AsStringAndSize(obj.as_ptr(), &mut data, &mut len) == 0 {
+                    write_uleb(&mut self.out, len as u64);
+                    self.out.extend_from_slice(std::slice::from_raw_parts(data as *const u8, len as usize));
+                    return Some(());
+                }
+                ffi::PyErr_Clear();
+            }
+            let len = ffi::PySequence_Length(obj.as_ptr());
+            if len < 0 {
+                return None;
+            }
+            write_uleb(&mut self.out, len as u64);
+            for i in 0..len {
+                let item = PyObjectRef::from_owned(ffi::PySequence_GetItem(obj.as_ptr(), i))?;
+                self.value(&item, inner)?;
+            }
+        }
+        Some(())
+    }
+
+    fn tuple_record(&mut self, obj: &PyObjectRef, fields: &[Field]) -> Option<()> {
+        for (i, field) in fields.iter().enumerate() {
+            let item = unsafe {
+                PyObjectRef::from_owned(ffi::PySequence_GetItem(obj.as_ptr(), i as ffi::Py_ssize_t))?
+            };
+            self.value(&item, &field.ty)?;
+        }
+        Some(())
+    }
+
+    fn dict_record(&mut self, obj: &PyObjectRef, fields: &[Field]) -> Option<()> {
+        let keys = self.record_keys(fields)?;
+        for (field, (key, candid_key)) in fields.iter().zip(keys.iter()) {
+            let item = match get_item(obj, key) {
+                Some(item) => item,
+                None => get_item(obj, candid_key.as_ref()?)?,
+            };
+            self.value(&item, &field.ty)?;
+        }
+        Some(())
+    }
+
+    fn record_keys(&mut self, fields: &[Field]) -> Option<RecordKeys> {
+        let ptr = fields.as_ptr();
+        if let Some(keys) = self.keys.get(&ptr) {
+            return Some(keys.clone());
+        }
+        let keys = fields
+            .iter()
+            .map(|f| {
+                let py_key = label_to_py_key(&f.id);
+                let candid_key = match f.id.as_ref() {
+                    Label::Named(name) => name.clone(),
+                    label => format!("_{}_", label.get_id()),
+                };
+                let fallback = if candid_key != py_key {
+                    Some(PyObjectRef::from_str(&candid_key).ok()?)
+                } else {
+                    None
+                };
+                Some((PyObjectRef::from_str(&py_key).ok()?, fallback))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let keys = Rc::new(keys);
+        self.keys.insert(ptr, keys.clone());
+        Some(keys)
+    }
+
+    /// A variant is a single-key dict, matched the same way as
+    /// `python_dict_to_variant`: by the keyword-stripped key or verbatim.
+    fn variant(&mut self, obj: &PyObjectRef, cases: &[Field]) -> Option<()> {
+        let (key, value) = unsafe {
+            // PyDict_Size fails (-1) for non-dicts
+            if ffi::PyDict_Size(obj.as_ptr()) != 1 {
+                return None;
+            }
+            let mut pos: ffi::Py_ssize_t = 0;
+            let mut key: *mut ffi::PyObject = std::ptr::null_mut();
+            let mut value: *mut ffi::PyObject = std::ptr::null_mut();
+            if ffi::PyDict_Next(obj.as_ptr(), &mut pos, &mut key, &mut value) == 0 {
+                return None;
+            }
+            (PyObjectRef::from_borrowed(key)?, PyObjectRef::from_borrowed(value)?)
+        };
+        let key = key.extract_str().ok()?;
+        let clean_id = field_name_to_label(&key).get_id();
+        let raw_id = candid_name_to_label(&key).get_id();
+        let index = cases
+            .iter()
+            .position(|c| c.id.get_id() == clean_id || c.id.get_id() == raw_id)?;
+        write_uleb(&mut self.out, index as u64);
+        let case_ty = &cases[index].ty;
+        if value.is_none() && matches!(case_ty.as_ref(), TypeInner::Null) {
+            return Some(());
+        }
+        self.value(&value, case_ty)
+    }
+}
+
+/// `obj[key]`, or None (with the Python error cleared) if absent.
+fn get_item(obj: &PyObjectRef, key: &PyObjectRef) -> Option<PyObjectRef> {
+    unsafe {
+        let item = ffi::PyObject_GetItem(obj.as_ptr(), key.as_ptr());
+        if item.is_null() {
+            ffi::PyErr_Clear();
+        }
+        PyObjectRef::from_owned(item)
+    }
+}
@@ -0,0 +1,78 @@
+//! Candid service description of the canister.
+//!
+//! The `.did` text is rebuilt from the injected metadata (`METHOD_METADATA`,
+//! `TYPE_DEFS` and the `init` signature in `LIFECYCLE`) in `init`/`post_upgrade`
+//! and served from the `__get_candid_interface_tmp_hack` query, which Candid UI
+//! and agent tooling use to discover a canister's interface.
+
+use crate::wasm_data::{MethodInfo, LIFECYCLE, METHOD_METADATA, TYPE_DEFS};
+
+/// Query method that returns the canister's Candid interface.
+pub const CANDID_INTERFACE_METHOD: &str = "__get_candid_interface_tmp_hack";
+
+/// Generated `.did` text (populated at init / post_upgrade).
+static mut CANDID_INTERFACE: Option<String> = None;
+
+/// Build the Candid interface from the metadata globals and cache it.
+/// Must run after those globals have been populated.
+pub fn build_candid_interface() {
+    let did = generate_candid_interface();
+    unsafe {
+        CANDID_INTERFACE = Some(did);
+    }
+}
+
+/// The canister's Candid interface as `.did` text.
+pub fn candid_interface() -> &'static str {
+    unsafe { CANDID_INTERFACE.get_or_insert_with(generate_candid_interface) }
+}
+
+fn generate_candid_interface() -> String {
+    let mut out = String::new();
+
+    // Named types, sorted so the output does not depend on map order
+    if let Some(type_defs) = unsafe { TYPE_DEFS.as_ref() } {
+        let mut names: Vec<&String> = type_defs.keys().collect();
+        names.sort();
+        for name in &names {
+            out.push_str(&format!("type {} = {};\n", name, type_defs[*name]));
+        }
+        if !names.is_empty() {
+            out.push('\n');
+        }
+    }
+
+    let init = unsafe { LIFECYCLE.as_ref() }.and_then(|lc| lc.get("init"));
+    match init {
+        Some(init) if !init.params.is_empty() => {
+            out.push_str(&format!("service : ({}) -> {{\n", param_list(init)));
+        }
+        _ => out.push_str("service : {\n"),
+    }
+
+    for method in unsafe { METHOD_METADATA.as_ref() }.into_iter().flatten() {
+        let mode = match method.method_type.as_str() {
+            "query" => " query",
+            "composite_query" => " composite_query",
+            _ => "",
+        };
+        out.push_str(&format!(
+            "  \"{}\" : ({}) -> ({}){};\n",
+            method.name,
+            param_list(method),
+            method.returns.iter().map(|t| t.trim()).collect::<Vec<_>>().join(", "),
+            mode
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn param_list(method: &MethodInfo) -> String {
+    method
+        .params
+        .iter()
+        .map(|p| p.candid_type.trim())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -49,11 +49,15 @@ pub fn basilisk_ic_create_module() -> Result<PyObjectRef, basilisk_cpython::PyEr
         add_method!("time", ic_time, ffi::METH_NOARGS);
         add_method!("candid_decode", ic_candid_decode, ffi::METH_O);
         add_method!("candid_encode", ic_candid_encode, ffi::METH_O);
+        add_method!("candid_decode_values", ic_candid_decode_values, ffi::METH_VARARGS);
+        add_method!("candid_encode_values", ic_candid_encode_values, ffi::METH_VARARGS);
         add_method!("msg_cycles_accept", ic_msg_cycles_accept, ffi::METH_O);
         add_method!("msg_cycles_accept128", ic_msg_cycles_accept128, ffi::METH_O);
         add_method!("performance_counter", ic_performance_counter, ffi::METH_O);
+        add_method!("preserve_opt_nesting", ic_preserve_opt_nesting, ffi::METH_O);
         add_method!("print", ic_print, ffi::METH_O);
         add_method!("reject", ic_reject, ffi::METH_O);
+        add_method!("register_field_name", ic_register_field_name, ffi::METH_O);
         add_method!("reply_raw", ic_reply_raw, ffi::METH_O);
         add_method!("set_certified_data", ic_set_certified_data, ffi::METH_O);
         add_method!("trap", ic_trap, ffi::METH_O);
@@ -72,6 +76,11 @@ pub fn basilisk_ic_create_module() -> Result<PyObjectRef, basilisk_cpython::PyEr
         add_method!("notify_raw", ic_notify_raw, ffi::METH_VARARGS);
         add_method!("notify_service_call", ic_notify_service_call, ffi::METH_O);
         add_method!("is_controller", ic_is_controller, ffi::METH_O);
+        add_method!("lock_acquire", ic_lock_acquire, ffi::METH_O);
+        add_method!("lock_release", ic_lock_release, ffi::METH_O);
+        add_method!("lock_held", ic_lock_held, ffi::METH_O);
+        add_method!("set_error_handler", ic_set_error_handler, ffi::METH_O);
+        add_method!("background_errors", ic_background_errors, ffi::METH_NOARGS);
 
         // Stable structures
         add_method!("smap_init", ic_smap_init, ffi::METH_O);
@@ -231,7 +240,7 @@ unsafe extern "C" fn ic_canister_balance128(
     _self: *mut ffi::PyObject,
     _args: *mut ffi::PyObject,
 ) -> *mut ffi::PyObject {
-    match PyObjectRef::from_u64(ic_cdk::api::canister_balance128() as u64) {
+    match PyObjectRef::from_u128(ic_cdk::api::canister_balance128()) {
         Ok(obj) => obj.into_ptr(),
         Err(_) => core::ptr::null_mut(),
     }
@@ -305,7 +314,7 @@ unsafe extern "C" fn ic_msg_cycles_available128(
     _self: *mut ffi::PyObject,
     _args: *mut ffi::PyObject,
 ) -> *mut ffi::PyObject {
-    match PyObjectRef::from_u64(ic_cdk::api::call::msg_cycles_available128() as u64) {
+    match PyObjectRef::from_u128(ic_cdk::api::call::msg_cycles_available128()) {
         Ok(obj) => obj.into_ptr(),
         Err(_) => core::ptr::null_mut(),
     }
@@ -325,7 +334,7 @@ unsafe extern "C" fn ic_msg_cycles_refunded128(
     _self: *mut ffi::PyObject,
     _args: *mut ffi::PyObject,
 ) -> *mut ffi::PyObject {
-    match PyObjectRef::from_u64(ic_cdk::api::call::msg_cycles_refunded128() as u64) {
+    match PyObjectRef::from_u128(ic_cdk::api::call::msg_cycles_refunded128()) {
         Ok(obj) => obj.into_ptr(),
         Err(_) => core::ptr::null_mut(),
     }
@@ -335,16 +344,7 @@ unsafe extern "C" fn ic_reject_code(
     _self: *mut ffi::PyObject,
     _args: *mut ffi::PyObject,
 ) -> *mut ffi::PyObject {
-    let code = ic_cdk::api::call::reject_code();
-    let attribute = match code {
-        ic_cdk::api::call::RejectionCode::NoError => "NoError",
-        ic_cdk::api::call::RejectionCode::SysFatal => "SysFatal",
-        ic_cdk::api::call::RejectionCode::SysTransient => "SysTransient",
-        ic_cdk::api::call::RejectionCode::DestinationInvalid => "DestinationInvalid",
-        ic_cdk::api::call::RejectionCode::CanisterReject => "CanisterReject",
-        ic_cdk::api::call::RejectionCode::CanisterError => "CanisterError",
-        ic_cdk::api::call::RejectionCode::Unknown => "Unknown",
-    };
+    let attribute = crate::method_dispatch::rejection_code_name(ic_cdk::api::call::reject_code());
     match basilisk_cpython::PyDict::new() {
         Ok(dict) => {
             let none = PyObjectRef::none();
@@ -564,6 +564,70 @@ unsafe extern "C" fn ic_candid_encode(
     }
 }
 
+/// ic.candid_encode_values(types, values) -> bytes
+/// Encode Python values with a Candid type string, the way method results are.
+unsafe extern "C" fn ic_candid_encode_values(
+    _self: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let args_tuple = match basilisk_cpython::PyTuple::from_object_unchecked(args) {
+        Some(t) if t.len() == 2 => t,
+        _ => return raise_value_error("candid_encode_values: expected (types, values)"),
+    };
+    let types = match args_tuple.get_item(0).map(|types| types.extract_str()) {
+        Some(Ok(types)) => types,
+        _ => return raise_value_error("candid_encode_values: types must be a str"),
+    };
+    if let Some(unknown) = crate::method_dispatch::unknown_value_type(&types) {
+        return raise_value_error(&format!("unknown Candid type '{}'", unknown));
+    }
+    let values = match args_tuple.get_item(1) {
+        Some(values) => values,
+        None => return core::ptr::null_mut(),
+    };
+    match crate::method_dispatch::encode_candid_values(&types, &values) {
+        Ok(bytes) => match PyObjectRef::from_bytes(&bytes) {
+            Ok(obj) => obj.into_ptr(),
+            Err(_) => core::ptr::null_mut(),
+        },
+        Err(e) => raise_value_error(&format!("candid_encode_values: {}", e)),
+    }
+}
+
+/// ic.candid_decode_values(data, types) -> value, or a tuple for a type list
+/// Decode Candid bytes into Python values, the way method arguments are.
+unsafe extern "C" fn ic_candid_decode_values(
+    _self: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let args_tuple = match basilisk_cpython::PyTuple::from_object_unchecked(args) {
+        Some(t) if t.len() == 2 => t,
+        _ => return raise_value_error("candid_decode_values: expected (data, types)"),
+    };
+    let bytes = match args_tuple.get_item(0).map(|data| data.extract_bytes()) {
+        Some(Ok(bytes)) => bytes,
+        _ => return raise_value_error("candid_decode_values: data must be bytes"),
+    };
+    let types = match args_tuple.get_item(1).map(|types| types.extract_str()) {
+        Some(Ok(types)) => types,
+        _ => return raise_value_error("candid_decode_values: types must be a str"),
+    };
+    if let Some(unknown) = crate::method_dispatch::unknown_value_type(&types) {
+        return raise_value_error(&format!("unknown Candid type '{}'", unknown));
+    }
+    match crate::method_dispatch::decode_candid_values(&bytes, &types) {
+        Ok(value) => value.into_ptr(),
+        Err(e) => raise_value_error(&format!("candid_decode_values: {}", e)),
+    }
+}
+
+/// Set a Python ValueError and return NULL.
+unsafe fn raise_value_error(msg: &str) -> *mut ffi::PyObject {
+    let msg = format!("{}\0", msg.replace('\0', ""));
+    ffi::PyErr_SetString(ffi::PyExc_ValueError, msg.as_ptr() as *const core::ffi::c_char);
+    core::ptr::null_mut()
+}
+
 unsafe extern "C" fn ic_msg_cycles_accept(
     _self: *mut ffi::PyObject,
     arg: *mut ffi::PyObject,
@@ -591,12 +655,12 @@ unsafe extern "C" fn ic_msg_cycles_accept128(
         Some(o) => o,
         None => return core::ptr::null_mut(),
     };
-    let max_amount: u64 = match obj.extract_u64() {
+    let max_amount: u128 = match obj.extract_u128() {
         Ok(v) => v,
         Err(_) => { ic_cdk::trap("msg_cycles_accept128: expected int argument"); }
     };
-    let accepted = ic_cdk::api::call::msg_cycles_accept128(max_amount as u128);
-    match PyObjectRef::from_u64(accepted as u64) {
+    let accepted = ic_cdk::api::call::msg_cycles_accept128(max_amount);
+    match PyObjectRef::from_u128(accepted) {
         Ok(obj) => obj.into_ptr(),
         Err(_) => core::ptr::null_mut(),
     }
@@ -640,6 +704,37 @@ unsafe extern "C" fn ic_print(
     PyObjectRef::none().into_ptr()
 }
 
+/// ic.register_field_names(*names) — make record field / variant case names
+/// recoverable from their hashes when decoding values of unknown type.
+unsafe extern "C" fn ic_register_field_name(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let obj = match PyObjectRef::from_borrowed(arg) {
+        Some(o) => o,
+        None => return core::ptr::null_mut(),
+    };
+    match obj.extract_str() {
+        Ok(name) => crate::type_table::register_field_name(&name),
+        Err(_) => { ic_cdk::trap("register_field_names: expected str arguments"); }
+    }
+    PyObjectRef::none().into_ptr()
+}
+
+/// ic.preserve_opt_nesting(enabled) — decode `opt` values as `Opt(...)`
+/// wrappers in methods that don't set `preserve_opt` themselves.
+unsafe extern "C" fn ic_preserve_opt_nesting(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let obj = match PyObjectRef::from_borrowed(arg) {
+        Some(o) => o,
+        None => return core::ptr::null_mut(),
+    };
+    crate::candid_codec::set_preserve_opt_default(obj.is_true());
+    PyObjectRef::none().into_ptr()
+}
+
 unsafe extern "C" fn ic_reject(
     _self: *mut ffi::PyObject,
     arg: *mut ffi::PyObject,
@@ -903,6 +998,98 @@ unsafe extern "C" fn ic_is_controller(
     PyObjectRef::from_bool(result).into_ptr()
 }
 
+// ─── Locks ───────────────────────────────────────────────────────────────────
+
+/// The lock key argument of the lock functions; sets TypeError if it isn't a str.
+unsafe fn lock_key(arg: *mut ffi::PyObject) -> Option<String> {
+    let obj = PyObjectRef::from_borrowed(arg)?;
+    match obj.extract_str() {
+        Ok(key) => Some(key),
+        Err(_) => {
+            ffi::PyErr_SetString(ffi::PyExc_TypeError, c"lock key must be a str".as_ptr());
+            None
+        }
+    }
+}
+
+/// ic.lock_acquire(key) — take the lock for the running call; False if another call holds it.
+unsafe extern "C" fn ic_lock_acquire(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let Some(key) = lock_key(arg) else {
+        return core::ptr::null_mut();
+    };
+    match crate::locks::try_acquire(&key) {
+        crate::locks::Acquire::Acquired => PyObjectRef::from_bool(true).into_ptr(),
+        crate::locks::Acquire::Busy => PyObjectRef::from_bool(false).into_ptr(),
+        crate::locks::Acquire::AlreadyHeld => {
+            let msg = format!("Lock '{}' is already held by this call\0", key.replace('\0', ""));
+            ffi::PyErr_SetString(ffi::PyExc_RuntimeError, msg.as_ptr() as *const core::ffi::c_char);
+            core::ptr::null_mut()
+        }
+    }
+}
+
+/// ic.lock_release(key) — release the lock if the running call holds it; returns whether it did.
+unsafe extern "C" fn ic_lock_release(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    match lock_key(arg) {
+        Some(key) => PyObjectRef::from_bool(crate::locks::release(&key)).into_ptr(),
+        None => core::ptr::null_mut(),
+    }
+}
+
+/// ic.lock_held(key) — whether any call holds the lock.
+unsafe extern "C" fn ic_lock_held(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    match lock_key(arg) {
+        Some(key) => PyObjectRef::from_bool(crate::locks::is_locked(&key)).into_ptr(),
+        None => core::ptr::null_mut(),
+    }
+}
+
+// ─── Background errors ───────────────────────────────────────────────────────
+
+/// ic.set_error_handler(handler) — call handler(error) for each failed timer,
+/// heartbeat or background generator; None unregisters it.
+unsafe extern "C" fn ic_set_error_handler(
+    _self: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let Some(handler) = PyObjectRef::from_borrowed(arg) else {
+        return core::ptr::null_mut();
+    };
+    if handler.is_none() {
+        crate::background_errors::set_error_handler(None);
+    } else if handler.has_attr("__call__") {
+        crate::background_errors::set_error_handler(Some(handler));
+    } else {
+        ffi::PyErr_SetString(ffi::PyExc_TypeError, c"error handler must be callable or None".as_ptr());
+        return core::ptr::null_mut();
+    }
+    PyObjectRef::none().into_ptr()
+}
+
+/// ic.background_errors() — the most recent background failures, oldest first.
+unsafe extern "C" fn ic_background_errors(
+    _self: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let errors: Result<Vec<_>, _> = crate::background_errors::background_errors()
+        .iter()
+        .map(|error| error.to_python())
+        .collect();
+    match errors {
+        Ok(errors) => crate::python_list(errors).into_ptr(),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
 // ─── Timers ──────────────────────────────────────────────────────────────────
 
 static mut TIMER_CB_COUNTER: u64 = 0;
@@ -962,19 +1149,23 @@ unsafe extern "C" fn ic_set_timer(
             .unwrap_or_else(|e| {
                 ic_cdk::trap(&format!("Timer callback '{}' not found: {}", func_name, e.to_rust_err_string()));
             });
+        let cb_name = crate::background_errors::callback_name(&py_func, &func_name);
         let empty = basilisk_cpython::PyTuple::new(Vec::new()).unwrap();
+        let task = crate::locks::Task::begin();
         match py_func.call(&empty.into_object(), None) {
             Ok(result) => {
-                // If the callback returns a generator, drive it async
+                // If the callback returns a generator (or an async def coroutine), drive it async
                 if result.has_attr("send") {
-                    let cb_name = func_name.clone();
                     ic_cdk::spawn(async move {
-                        let _ = crate::drive_generator(result, &cb_name).await;
+                        let _task = task;
+                        if let Err(e) = crate::drive_generator(result, &cb_name, "update", false).await {
+                            crate::background_errors::report(&cb_name, &e);
+                        }
                     });
                 }
             }
             Err(e) => {
-                ic_cdk::println!("Timer callback '{}' error: {}", func_name, e.to_rust_err_string());
+                crate::background_errors::report(&cb_name, &e);
             }
         }
     });
@@ -1018,19 +1209,23 @@ unsafe extern "C" fn ic_set_timer_interval(
             .unwrap_or_else(|e| {
                 ic_cdk::trap(&format!("Timer callback '{}' not found: {}", func_name, e.to_rust_err_string()));
             });
+        let cb_name = crate::background_errors::callback_name(&py_func, &func_name);
         let empty = basilisk_cpython::PyTuple::new(Vec::new()).unwrap();
+        let task = crate::locks::Task::begin();
         match py_func.call(&empty.into_object(), None) {
             Ok(result) => {
-                // If the callback returns a generator, drive it async
+                // If the callback returns a generator (or an async def coroutine), drive it async
                 if result.has_attr("send") {
-                    let cb_name = func_name.clone();
                     ic_cdk::spawn(async move {
-                        let _ = crate::drive_generator(result, &cb_name).await;
+                        let _task = task;
+                        if let Err(e) = crate::drive_generator(result, &cb_name, "update", false).await {
+                            crate::background_errors::report(&cb_name, &e);
+                        }
                     });
                 }
             }
             Err(e) => {
-                ic_cdk::println!("Timer callback '{}' error: {}", func_name, e.to_rust_err_string());
+                crate::background_errors::report(&cb_name, &e);
             }
         }
     });
@@ -1063,9 +1258,9 @@ unsafe extern "C" fn ic_clear_timer(
 
 // ─── Cross-canister calls ────────────────────────────────────────────────────
 
-/// ic.call_raw(canister_id: Principal, method: str, args_raw: bytes, cycles: int) -> bytes
-/// Returns a future-like that resolves to the raw Candid response bytes.
-/// For now, this is synchronous (blocking) since CPython doesn't support IC async natively.
+/// ic.call_raw(canister_id: Principal, method: str, args_raw: bytes, cycles: int)
+/// Returns a call to yield (or await); it resolves to a CallResult holding the
+/// raw Candid response bytes.
 unsafe extern "C" fn ic_call_raw(
     _self: *mut ffi::PyObject,
     args: *mut ffi::PyObject,
@@ -1077,16 +1272,37 @@ unsafe extern "C" fn ic_call_raw(
     if args_tuple.len() < 3 {
         ic_cdk::trap("call_raw: expected at least 3 arguments (canister_id, method, args_raw)");
     }
-    // For now, trap with a clear message — async cross-canister calls require generator/async support
-    ic_cdk::trap("call_raw: cross-canister calls are not yet supported in CPython mode. This requires async/generator support (Tier 2).");
+    call_shim_ic_method("call_raw", args)
 }
 
 /// ic.call_raw128 — same as call_raw but with 128-bit cycles
 unsafe extern "C" fn ic_call_raw128(
     _self: *mut ffi::PyObject,
-    _args: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
 ) -> *mut ffi::PyObject {
-    ic_cdk::trap("call_raw128: cross-canister calls are not yet supported in CPython mode. This requires async/generator support (Tier 2).");
+    let args_tuple = match basilisk_cpython::PyTuple::from_object_unchecked(args) {
+        Some(t) => t,
+        None => { ic_cdk::trap("call_raw128: expected tuple args"); }
+    };
+    if args_tuple.len() < 3 {
+        ic_cdk::trap("call_raw128: expected at least 3 arguments (canister_id, method, args_raw)");
+    }
+    call_shim_ic_method("call_raw128", args)
+}
+
+/// Call the shim's `ic.<name>`, which builds the `_ServiceCall` the generator
+/// yields; the call itself is made by the async driver in method_dispatch.rs.
+unsafe fn call_shim_ic_method(name: &str, args: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    let interpreter = match crate::INTERPRETER_OPTION.as_mut() {
+        Some(i) => i,
+        None => { ic_cdk::trap("SystemError: missing python interpreter"); }
+    };
+    let method = match interpreter.get_global("ic").and_then(|ic| ic.get_attr(name)) {
+        Ok(m) => m,
+        Err(e) => { ic_cdk::trap(&format!("{}: {}", name, e.to_rust_err_string())); }
+    };
+    // Returns NULL with the Python exception still set if the shim raises
+    ffi::PyObject_Call(method.as_ptr(), args, core::ptr::null_mut())
 }
 
 /// ic.notify_raw(canister_id, method, args_raw, cycles=0) -> NotifyResult
@@ -1144,14 +1360,11 @@ unsafe extern "C" fn ic_notify_raw(
     });
 
     // Extract cycles (optional, default 0)
-    let cycles: u128 = if args_tuple.len() > 3 {
-        if let Some(c) = args_tuple.get_item(3) {
-            c.extract_u64().unwrap_or(0) as u128
-        } else {
-            0
-        }
-    } else {
-        0
+    let cycles: u128 = match args_tuple.get_item(3) {
+        Some(c) if args_tuple.len() > 3 => c.extract_u128().unwrap_or_else(|e| {
+            ic_cdk::trap(&format!("notify_raw: invalid cycles: {}", e.to_rust_err_string()));
+        }),
+        _ => 0,
     };
 
     // Call ic_cdk notify_raw
@@ -1169,15 +1382,7 @@ unsafe extern "C" fn ic_notify_raw(
             });
         }
         Err(reject_code) => {
-            let code_str = match reject_code {
-                ic_cdk::api::call::RejectionCode::NoError => "NoError",
-                ic_cdk::api::call::RejectionCode::SysFatal => "SysFatal",
-                ic_cdk::api::call::RejectionCode::SysTransient => "SysTransient",
-                ic_cdk::api::call::RejectionCode::DestinationInvalid => "DestinationInvalid",
-                ic_cdk::api::call::RejectionCode::CanisterReject => "CanisterReject",
-                ic_cdk::api::call::RejectionCode::CanisterError => "CanisterError",
-                _ => "Unknown",
-            };
+            let code_str = crate::method_dispatch::rejection_code_name(reject_code);
             // Return as variant dict: {"Err": {"SysFatal": None}} etc.
             let inner_dict = basilisk_cpython::PyDict::new().unwrap_or_else(|e| {
                 ic_cdk::trap(&format!("notify_raw: inner dict: {}", e.to_rust_err_string()));
@@ -1226,10 +1431,7 @@ unsafe extern "C" fn ic_notify_service_call(
             ic_cdk::trap(&format!("notify_service_call: missing method_name: {}", e.to_rust_err_string()));
         });
 
-    // Extract payment (default 0)
-    let payment = service_call.get_attr("payment")
-        .and_then(|p| p.extract_u64())
-        .unwrap_or(0u64) as u128;
+    let payment = crate::method_dispatch::service_call_payment(&service_call);
 
     // Encode args using the same logic as method_dispatch::encode_service_call_args
     let args_raw = crate::method_dispatch::encode_service_call_args(&service_call);
@@ -1249,15 +1451,7 @@ unsafe extern "C" fn ic_notify_service_call(
             });
         }
         Err(reject_code) => {
-            let code_str = match reject_code {
-                ic_cdk::api::call::RejectionCode::NoError => "NoError",
-                ic_cdk::api::call::RejectionCode::SysFatal => "SysFatal",
-                ic_cdk::api::call::RejectionCode::SysTransient => "SysTransient",
-                ic_cdk::api::call::RejectionCode::DestinationInvalid => "DestinationInvalid",
-                ic_cdk::api::call::RejectionCode::CanisterReject => "CanisterReject",
-                ic_cdk::api::call::RejectionCode::CanisterError => "CanisterError",
-                _ => "Unknown",
-            };
+            let code_str = crate::method_dispatch::rejection_code_name(reject_code);
             let inner_dict = basilisk_cpython::PyDict::new().unwrap_or_else(|e| {
                 ic_cdk::trap(&format!("notify_service_call: inner dict: {}", e.to_rust_err_string()));
             });
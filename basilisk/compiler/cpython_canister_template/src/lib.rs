@@ -3,7 +3,6 @@
 //! Pre-compiled canister template that contains all the
 //! identical boilerplate code that every CPython-based basilisk canister needs:
 //! - Type conversion traits (CdkActTryIntoVmValue / CdkActTryFromVmValue)
-//! - Async result handler for cross-canister calls
 //! - IC API bindings (_basilisk_ic CPython module)
 //! - CPython initialization
 //! - Generic method dispatch via dynamic Candid
@@ -31,18 +30,25 @@ use slotmap::Key as _BasiliskTraitSlotMapKey;
 use std::{convert::TryInto as _BasiliskTraitTryInto, str::FromStr as _BasiliskTraitFromStr};
 
 mod ic_api;
+mod background_errors;
+mod locks;
 mod type_conversions;
-mod async_handler;
+mod bounded_wait;
 mod python_init;
 mod method_dispatch;
+mod candid_codec;
+mod candid_interface;
+mod subtyping;
+mod type_table;
 mod wasm_data;
 mod stable_structures;
 
 // Re-export from submodules
 use type_conversions::*;
-use async_handler::*;
 use python_init::*;
 use method_dispatch::*;
+use type_table::*;
+use candid_interface::*;
 use wasm_data::*;
 
 // ─── Global state ───────────────────────────────────────────────────────────
@@ -51,9 +57,9 @@ static mut INTERPRETER_OPTION: Option<basilisk_cpython::Interpreter> = None;
 static mut SCOPE_OPTION: Option<basilisk_cpython::Scope> = None;
 static mut CPYTHON_INIT_DONE: bool = false;
 static mut PRINCIPAL_CLASS_OPTION: Option<basilisk_cpython::PyObjectRef> = None;
-/// Current method's return type string — set before calling Manual[T] methods
+/// Current method's return type strings — set before calling Manual[T] methods
 /// so that ic.reply() knows how to encode the value.
-static mut CURRENT_RETURN_TYPE: Option<String> = None;
+static mut CURRENT_RETURN_TYPE: Option<Vec<String>> = None;
 
 // ─── RNG ────────────────────────────────────────────────────────────────────
 
@@ -77,7 +83,7 @@ fn init() {
     ic_wasi_polyfill::init(&[], &[]);
 
     let python_code = get_python_code();
-    let (method_meta, type_defs, lifecycle) = get_method_metadata();
+    let (method_meta, type_defs, lifecycle, decoding_quotas) = get_method_metadata();
 
     cpython_full_init(&python_code);
 
@@ -85,7 +91,14 @@ fn init() {
         METHOD_METADATA = Some(method_meta);
         TYPE_DEFS = Some(type_defs);
         LIFECYCLE = Some(lifecycle);
+        DECODING_QUOTAS = Some(decoding_quotas);
     }
+    compile_type_table();
+    compile_service_types();
+    bind_record_classes();
+    bind_service_classes();
+    bind_opt_class();
+    build_candid_interface();
 
     // Call user-defined @init function if present
     call_lifecycle_hook("init");
@@ -96,7 +109,7 @@ fn post_upgrade() {
     ic_wasi_polyfill::init(&[], &[]);
 
     let python_code = get_python_code();
-    let (method_meta, type_defs, lifecycle) = get_method_metadata();
+    let (method_meta, type_defs, lifecycle, decoding_quotas) = get_method_metadata();
 
     cpython_full_init(&python_code);
 
@@ -104,7 +117,15 @@ fn post_upgrade() {
         METHOD_METADATA = Some(method_meta);
         TYPE_DEFS = Some(type_defs);
         LIFECYCLE = Some(lifecycle);
+        DECODING_QUOTAS = Some(decoding_quotas);
     }
+    compile_type_table();
+    compile_service_types();
+    bind_record_classes();
+    bind_service_classes();
+    bind_opt_class();
+    build_candid_interface();
+    background_errors::restore();
 
     // Call user-defined @post_upgrade function if present
     call_lifecycle_hook("post_upgrade");
@@ -118,6 +139,7 @@ fn pre_upgrade() {
     // Stable structures persist automatically — no serialization needed.
     // Call user-defined @pre_upgrade function if present.
     call_lifecycle_hook("pre_upgrade");
+    background_errors::save();
 }
 
 #[ic_cdk_macros::heartbeat]
@@ -147,7 +169,8 @@ fn inspect_message() {
 // ─── Generic method execution ───────────────────────────────────────────────
 // These are placeholder functions that get called by the canister_query/update
 // export stubs added via wasm manipulation. The index maps to the method
-// metadata array.
+// metadata array. canister_composite_query stubs also go through
+// execute_query_method; their generators may yield calls to other queries.
 
 #[no_mangle]
 pub extern "C" fn execute_query_method(method_index: i32) {
@@ -0,0 +1,98 @@
+//! Named locks that Python methods hold across yields and awaits (`basilisk.Lock`).
+//!
+//! Every canister method, timer callback and lifecycle hook runs as a task:
+//! `Task::begin` gives it an id and makes it the current task, and
+//! `drive_generator` makes it current again each time it resumes the task's
+//! generator in a later message. A lock belongs to the task that acquired
+//! it, and dropping the `Task` — when the method finishes, or when its future
+//! is dropped after a trap in a later message — releases whatever the task
+//! still holds. Acquiring never waits: a lock held by another task is
+//! reported as busy, and the Python side rejects the call.
+
+use std::collections::BTreeMap;
+
+/// Lock key -> id of the task holding it.
+static mut LOCKS: Option<BTreeMap<String, u64>> = None;
+
+/// The task whose Python code is running (0 outside any task).
+static mut CURRENT_TASK: u64 = 0;
+
+static mut NEXT_TASK: u64 = 1;
+
+/// The outcome of `try_acquire`.
+pub enum Acquire {
+    Acquired,
+    /// Another task holds the lock.
+    Busy,
+    /// The current task already holds the lock.
+    AlreadyHeld,
+}
+
+/// A running method, timer callback or lifecycle hook; releases its locks when dropped.
+pub struct Task {
+    id: u64,
+}
+
+impl Task {
+    /// Start a new task and make it the current one.
+    pub fn begin() -> Task {
+        unsafe {
+            let id = NEXT_TASK;
+            NEXT_TASK += 1;
+            CURRENT_TASK = id;
+            Task { id }
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        let id = self.id;
+        if let Some(locks) = unsafe { LOCKS.as_mut() } {
+            locks.retain(|_, holder| *holder != id);
+        }
+    }
+}
+
+/// The id of the current task.
+pub fn current_task() -> u64 {
+    unsafe { CURRENT_TASK }
+}
+
+/// Make task `id` current again before resuming its Python code.
+pub fn resume_task(id: u64) {
+    unsafe { CURRENT_TASK = id };
+}
+
+/// Acquire `key` for the current task if no task holds it.
+pub fn try_acquire(key: &str) -> Acquire {
+    let task = current_task();
+    let locks = unsafe { LOCKS.get_or_insert_with(BTreeMap::new) };
+    match locks.get(key) {
+        Some(&holder) if holder == task => Acquire::AlreadyHeld,
+        Some(_) => Acquire::Busy,
+        None => {
+            locks.insert(key.to_string(), task);
+            Acquire::Acquired
+        }
+    }
+}
+
+/// Release `key` if the current task holds it; returns whether it did.
+pub fn release(key: &str) -> bool {
+    let task = current_task();
+    let Some(locks) = (unsafe { LOCKS.as_mut() }) else {
+        return false;
+    };
+    if locks.get(key) == Some(&task) {
+        locks.remove(key);
+        true
+    } else {
+        false
+    }
+}
+
+/// Whether any task holds `key`.
+pub fn is_locked(key: &str) -> bool {
+    unsafe { LOCKS.as_ref() }.is_some_and(|locks| locks.contains_key(key))
+}
@@ -3,12 +3,17 @@
 //! Instead of generating typed Rust functions per canister method,
 //! this module provides a single generic dispatcher that:
 //! 1. Reads raw Candid bytes from the IC message
-//! 2. Decodes them dynamically using candid::IDLArgs
-//! 3. Converts IDLValue arguments to Python objects
-//! 4. Calls the named Python function
-//! 5. Converts the Python return value back to an IDLValue
-//! 6. Encodes and replies with raw Candid bytes
-
+//! 2. Decodes them straight into Python objects (`candid_codec`), falling back
+//!    to candid::IDLArgs + IDLValue conversion for anything the codec skips
+//! 3. Calls the named Python function
+//! 4. Encodes the Python return value to Candid bytes the same way
+//! 5. Replies with the raw Candid bytes
+
+use crate::candid_codec;
+use crate::candid_interface::{candid_interface, CANDID_INTERFACE_METHOD};
+use basilisk_cpython::{TryFromPyObject, TryIntoPyObject};
+use crate::subtyping::{coerce_arg, CandidDecodeError};
+use crate::type_table::{candid_name_to_label, field_name, lookup_type, named_types, type_env, unroll};
 use crate::wasm_data::{MethodInfo, METHOD_METADATA, TYPE_DEFS, LIFECYCLE};
 use std::collections::HashMap;
 
@@ -48,7 +53,11 @@ pub fn call_lifecycle_hook(hook_name: &str) {
     // Decode init args if the hook takes parameters (e.g. @init with args)
     let args = if !hook_info.params.is_empty() {
         let arg_bytes = ic_cdk::api::call::arg_data_raw();
-        decode_candid_args_to_python(&arg_bytes, &hook_info.params)
+        // Lifecycle hooks cannot reject, so a bad init payload traps
+        let preserve_opt = hook_info.preserve_opt.unwrap_or_else(candid_codec::preserve_opt_default);
+        let quotas = hook_info.effective_decoding_quotas();
+        decode_candid_args_to_python(&arg_bytes, &hook_info.params, preserve_opt, &quotas)
+            .unwrap_or_else(|e| ic_cdk::trap(&e.to_string()))
     } else {
         Vec::new()
     };
@@ -61,25 +70,125 @@ pub fn call_lifecycle_hook(hook_name: &str) {
         ));
     });
 
-    let py_result = py_func
-        .call(&args_tuple.into_object(), None)
-        .unwrap_or_else(|e| {
+    let task = crate::locks::Task::begin();
+    let py_result = match py_func.call(&args_tuple.into_object(), None) {
+        Ok(result) => result,
+        // A failing heartbeat has nothing to fail but itself; the other
+        // hooks still trap so a bad install or upgrade is refused
+        Err(e) if hook_name == "heartbeat" => {
+            crate::background_errors::report(function_name, &e);
+            return;
+        }
+        Err(e) => {
             ic_cdk::trap(&format!(
                 "Error calling lifecycle '{}': {}",
                 function_name,
                 e.to_rust_err_string()
             ));
-        });
+        }
+    };
 
-    // If the lifecycle hook returns a generator (async with yield), drive it
+    // If the lifecycle hook returns a generator (async with yield) or a coroutine (async def), drive it
     if py_result.has_attr("send") {
         let func_name = function_name.clone();
         ic_cdk::spawn(async move {
-            drive_generator(py_result, &func_name).await;
+            let _task = task;
+            if let Err(e) = drive_generator(py_result, &func_name, "update", false).await {
+                crate::background_errors::report(&func_name, &e);
+            }
         });
     }
 }
 
+/// Compile the `_arg_types` / `_return_types` strings of every `Service`
+/// subclass, so their field names are known when decoding replies and the
+/// compiled types are cached before the first call.
+pub fn compile_service_types() {
+    let Some(interpreter) = (unsafe { crate::INTERPRETER_OPTION.as_ref() }) else {
+        return;
+    };
+    let types = match interpreter.eval_expression("iter(_basilisk_service_candid_types())") {
+        Ok(types) => types,
+        Err(e) => {
+            ic_cdk::println!("Warning: could not collect Service types: {}", e.to_rust_err_string());
+            return;
+        }
+    };
+    while let Ok(Some(type_str)) = types.iter_next() {
+        if let Ok(type_str) = type_str.extract_str() {
+            lookup_type(&type_str);
+        }
+    }
+}
+
+/// Bind the user's `Record` / `Variant` subclasses to the compiled named
+/// types of the same name, so decoded values are built as instances of them.
+pub fn bind_record_classes() {
+    let Some(interpreter) = (unsafe { crate::INTERPRETER_OPTION.as_ref() }) else {
+        return;
+    };
+    let classes = match interpreter.eval_expression("_basilisk_candid_classes()") {
+        Ok(classes) => classes,
+        Err(e) => {
+            ic_cdk::println!("Warning: could not collect Record/Variant classes: {}", e.to_rust_err_string());
+            return;
+        }
+    };
+    let mut bound = HashMap::new();
+    for (name, ty) in named_types() {
+        let fields = match ty.as_ref() {
+            candid::types::TypeInner::Record(fields) if !is_tuple_record(fields) => fields,
+            candid::types::TypeInner::Variant(fields) => fields,
+            _ => continue,
+        };
+        if fields.is_empty() {
+            continue;
+        }
+        if let Ok(class) = classes.get_item_str(name) {
+            bound.entry(fields.as_ptr()).or_insert(class);
+        }
+    }
+    candid_codec::set_record_classes(bound);
+}
+
+/// Give the user's `Service` subclasses the method types of the compiled
+/// service type of the same name (see `set_service_method_types`), so calls
+/// made through them, including `@service_method` descriptors, are typed.
+pub fn bind_service_classes() {
+    let Some(interpreter) = (unsafe { crate::INTERPRETER_OPTION.as_ref() }) else {
+        return;
+    };
+    let classes = match interpreter.eval_expression("_basilisk_service_classes()") {
+        Ok(classes) => classes,
+        Err(e) => {
+            ic_cdk::println!("Warning: could not collect Service classes: {}", e.to_rust_err_string());
+            return;
+        }
+    };
+    for (name, ty) in named_types() {
+        let candid::types::TypeInner::Service(methods) = ty.as_ref() else {
+            continue;
+        };
+        if let Ok(class) = classes.get_item_str(name) {
+            if let Err(e) = set_service_method_types(&class, methods) {
+                ic_cdk::println!("Warning: could not type Service class {}: {}", name, e);
+            }
+        }
+    }
+}
+
+/// Look up the shim's `Opt` class so decoding can wrap `opt` values in it and
+/// encoding can recognise explicit `Opt(...)` layers.
+pub fn bind_opt_class() {
+    let Some(interpreter) = (unsafe { crate::INTERPRETER_OPTION.as_ref() }) else {
+        return;
+    };
+    match interpreter.eval_expression("_Opt") {
+        Ok(class) => candid_codec::set_opt_class(class),
+        Err(e) => ic_cdk::println!("Warning: could not find the Opt class: {}", e.to_rust_err_string()),
+    }
+}
+
 /// Call a Python function by name with no arguments.
 /// Silently returns if CPython is not initialized or the function doesn't exist.
 /// Used for internal hooks like _basilisk_load_files.
@@ -208,6 +317,18 @@ pub fn execute_canister_method(method_index: i32, _is_update: bool) {
 
     let function_name = &method_info.name;
 
+    // The Candid interface is generated by the template, not by Python code
+    if function_name == CANDID_INTERFACE_METHOD {
+        let did = candid::encode_one(candid_interface()).unwrap_or_else(|e| {
+            ic_cdk::trap(&format!("Failed to encode Candid interface: {}", e));
+        });
+        ic_cdk::api::call::reply_raw(&did);
+        return;
+    }
+
+    // Locks the method acquires are released when this task ends
+    let task = crate::locks::Task::begin();
+
     // Execute guard function if present
     if let Some(guard_name) = &method_info.guard {
         if guard_name == "guard_against_non_controllers" {
@@ -223,12 +344,21 @@ pub fn execute_canister_method(method_index: i32, _is_update: bool) {
     // Get raw Candid argument bytes from the IC message
     let arg_bytes = ic_cdk::api::call::arg_data_raw();
 
-    // Decode Candid arguments dynamically
+    // Decode Candid arguments against the declared parameter types
     let args = if arg_bytes.len() <= 6 && method_info.params.is_empty() {
         // No arguments expected — common case for simple queries
         Vec::new()
     } else {
-        decode_candid_args_to_python(&arg_bytes, &method_info.params)
+        let preserve_opt = method_info.preserve_opt.unwrap_or_else(candid_codec::preserve_opt_default);
+        let quotas = method_info.effective_decoding_quotas();
+        match decode_candid_args_to_python(&arg_bytes, &method_info.params, preserve_opt, &quotas) {
+            Ok(args) => args,
+            Err(e) => {
+                // Malformed or ill-typed arguments are the caller's fault: reject, don't trap
+                ic_cdk::api::call::reject(&e.to_string());
+                return;
+            }
+        }
     };
 
     // Call the Python function
@@ -261,22 +391,17 @@ pub fn execute_canister_method(method_index: i32, _is_update: bool) {
         ));
     });
 
-    let py_result = py_func
-        .call(&args_tuple.into_object(), None)
-        .unwrap_or_else(|e| {
-            ic_cdk::trap(&format!(
-                "Error calling '{}': {}",
-                function_name,
-                e.to_rust_err_string()
-            ));
-        });
+    let py_result = match py_func.call(&args_tuple.into_object(), None) {
+        Ok(result) => result,
+        Err(e) => {
+            reply_with_exception(method_info, &e, "Error calling", false);
+            return;
+        }
+    };
 
-    // For async methods (generators), spawn the async driver
+    // For async methods (generators and async def coroutines), spawn the async driver
     if method_info.is_async {
-        let return_type = method_info.returns.clone();
-        let manual_reply = method_info.manual_reply;
-        let func_name = function_name.clone();
-        execute_async_generator(py_result, return_type, manual_reply, func_name);
+        execute_async_generator(py_result, method_info.clone(), task);
         return;
     }
 
@@ -294,100 +419,487 @@ pub fn execute_canister_method(method_index: i32, _is_update: bool) {
 /// The generator yields `_ServiceCall` objects which we execute via `ic_cdk::api::call::call_raw`,
 /// and we send the results back via `gen.send(result)`. When the generator raises StopIteration,
 /// we extract the return value and reply.
-fn execute_async_generator(
-    generator: basilisk_cpython::PyObjectRef,
-    return_type: String,
-    manual_reply: bool,
-    func_name: String,
-) {
+fn execute_async_generator(generator: basilisk_cpython::PyObjectRef, method_info: MethodInfo, task: crate::locks::Task) {
     ic_cdk::spawn(async move {
-        let result = drive_generator(generator, &func_name).await;
+        // Dropped when the method finishes, or if its future is dropped after a trap
+        let _task = task;
+        let mut driver = drive_generator(generator, &method_info.name, &method_info.method_type, method_info.raise_on_reject);
+        // Whether the method has suspended at an await, which commits its
+        // state changes up to that point
+        let mut suspended = false;
+        let outcome = std::future::poll_fn(|context| {
+            let poll = std::future::Future::poll(driver.as_mut(), context);
+            suspended |= poll.is_pending();
+            poll
+        })
+        .await;
+        let result = match outcome {
+            Ok(result) => result,
+            Err(e) => {
+                reply_with_exception(&method_info, &e, "Error in async method", suspended);
+                return;
+            }
+        };
 
-        if manual_reply {
+        if method_info.manual_reply {
             return;
         }
-        let result_bytes = encode_python_to_candid(&result, &return_type);
+        let result_bytes = encode_python_to_candid(&result, &method_info.returns);
         ic_cdk::api::call::reply_raw(&result_bytes);
     });
 }
 
-/// Recursively drive a Python generator. Handles both _ServiceCall yields (IC calls)
-/// and nested generator yields (sub-generators that themselves yield _ServiceCall objects).
-/// Returns the generator's return value (from StopIteration.value).
+/// Answer a call whose Python code raised `e` instead of returning.
+///
+/// `basilisk.Reject(message)`, or an instance of any subclass of it (such as
+/// `LockBusy`), rejects the call with `CANISTER_REJECT` and that message. Methods declared with
+/// `err_on_exception=True` whose return type is a `variant { Ok : ...; Err : text }`
+/// reply with `Err` set to the exception text.
+/// Methods declared with `raise_on_reject=True` whose exception was raised
+/// after their first await (`suspended`) reject with the exception text. The
+/// state changes made before that await were committed when it suspended;
+/// rejecting rather than trapping also keeps the changes made since, such as
+/// the cleanup `finally` blocks ran while the exception propagated. An
+/// exception raised before any await still traps, so a method that fails
+/// without ever suspending leaves no partial state behind.
+/// Anything else traps with `context`, which rolls back the message's state
+/// changes since its last await.
+fn reply_with_exception(method_info: &MethodInfo, e: &basilisk_cpython::PyError, context: &str, suspended: bool) {
+    if is_reject(e) {
+        ic_cdk::api::call::reject(&e.message);
+        return;
+    }
+
+    if method_info.err_on_exception && !method_info.manual_reply && returns_text_err(&method_info.returns) {
+        let message = basilisk_cpython::PyObjectRef::from_str(&e.to_rust_err_string())
+            .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none());
+        let dict = basilisk_cpython::PyDict::new().unwrap_or_else(|e| {
+            ic_cdk::trap(&format!("Failed to create dict: {}", e));
+        });
+        let _ = dict.set_item_str("Err", &message);
+        let result_bytes = encode_python_to_candid(&dict.into_object(), &method_info.returns);
+        ic_cdk::api::call::reply_raw(&result_bytes);
+        return;
+    }
+
+    if method_info.raise_on_reject && suspended {
+        ic_cdk::api::call::reject(&format!(
+            "{} '{}': {}",
+            context,
+            method_info.name,
+            e.to_rust_err_string()
+        ));
+        return;
+    }
+
+    ic_cdk::trap(&format!(
+        "{} '{}': {}",
+        context,
+        method_info.name,
+        e.to_rust_err_string()
+    ));
+}
+
+/// Whether `e` is an instance of the shim's `Reject` class (or a subclass of
+/// it), rather than merely an exception named `Reject`.
+fn is_reject(e: &basilisk_cpython::PyError) -> bool {
+    let Some(exception) = &e.exception else {
+        return false;
+    };
+    let Some(interpreter) = (unsafe { crate::INTERPRETER_OPTION.as_ref() }) else {
+        return false;
+    };
+    interpreter
+        .get_global("_Reject")
+        .is_ok_and(|cls| exception.is_instance(&cls))
+}
+
+/// Whether `return_types` is a single variant with an `Ok` case and a `text` `Err` case.
+fn returns_text_err(return_types: &[String]) -> bool {
+    let [return_type] = return_types else {
+        return false;
+    };
+    let Some(ty) = lookup_type(return_type).as_ref().and_then(unroll) else {
+        return false;
+    };
+    let candid::types::TypeInner::Variant(fields) = ty.as_ref() else {
+        return false;
+    };
+    let case = |name: &str| fields.iter().find(|f| f.id.as_ref() == &candid_name_to_label(name));
+    case("Ok").is_some()
+        && case("Err")
+            .and_then(|f| unroll(&f.ty))
+            .is_some_and(|t| matches!(t.as_ref(), candid::types::TypeInner::Text))
+}
+
+/// Recursively drive a Python generator. Handles _ServiceCall yields (IC calls), the
+/// `AsyncInfo` call descriptors older basilisk code yields (typed or raw, with or
+/// without a 64/128-bit payment), lists of either, and nested generator yields
+/// (sub-generators that themselves yield calls).
+/// Returns the generator's return value (from StopIteration.value), or the
+/// exception the generator (or one of its sub-generators) raised.
+///
+/// Coroutines from `async def` follow the same `send`/`StopIteration` protocol:
+/// `await call` reaches this loop as the `_ServiceCall` yielded by its
+/// `__await__`, and awaiting another coroutine is delegated by Python itself.
+///
+/// `method_type` is the kind of method the calls are made from ("update" for
+/// timers and lifecycle hooks); see `service_call_result`.
+///
+/// With `raise_on_reject`, a rejected call is thrown into the generator as a
+/// `CallRejected` exception instead of being sent back as an Err `CallResult`,
+/// and a sub-generator's exception is thrown into the generator that yielded
+/// it, so `try`/`except`/`finally` around the yield sees both.
 pub fn drive_generator(
     generator: basilisk_cpython::PyObjectRef,
     func_name: &str,
-) -> std::pin::Pin<Box<dyn std::future::Future<Output = basilisk_cpython::PyObjectRef> + 'static>> {
+    method_type: &str,
+    raise_on_reject: bool,
+) -> std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<basilisk_cpython::PyObjectRef, basilisk_cpython::PyError>> + 'static>,
+> {
     let func_name = func_name.to_string();
+    let method_type = method_type.to_string();
+    let task = crate::locks::current_task();
     Box::pin(async move {
         let gen = generator;
-        let mut send_value = basilisk_cpython::PyObjectRef::none();
+        let mut resume = Resume::Send(basilisk_cpython::PyObjectRef::none());
 
         loop {
-            let result = gen.call_method_one_arg("send", &send_value);
+            // Later messages may have run other tasks since the last resume
+            crate::locks::resume_task(task);
+            let result = match &resume {
+                Resume::Send(value) => gen.call_method_one_arg("send", value),
+                Resume::Throw(exception) => gen.call_method_one_arg("throw", exception),
+            };
 
             match result {
                 Ok(yielded) => {
-                    // Check if yielded value is a _ServiceCall (has canister_principal attr)
-                    if yielded.has_attr("canister_principal") {
-                        // It's a _ServiceCall — make the IC inter-canister call
-                        let return_raw = yielded.has_attr("_return_raw");
-                        // Extract optional return type hint for typed decoding
-                        let return_type_hint = yielded.get_attr("_return_candid_type")
-                            .ok()
-                            .and_then(|obj| if obj.is_none() { None } else { obj.extract_str().ok() });
-                        let call_result = perform_service_call(&yielded).await;
-                        send_value = match call_result {
-                            Ok(raw_bytes) => {
-                                let py_val = if return_raw {
-                                    // call_raw: return raw bytes, Python decodes with ic.candid_decode()
-                                    basilisk_cpython::PyObjectRef::from_bytes(&raw_bytes)
-                                        .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none())
-                                } else {
-                                    decode_candid_response_typed(&raw_bytes, return_type_hint.as_deref())
-                                };
-                                make_python_dict_result("Ok", py_val)
+                    let calls = match yielded_calls(&yielded) {
+                        Ok(calls) => calls,
+                        Err(e) => {
+                            // A malformed descriptor raises where it was yielded
+                            resume = match e.exception {
+                                Some(exception) => Resume::Throw(exception),
+                                None => return Err(e),
+                            };
+                            continue;
+                        }
+                    };
+                    resume = match calls {
+                        Some(YieldedCalls::One(call)) => {
+                            // A single call — make the IC inter-canister call
+                            let call_result = service_call_result(&call, &method_type).await;
+                            match rejected_call(raise_on_reject, &call_result, None) {
+                                Some(exception) => Resume::Throw(exception),
+                                None => Resume::Send(call_result),
                             }
-                            Err((rejection_code, msg)) => {
-                                let code_num = match rejection_code {
-                                    ic_cdk::api::call::RejectionCode::NoError => 0,
-                                    ic_cdk::api::call::RejectionCode::SysFatal => 1,
-                                    ic_cdk::api::call::RejectionCode::SysTransient => 2,
-                                    ic_cdk::api::call::RejectionCode::DestinationInvalid => 3,
-                                    ic_cdk::api::call::RejectionCode::CanisterReject => 4,
-                                    ic_cdk::api::call::RejectionCode::CanisterError => 5,
-                                    _ => 99,
-                                };
-                                let err_msg = format!("Rejection code {}, {}", code_num, msg);
-                                let py_err = basilisk_cpython::PyObjectRef::from_str(&err_msg)
-                                    .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none());
-                                make_python_dict_result("Err", py_err)
+                        }
+                        Some(YieldedCalls::Batch(calls)) => {
+                            // A list or tuple of calls — make all the calls
+                            // concurrently and send back their results in order
+                            // (or throw the first rejection, with raise_on_reject)
+                            let results = futures::future::join_all(calls.iter().map(|call| service_call_result(call, &method_type))).await;
+                            let first_err = results.iter().find(|r| call_result_is_err(r)).cloned();
+                            let results = python_list(results);
+                            match first_err.and_then(|r| rejected_call(raise_on_reject, &r, Some(&results))) {
+                                Some(exception) => Resume::Throw(exception),
+                                None => Resume::Send(results),
                             }
-                        };
-                    } else if yielded.has_attr("send") {
-                        // It's a sub-generator — recursively drive it
-                        send_value = drive_generator(yielded, &func_name).await;
-                    } else {
+                        }
+                        None if yielded.has_attr("send") => {
+                            // It's a sub-generator — recursively drive it
+                            match drive_generator(yielded, &func_name, &method_type, raise_on_reject).await {
+                                Ok(value) => Resume::Send(value),
+                                Err(e) => match e.exception {
+                                    Some(exception) if raise_on_reject => Resume::Throw(exception),
+                                    _ => return Err(e),
+                                },
+                            }
+                        }
                         // Unknown yielded type — pass it through as-is
-                        send_value = yielded;
-                    }
+                        None => Resume::Send(yielded),
+                    };
                 }
                 Err(e) => {
                     if e.type_name == "StopIteration" {
-                        return e.value.unwrap_or_else(basilisk_cpython::PyObjectRef::none);
-                    } else {
-                        ic_cdk::trap(&format!(
-                            "Error in async method '{}': {}",
-                            func_name,
-                            e.to_rust_err_string()
-                        ));
+                        return Ok(e.value.unwrap_or_else(basilisk_cpython::PyObjectRef::none));
                     }
+                    return Err(e);
                 }
             }
         }
     })
 }
 
+/// How `drive_generator` resumes a generator: send it a value, or throw an exception into it.
+enum Resume {
+    Send(basilisk_cpython::PyObjectRef),
+    Throw(basilisk_cpython::PyObjectRef),
+}
+
+/// Whether a `CallResult` holds an Err.
+fn call_result_is_err(call_result: &basilisk_cpython::PyObjectRef) -> bool {
+    let err_key = basilisk_cpython::PyObjectRef::from_str("Err").unwrap();
+    call_result.get_item(&err_key).is_ok_and(|err| !err.is_none())
+}
+
+/// The `CallRejected` exception to throw for an Err `call_result` when
+/// `raise_on_reject` is set; `results` is the whole batch it came from, if any.
+fn rejected_call(
+    raise_on_reject: bool,
+    call_result: &basilisk_cpython::PyObjectRef,
+    results: Option<&basilisk_cpython::PyObjectRef>,
+) -> Option<basilisk_cpython::PyObjectRef> {
+    if !raise_on_reject || !call_result_is_err(call_result) {
+        return None;
+    }
+    let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() }?;
+    let cls = interpreter.get_global("_CallRejected").ok()?;
+    let results = results.cloned().unwrap_or_else(basilisk_cpython::PyObjectRef::none);
+    let args = basilisk_cpython::PyTuple::new(vec![call_result.clone(), results]).ok()?;
+    cls.call(&args.into_object(), None).ok()
+}
+
+/// Make the call a yielded `_ServiceCall` describes and return its Python `CallResult`,
+/// with the reply decoded by the call's return type hint (or left raw for `ic.call_raw`).
+///
+/// The result also records the cycles refunded for this call and the call
+/// context instruction counter just before the call and just after its
+/// response (see `set_call_info`). Only update calls can read a refund
+/// (`ic0.msg_cycles_refunded128` traps in composite query callbacks), so calls
+/// from a `method_type` of "composite_query", which cannot attach cycles
+/// anyway, report 0.
+async fn service_call_result(
+    service_call: &basilisk_cpython::PyObjectRef,
+    method_type: &str,
+) -> basilisk_cpython::PyObjectRef {
+    let return_raw = service_call.has_attr("_return_raw");
+    // Extract optional return type hint for typed decoding
+    let return_type_hint = service_call.get_attr("_return_candid_type")
+        .ok()
+        .and_then(|obj| if obj.is_none() { None } else { obj.extract_str().ok() });
+    let bounded_wait = service_call_timeout(service_call).is_some();
+    let payment = service_call_payment(service_call);
+    let instructions_before = ic_cdk::api::performance_counter(1);
+    let mut call = std::pin::pin!(perform_service_call(service_call));
+    let mut sent = false;
+    let outcome = std::future::poll_fn(|context| {
+        let poll = std::future::Future::poll(call.as_mut(), context);
+        sent |= poll.is_pending();
+        poll
+    })
+    .await;
+    // The response callback is still running here, so the refund is this
+    // call's own. A call that resolved without suspending was never sent
+    // (call_perform failed), and its cycles stayed in the balance.
+    let cycles_refunded = if method_type == "composite_query" {
+        0
+    } else if sent {
+        ic_cdk::api::call::msg_cycles_refunded128()
+    } else {
+        payment
+    };
+    let instructions_after = ic_cdk::api::performance_counter(1);
+    let result = match outcome {
+        Ok(raw_bytes) if return_raw => {
+            // call_raw: return raw bytes, Python decodes with ic.candid_decode()
+            let py_val = basilisk_cpython::PyObjectRef::from_bytes(&raw_bytes)
+                .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none());
+            make_python_dict_result("Ok", py_val)
+        }
+        Ok(raw_bytes) => match decode_candid_response_typed(&raw_bytes, return_type_hint.as_deref()) {
+            Ok(py_val) => make_python_dict_result("Ok", py_val),
+            // Like ic-cdk's typed calls, a reply that can't be decoded is a
+            // CanisterError: the callee ran, but its reply is unusable
+            Err(e) => make_python_dict_result(
+                "Err",
+                call_error(
+                    ic_cdk::api::call::RejectionCode::CanisterError,
+                    &format!("failed to decode canister response: {}", e),
+                    false,
+                ),
+            ),
+        },
+        Err((rejection_code, msg)) => {
+            // A bounded-wait call rejected with SYS_UNKNOWN timed out (or had its
+            // response dropped): whether the callee acted on it is unknown.
+            let timed_out = bounded_wait && rejection_code == ic_cdk::api::call::RejectionCode::Unknown;
+            make_python_dict_result("Err", call_error(rejection_code, &msg, timed_out))
+        }
+    };
+    set_call_info(&result, cycles_refunded, instructions_before, instructions_after);
+    result
+}
+
+/// Attach a call's refunded cycles and instruction counters to its
+/// `CallResult` through the shim's `_set_call_info`. A fallback plain dict
+/// has no such method and is left as it is.
+fn set_call_info(
+    result: &basilisk_cpython::PyObjectRef,
+    cycles_refunded: u128,
+    instructions_before: u64,
+    instructions_after: u64,
+) {
+    let Ok(set_call_info) = result.get_attr("_set_call_info") else {
+        return;
+    };
+    let args = [
+        basilisk_cpython::PyObjectRef::from_u128(cycles_refunded),
+        basilisk_cpython::PyObjectRef::from_u64(instructions_before),
+        basilisk_cpython::PyObjectRef::from_u64(instructions_after),
+    ];
+    let Ok(args) = args.into_iter().collect::<Result<Vec<_>, _>>() else {
+        return;
+    };
+    if let Ok(args) = basilisk_cpython::PyTuple::new(args) {
+        let _ = set_call_info.call(&args.into_object(), None);
+    }
+}
+
+/// The number of a reject code, as in the IC interface spec. ic-cdk folds
+/// every code it doesn't name into `Unknown`; the only such code calls can
+/// see is SYS_UNKNOWN (6), from bounded-wait calls.
+pub fn rejection_code_number(code: ic_cdk::api::call::RejectionCode) -> u32 {
+    match code {
+        ic_cdk::api::call::RejectionCode::NoError => 0,
+        ic_cdk::api::call::RejectionCode::SysFatal => 1,
+        ic_cdk::api::call::RejectionCode::SysTransient => 2,
+        ic_cdk::api::call::RejectionCode::DestinationInvalid => 3,
+        ic_cdk::api::call::RejectionCode::CanisterReject => 4,
+        ic_cdk::api::call::RejectionCode::CanisterError => 5,
+        ic_cdk::api::call::RejectionCode::Unknown => 6,
+    }
+}
+
+/// The `RejectionCode` variant name of a reject code, as `ic.reject_code()` reports it.
+pub fn rejection_code_name(code: ic_cdk::api::call::RejectionCode) -> &'static str {
+    match code {
+        ic_cdk::api::call::RejectionCode::NoError => "NoError",
+        ic_cdk::api::call::RejectionCode::SysFatal => "SysFatal",
+        ic_cdk::api::call::RejectionCode::SysTransient => "SysTransient",
+        ic_cdk::api::call::RejectionCode::DestinationInvalid => "DestinationInvalid",
+        ic_cdk::api::call::RejectionCode::CanisterReject => "CanisterReject",
+        ic_cdk::api::call::RejectionCode::CanisterError => "CanisterError",
+        ic_cdk::api::call::RejectionCode::Unknown => "Unknown",
+    }
+}
+
+/// The IC error code (e.g. `IC0503`) a reject message mentions, if any.
+fn reject_message_error_code(message: &str) -> Option<&str> {
+    message.match_indices("IC").map(|(i, _)| &message[i..]).find_map(|rest| {
+        let code = rest.get(..6)?;
+        code[2..].bytes().all(|b| b.is_ascii_digit()).then_some(code)
+    })
+}
+
+/// The `CallResult` Err for a rejected call: an instance of the shim's
+/// `CallError` (or its subclass `CallTimeout` for a timed-out bounded-wait
+/// call) carrying the reject code, message and IC error code. Falls back to
+/// the plain "Rejection code N, message" string if the class is unavailable.
+pub fn call_error(
+    code: ic_cdk::api::call::RejectionCode,
+    message: &str,
+    timed_out: bool,
+) -> basilisk_cpython::PyObjectRef {
+    let class_name = if timed_out { "_CallTimeout" } else { "_CallError" };
+    let number = rejection_code_number(code);
+    let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() };
+    interpreter
+        .and_then(|interp| interp.get_global(class_name).ok())
+        .and_then(|cls| {
+            let error_code = match reject_message_error_code(message) {
+                Some(error_code) => basilisk_cpython::PyObjectRef::from_str(error_code).ok()?,
+                None => basilisk_cpython::PyObjectRef::none(),
+            };
+            let args = basilisk_cpython::PyTuple::new(vec![
+                basilisk_cpython::PyObjectRef::from_u64(number as u64).ok()?,
+                basilisk_cpython::PyObjectRef::from_str(rejection_code_name(code)).ok()?,
+                basilisk_cpython::PyObjectRef::from_str(message).ok()?,
+                error_code,
+            ])
+            .ok()?;
+            cls.call(&args.into_object(), None).ok()
+        })
+        .unwrap_or_else(|| {
+            basilisk_cpython::PyObjectRef::from_str(&format!("Rejection code {}, {}", number, message))
+                .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none())
+        })
+}
+
+/// The inter-canister calls a generator yielded, as `_ServiceCall`s.
+enum YieldedCalls {
+    One(basilisk_cpython::PyObjectRef),
+    /// A list or tuple of calls, made concurrently.
+    Batch(Vec<basilisk_cpython::PyObjectRef>),
+}
+
+/// Names of the call descriptors older basilisk code yields (the shim's `AsyncInfo`).
+const CALL_DESCRIPTOR_NAMES: [&str; 5] = ["call", "call_with_payment", "call_with_payment128", "call_raw", "call_raw128"];
+
+/// The calls in a yielded value: a `_ServiceCall`, an `AsyncInfo`-style call
+/// descriptor, or a non-empty list or tuple of those. `None` for anything else.
+fn yielded_calls(yielded: &basilisk_cpython::PyObjectRef) -> Result<Option<YieldedCalls>, basilisk_cpython::PyError> {
+    if let Some(call) = as_service_call(yielded)? {
+        return Ok(Some(YieldedCalls::One(call)));
+    }
+    let type_name = yielded.type_name();
+    if type_name != "list" && type_name != "tuple" {
+        return Ok(None);
+    }
+    let len = unsafe { basilisk_cpython::ffi::PySequence_Length(yielded.as_ptr()) };
+    let Some(items) = (0..len)
+        .map(|i| unsafe {
+            basilisk_cpython::PyObjectRef::from_owned(basilisk_cpython::ffi::PySequence_GetItem(yielded.as_ptr(), i))
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Ok(None);
+    };
+    let mut calls = Vec::with_capacity(items.len());
+    for item in &items {
+        match as_service_call(item)? {
+            Some(call) => calls.push(call),
+            None => return Ok(None),
+        }
+    }
+    Ok((!calls.is_empty()).then_some(YieldedCalls::Batch(calls)))
+}
+
+/// `value` as a `_ServiceCall`: itself if it is one (it has `canister_principal`),
+/// or converted by the shim if it is a call descriptor with a known `name`.
+fn as_service_call(value: &basilisk_cpython::PyObjectRef) -> Result<Option<basilisk_cpython::PyObjectRef>, basilisk_cpython::PyError> {
+    if value.has_attr("canister_principal") {
+        return Ok(Some(value.clone()));
+    }
+    let is_descriptor = value.has_attr("args")
+        && value
+            .get_attr("name")
+            .and_then(|name| name.extract_str())
+            .is_ok_and(|name| CALL_DESCRIPTOR_NAMES.contains(&name.as_str()));
+    if !is_descriptor {
+        return Ok(None);
+    }
+    let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() }
+        .ok_or_else(|| basilisk_cpython::PyError::new("SystemError", "missing python interpreter"))?;
+    let convert = interpreter.get_global("_service_call_from_descriptor")?;
+    let args = basilisk_cpython::PyTuple::new(vec![value.clone()])?;
+    convert.call(&args.into_object(), None).map(Some)
+}
+
+/// Build a Python list from `items`.
+pub fn python_list(items: Vec<basilisk_cpython::PyObjectRef>) -> basilisk_cpython::PyObjectRef {
+    unsafe {
+        let list = basilisk_cpython::ffi::PyList_New(items.len() as basilisk_cpython::ffi::Py_ssize_t);
+        for (i, item) in items.into_iter().enumerate() {
+            basilisk_cpython::ffi::PyList_SetItem(list, i as basilisk_cpython::ffi::Py_ssize_t, item.into_ptr());
+        }
+        basilisk_cpython::PyObjectRef::from_owned(list).unwrap_or_else(|| {
+            ic_cdk::trap("Failed to create list");
+        })
+    }
+}
+
 /// Extract fields from a Python _ServiceCall object and make an IC inter-canister call.
 async fn perform_service_call(
     service_call: &basilisk_cpython::PyObjectRef,
@@ -425,32 +937,70 @@ async fn perform_service_call(
             ic_cdk::trap(&format!("_ServiceCall missing method_name: {}", e));
         });
 
-    // Extract payment (default 0)
-    let payment = service_call
-        .get_attr("payment")
-        .and_then(|p| p.extract_u64())
-        .unwrap_or(0u64);
+    let payment = service_call_payment(service_call);
 
     // Encode args to Candid
     // The args field is a tuple of Python objects — encode them generically
     let args_raw = encode_service_call_args(service_call);
 
-    ic_cdk::api::call::call_raw(ic_principal, &method_name, &args_raw, payment).await
+    match service_call_timeout(service_call) {
+        Some(timeout_seconds) => {
+            crate::bounded_wait::call_raw128_bounded(ic_principal, &method_name, args_raw, payment, timeout_seconds).await
+        }
+        None => ic_cdk::api::call::call_raw128(ic_principal, &method_name, &args_raw, payment).await,
+    }
+}
+
+/// The timeout of a bounded-wait `_ServiceCall` (set by `with_timeout`), or
+/// `None` for a guaranteed-response call.
+fn service_call_timeout(service_call: &basilisk_cpython::PyObjectRef) -> Option<u32> {
+    match service_call.get_attr("timeout") {
+        Ok(timeout) if !timeout.is_none() => Some(
+            timeout
+                .extract_u64()
+                .ok()
+                .and_then(|seconds| u32::try_from(seconds).ok())
+                .filter(|&seconds| seconds > 0)
+                .unwrap_or_else(|| ic_cdk::trap("Invalid call timeout: expected a positive number of seconds")),
+        ),
+        _ => None,
+    }
+}
+
+/// The cycles a `_ServiceCall` carries (its `payment`, default 0), at full
+/// 128-bit width. Traps on a payment that is not a non-negative int below
+/// 2**128 rather than sending a different amount.
+pub fn service_call_payment(service_call: &basilisk_cpython::PyObjectRef) -> u128 {
+    match service_call.get_attr("payment") {
+        Ok(payment) if !payment.is_none() => payment.extract_u128().unwrap_or_else(|e| {
+            ic_cdk::trap(&format!("Invalid cycles payment: {}", e.to_rust_err_string()));
+        }),
+        _ => 0,
+    }
 }
 
 /// Encode the args from a _ServiceCall to Candid bytes.
 ///
 /// Encoding priority:
 /// 1. **Typed encoding** — if `_python_call_args` and `_candid_arg_type` are set,
-///    encode via `python_to_idl_value` + `to_bytes_with_types`.  This produces a
-///    Candid binary whose type table faithfully reflects the full type, including
-///    `func ... query` annotations required by the IC management canister for HTTP
-///    transform functions.
+///    encode against the compiled type.  This produces a Candid binary whose type
+///    table faithfully reflects the full type, including `func ... query`
+///    annotations required by the IC management canister for HTTP transform
+///    functions.  A type list such as `(nat64, text)` gives each argument its
+///    own type; a single type applies to every argument.
 /// 2. **Pre-encoded raw bytes** — `_raw_args` from the text-based encoding path
 ///    (via `_to_candid_text` → `candid_encode`).  Used as fallback or for
 ///    `ic.call_raw` / `ic.call_raw128`.
 /// 3. **Generic fallback** — iterate over `.args` and encode each as text.
+///
+/// An argument that none of these can encode traps rather than being left
+/// out of the call.
 pub fn encode_service_call_args(service_call: &basilisk_cpython::PyObjectRef) -> Vec<u8> {
+    let method_name = service_call
+        .get_attr("method_name")
+        .and_then(|m| m.extract_str())
+        .unwrap_or_else(|_| "<unknown>".to_string());
+
     // --- Priority 1: typed encoding via _python_call_args + _candid_arg_type ---
     if let (Ok(call_args), Ok(arg_type_obj)) = (
         service_call.get_attr("_python_call_args"),
@@ -458,30 +1008,18 @@ pub fn encode_service_call_args(service_call: &basilisk_cpython::PyObjectRef) ->
     ) {
         if let Ok(arg_type_str) = arg_type_obj.extract_str() {
             let length = unsafe { basilisk_cpython::ffi::PyObject_Length(call_args.as_ptr()) };
-            if length > 0 {
-                let type_defs = unsafe { TYPE_DEFS.as_ref() }.cloned().unwrap_or_default();
-                let mut idl_values = Vec::new();
-                let mut all_ok = true;
-                for i in 0..length {
-                    let idx = basilisk_cpython::PyObjectRef::from_i64(i as i64).unwrap();
-                    if let Ok(item) = call_args.get_item(&idx) {
-                        match python_to_idl_value(&item, &arg_type_str) {
-                            Ok(val) => idl_values.push(val),
-                            Err(_) => { all_ok = false; break; }
-                        }
-                    } else {
-                        all_ok = false;
-                        break;
-                    }
-                }
-                if all_ok && !idl_values.is_empty() {
-                    if let Some(candid_type) = type_str_to_candid_type(&arg_type_str, &type_defs) {
-                        let types: Vec<candid::types::Type> = idl_values.iter().map(|_| candid_type.clone()).collect();
-                        let idl_args = candid::IDLArgs::new(&idl_values);
-                        let env = candid::TypeEnv::new();
-                        if let Ok(bytes) = idl_args.to_bytes_with_types(&env, &types) {
-                            return bytes;
-                        }
+            let items: Option<Vec<_>> = (0..length.max(0))
+                .map(|i| {
+                    let idx = basilisk_cpython::PyObjectRef::from_i64(i as i64).ok()?;
+                    call_args.get_item(&idx).ok()
+                })
+                .collect();
+            if let Some(items) = items.filter(|items| !items.is_empty()) {
+                let types = crate::type_table::split_type_list(&arg_type_str)
+                    .unwrap_or_else(|| vec![arg_type_str.trim().to_string(); items.len()]);
+                if types.len() == items.len() && types.iter().all(|t| lookup_type(t).is_some()) {
+                    if let Ok(bytes) = encode_python_values(&items, &types) {
+                        return bytes;
                     }
                 }
             }
@@ -493,6 +1031,14 @@ pub fn encode_service_call_args(service_call: &basilisk_cpython::PyObjectRef) ->
         if let Ok(bytes) = raw_args.extract_bytes() {
             return bytes;
         }
+        if raw_args.is_none() {
+            // _ServiceCall couldn't encode its arguments as Candid text either
+            let error = service_call
+                .get_attr("_encode_error")
+                .and_then(|e| e.extract_str())
+                .unwrap_or_default();
+            ic_cdk::trap(&format!("Cannot encode the arguments of the call to '{}': {}", method_name, error));
+        }
     }
 
     // --- Priority 3: generic fallback ---
@@ -509,68 +1055,113 @@ pub fn encode_service_call_args(service_call: &basilisk_cpython::PyObjectRef) ->
     let mut idl_values = Vec::new();
     for i in 0..length {
         let idx = basilisk_cpython::PyObjectRef::from_i64(i as i64).unwrap();
-        if let Ok(item) = py_args.get_item(&idx) {
-            if let Ok(val) = python_to_idl_value(&item, "text") {
-                idl_values.push(val);
-            }
+        let encoded = py_args
+            .get_item(&idx)
+            .map_err(|e| e.to_rust_err_string())
+            .and_then(|item| python_to_idl_value(&item, "text"));
+        match encoded {
+            Ok(val) => idl_values.push(val),
+            Err(e) => ic_cdk::trap(&format!(
+                "Cannot encode argument {} of the call to '{}': {}",
+                i, method_name, e
+            )),
         }
     }
 
     let idl_args = candid::IDLArgs::new(&idl_values);
-    idl_args
-        .to_bytes()
-        .unwrap_or_else(|_| vec![0x44, 0x49, 0x44, 0x4c, 0x00, 0x00])
+    idl_args.to_bytes().unwrap_or_else(|e| {
+        ic_cdk::trap(&format!("Cannot encode the arguments of the call to '{}': {}", method_name, e));
+    })
 }
 
 /// Decode Candid response bytes to a Python object with optional type hint.
-/// If a type hint is provided, tries typed decoding first (handles opaque references).
-/// Falls back to typeless decoding, then raw bytes.
-fn decode_candid_response_typed(raw_bytes: &[u8], type_hint: Option<&str>) -> basilisk_cpython::PyObjectRef {
+/// The hint is compiled through the type table like method parameters, so a
+/// typed reply gets the same Record/Variant classes and func/service typing
+/// as decoded arguments. Falls back to typeless decoding (still reading field
+/// names from the hint's type where they match) for a reply that doesn't fit
+/// the hint. A reply over a decoding quota or that isn't valid Candid is an
+/// error.
+fn decode_candid_response_typed(
+    raw_bytes: &[u8],
+    type_hint: Option<&str>,
+) -> Result<basilisk_cpython::PyObjectRef, String> {
     if raw_bytes.is_empty() {
-        return basilisk_cpython::PyObjectRef::none();
+        return Ok(basilisk_cpython::PyObjectRef::none());
     }
 
+    let return_type = type_hint.and_then(lookup_type);
+    let preserve_opt = candid_codec::preserve_opt_default();
+    let quotas = crate::wasm_data::global_decoding_quotas();
+
     // Try typed decoding if we have a type hint
-    if let Some(hint) = type_hint {
-        if let Ok(result) = decode_with_type_hint(raw_bytes, hint) {
-            return result;
+    if let Some(return_type) = &return_type {
+        match decode_with_type(raw_bytes, return_type, preserve_opt, &quotas) {
+            Ok(result) => return Ok(result),
+            Err(ReplyDecodeError::Invalid(message)) => return Err(message),
+            // Fall through to typeless decoding
+            Err(ReplyDecodeError::Mismatch(_)) => {}
         }
-        // Fall through to typeless decoding
     }
 
     // Typeless decoding
-    match candid::IDLArgs::from_bytes(raw_bytes) {
+    match decode_idl_args(raw_bytes, None, &quotas) {
         Ok(idl_args) => {
             if let Some(first_val) = idl_args.args.into_iter().next() {
-                idl_value_to_python(&first_val).unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none())
+                Ok(idl_value_to_python_typed(&first_val, return_type.as_ref(), preserve_opt)
+                    .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none()))
             } else {
-                basilisk_cpython::PyObjectRef::none()
+                Ok(basilisk_cpython::PyObjectRef::none())
             }
         }
-        Err(_) => {
-            // If we can't decode as Candid, return raw bytes
-            basilisk_cpython::PyObjectRef::from_bytes(raw_bytes)
-                .unwrap_or_else(|_| basilisk_cpython::PyObjectRef::none())
-        }
+        Err(CandidDecodeError::QuotaExceeded(quota)) => Err(format!("Reply exceeds the {} decoding quota", quota)),
+        Err(e) => Err(format!("Malformed reply: {}", e)),
     }
 }
 
-/// Decode Candid bytes using a type hint string parsed via candid_parser.
-fn decode_with_type_hint(raw_bytes: &[u8], type_hint: &str) -> Result<basilisk_cpython::PyObjectRef, String> {
-    // Build a minimal .did source containing the type definition
-    let did_source = format!("type __Return = {}; service : {{ __m : () -> (__Return) }}", type_hint);
-    let (env, _) = candid_parser::utils::CandidSource::Text(&did_source)
-        .load()
-        .map_err(|e| format!("Failed to parse type hint '{}': {}", type_hint, e))?;
+/// Why `decode_with_type` couldn't decode a reply.
+enum ReplyDecodeError {
+    /// The reply is over a decoding quota or isn't valid Candid, so decoding
+    /// it any other way won't do better.
+    Invalid(String),
+    /// The reply doesn't fit the return type; typeless decoding may still read it.
+    Mismatch(String),
+}
 
-    let return_type = env.find_type("__Return")
-        .map_err(|e| format!("Type __Return not found: {}", e))?;
+/// Decode the first value of a reply against its compiled return type:
+/// through the wire codec, or the `IDLValue` path when the codec declines.
+fn decode_with_type(
+    raw_bytes: &[u8],
+    return_type: &candid::types::Type,
+    preserve_opt: bool,
+    quotas: &crate::wasm_data::DecodingQuotas,
+) -> Result<basilisk_cpython::PyObjectRef, ReplyDecodeError> {
+    let types = std::slice::from_ref(return_type);
+    match candid_codec::decode_args(raw_bytes, types, preserve_opt, quotas) {
+        Ok(values) => {
+            return values
+                .into_iter()
+                .next()
+                .ok_or_else(|| ReplyDecodeError::Mismatch("Empty reply".to_string()));
+        }
+        Err(candid_codec::DecodeFailure::QuotaExceeded(quota)) => {
+            return Err(ReplyDecodeError::Invalid(format!("Reply exceeds the {} decoding quota", quota)));
+        }
+        Err(candid_codec::DecodeFailure::Malformed(message)) => {
+            return Err(ReplyDecodeError::Invalid(format!("Malformed reply: {}", message)));
+        }
+        Err(candid_codec::DecodeFailure::Declined) => {}
+    }
 
-    let idl_args = candid::IDLArgs::from_bytes_with_types(raw_bytes, &env, &[return_type.clone()])
-        .map_err(|e| format!("Typed decode failed: {}", e))?;
+    let idl_args = decode_idl_args(raw_bytes, Some((type_env(), types)), quotas).map_err(|e| match e {
+        CandidDecodeError::QuotaExceeded(quota) => {
+            ReplyDecodeError::Invalid(format!("Reply exceeds the {} decoding quota", quota))
+        }
+        e => ReplyDecodeError::Mismatch(format!("Typed decode failed: {}", e)),
+    })?;
 
     if let Some(first_val) = idl_args.args.into_iter().next() {
-        idl_value_to_python(&first_val).map_err(|e| format!("IDL to Python failed: {}", e))
+        idl_value_to_python_typed(&first_val, Some(return_type), preserve_opt)
+            .map_err(|e| ReplyDecodeError::Mismatch(format!("IDL to Python failed: {}", e)))
     } else {
         Ok(basilisk_cpython::PyObjectRef::none())
     }
@@ -578,7 +1169,7 @@ fn decode_with_type_hint(raw_bytes: &[u8], type_hint: &str) -> Result<basilisk_c
 
 /// Decode Candid response bytes to a Python object (typeless).
 /// Returns the first value from the decoded IDLArgs, or None if empty.
-fn decode_candid_response_to_python(raw_bytes: &[u8]) -> basilisk_cpython::PyObjectRef {
+fn decode_candid_response_to_python(raw_bytes: &[u8]) -> Result<basilisk_cpython::PyObjectRef, String> {
     decode_candid_response_typed(raw_bytes, None)
 }
 
@@ -624,87 +1215,145 @@ fn ensure_cpython_initialized() {
     );
 }
 
-/// Decode raw Candid bytes into Python objects using dynamic IDLArgs.
-/// Uses parameter type info from method metadata to resolve record field hashes
-/// back to their original names via TYPE_DEFS.
+/// Decode raw Candid bytes into Python objects, checked against the declared
+/// parameter types. Values are coerced with Candid subtyping (see `subtyping`):
+/// missing opt arguments become None, extra arguments are ignored and numbers
+/// are widened. Parameters whose type is not in the type table are passed
+/// through as decoded.
+///
+/// When every parameter type is known, the wire codec decodes directly into
+/// Python objects; the IDLValue path below handles whatever it declines,
+/// including reporting type errors. Both stop with `QuotaExceeded` once
+/// decoding would exceed `quotas`, before any Python code sees the arguments.
 fn decode_candid_args_to_python(
     arg_bytes: &[u8],
     params: &[crate::wasm_data::ParamInfo],
-) -> Vec<basilisk_cpython::PyObjectRef> {
-    let idl_args = candid::IDLArgs::from_bytes(arg_bytes).unwrap_or_else(|e| {
-        ic_cdk::trap(&format!("Failed to decode Candid args: {}", e));
-    });
+    preserve_opt: bool,
+    quotas: &crate::wasm_data::DecodingQuotas,
+) -> Result<Vec<basilisk_cpython::PyObjectRef>, CandidDecodeError> {
+    let param_types: Option<Vec<candid::types::Type>> =
+        params.iter().map(|p| lookup_type(&p.candid_type)).collect();
+    if let Some(param_types) = &param_types {
+        match candid_codec::decode_args(arg_bytes, param_types, preserve_opt, quotas) {
+            Ok(py_args) => return Ok(py_args),
+            Err(candid_codec::DecodeFailure::QuotaExceeded(quota)) => {
+                return Err(CandidDecodeError::QuotaExceeded(quota.to_string()))
+            }
+            Err(candid_codec::DecodeFailure::Malformed(message)) => {
+                return Err(CandidDecodeError::Malformed(message.to_string()))
+            }
+            Err(candid_codec::DecodeFailure::Declined) => {}
+        }
+    }
 
-    let type_defs = unsafe { TYPE_DEFS.as_ref() };
-    let empty_map = HashMap::new();
-    let type_defs = type_defs.unwrap_or(&empty_map);
+    let idl_args = decode_idl_args(arg_bytes, None, quotas)?;
+
+    let mut values = idl_args.args.into_iter();
+    let mut py_args = Vec::with_capacity(params.len());
+    for param in params {
+        let value = values.next();
+        let expected_type = lookup_type(&param.candid_type);
+        let value = match &expected_type {
+            Some(ty) => coerce_arg(value, ty, &param.name)?,
+            None => value.ok_or_else(|| CandidDecodeError::Missing {
+                path: param.name.clone(),
+                expected: param.candid_type.clone(),
+            })?,
+        };
+        let py_arg = idl_value_to_python_typed(&value, expected_type.as_ref(), preserve_opt)
+            .unwrap_or_else(|e| {
+                ic_cdk::trap(&format!(
+                    "Failed to convert arg '{}' to Python: {}",
+                    param.name, e
+                ));
+            });
+        py_args.push(py_arg);
+    }
+    Ok(py_args)
+}
 
-    idl_args
-        .args
-        .into_iter()
-        .enumerate()
-        .map(|(i, idl_value)| {
-            let expected_type = params.get(i).map(|p| p.candid_type.as_str());
-            idl_value_to_python_typed(&idl_value, expected_type, type_defs)
-                .unwrap_or_else(|e| {
-                    let param_name = params
-                        .get(i)
-                        .map(|p| p.name.as_str())
-                        .unwrap_or("unknown");
-                    ic_cdk::trap(&format!(
-                        "Failed to convert arg '{}' to Python: {}",
-                        param_name, e
-                    ));
-                })
-        })
-        .collect()
+/// Decode a Candid message into `IDLValue`s, untyped or against `types`.
+/// Candid's decoder enforces the decoding and skipping quotas (at its own
+/// rates: untyped decoding costs more). The zero-sized quota is checked on the
+/// wire before candid allocates anything (see `candid_codec::check_zero_sized`),
+/// and again on the decoded values for a message the wire check can't walk.
+fn decode_idl_args(
+    bytes: &[u8],
+    types: Option<(&candid::types::TypeEnv, &[candid::types::Type])>,
+    quotas: &crate::wasm_data::DecodingQuotas,
+) -> Result<candid::IDLArgs, CandidDecodeError> {
+    if let Some(limit) = quotas.zero_sized {
+        if let Err(candid_codec::DecodeFailure::QuotaExceeded(quota)) = candid_codec::check_zero_sized(bytes, limit) {
+            return Err(CandidDecodeError::QuotaExceeded(quota.to_string()));
+        }
+    }
+    let config = quotas.decoder_config();
+    let idl_args = match types {
+        Some((env, types)) => candid::IDLArgs::from_bytes_with_types_with_config(bytes, env, types, &config),
+        None => candid::IDLArgs::from_bytes_with_config(bytes, &config),
+    }
+    .map_err(|e| {
+        let message = e.to_string();
+        if message.contains("Decoding cost exceeds the limit") {
+            CandidDecodeError::QuotaExceeded("decoding".to_string())
+        } else if message.contains("Skipping cost exceeds the limit") {
+            CandidDecodeError::QuotaExceeded("skipping".to_string())
+        } else {
+            CandidDecodeError::Malformed(message)
+        }
+    })?;
+    if let Some(limit) = quotas.zero_sized {
+        if idl_args.args.iter().map(count_zero_sized).sum::<usize>() > limit {
+            return Err(CandidDecodeError::QuotaExceeded("zero-sized values".to_string()));
+        }
+    }
+    Ok(idl_args)
+}
+
+/// Number of zero-sized values (null, reserved, empty records) in `value`.
+fn count_zero_sized(value: &candid::IDLValue) -> usize {
+    use candid::IDLValue;
+    match value {
+        IDLValue::Null | IDLValue::Reserved => 1,
+        IDLValue::Record(fields) if fields.is_empty() => 1,
+        IDLValue::Record(fields) => fields.iter().map(|f| count_zero_sized(&f.val)).sum(),
+        IDLValue::Variant(variant) => count_zero_sized(&variant.0.val),
+        IDLValue::Opt(inner) => count_zero_sized(inner),
+        IDLValue::Vec(items) => items.iter().map(count_zero_sized).sum(),
+        _ => 0,
+    }
 }
 
 /// Convert a candid::IDLValue to a Python object (convenience wrapper without type info).
 pub fn idl_value_to_python(
     value: &candid::IDLValue,
 ) -> Result<basilisk_cpython::PyObjectRef, String> {
-    let type_defs = unsafe { TYPE_DEFS.as_ref() };
-    let empty_map = HashMap::new();
-    let type_defs = type_defs.unwrap_or(&empty_map);
-    idl_value_to_python_typed(value, None, type_defs)
+    idl_value_to_python_typed(value, None, false)
 }
 
 /// Convert a candid::IDLValue to a Python object with type information.
 /// When `expected_type` is provided, Record/Variant field hashes are mapped back
-/// to their original names using the type definitions, and types are threaded
-/// recursively through nested structures.
-fn idl_value_to_python_typed(
+/// to their original names using the compiled type, and types are threaded
+/// recursively through nested structures. With `preserve_opt`, present `opt`
+/// values become `Opt(value)` rather than the bare value.
+pub fn idl_value_to_python_typed(
     value: &candid::IDLValue,
-    expected_type: Option<&str>,
-    type_defs: &HashMap<String, String>,
+    expected_type: Option<&candid::types::Type>,
+    preserve_opt: bool,
 ) -> Result<basilisk_cpython::PyObjectRef, String> {
     use candid::IDLValue;
+    use candid::types::internal::TypeInner;
 
-    // Resolve named type references (e.g. "ExtensionCallArgs" → "record { ... }")
-    let resolved = expected_type.map(|t| resolve_type(t, type_defs));
+    let unrolled = expected_type.and_then(unroll);
+    let resolved = unrolled.as_ref().map(|t| t.as_ref());
 
     match value {
         IDLValue::Null => Ok(basilisk_cpython::PyObjectRef::none()),
         IDLValue::Bool(b) => Ok(basilisk_cpython::PyObjectRef::from_bool(*b)),
         IDLValue::Text(s) => basilisk_cpython::PyObjectRef::from_str(s)
             .map_err(|e| e.to_rust_err_string()),
-        IDLValue::Nat(n) => {
-            let s = n.0.to_string();
-            let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() }
-                .ok_or_else(|| "missing interpreter".to_string())?;
-            interpreter
-                .eval_expression(&format!("int('{}')", s))
-                .map_err(|e| e.to_rust_err_string())
-        }
-        IDLValue::Int(n) => {
-            let s = n.0.to_string();
-            let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() }
-                .ok_or_else(|| "missing interpreter".to_string())?;
-            interpreter
-                .eval_expression(&format!("int('{}')", s))
-                .map_err(|e| e.to_rust_err_string())
-        }
+        IDLValue::Nat(n) => (&n.0).try_into_py_object().map_err(|e| e.0),
+        IDLValue::Int(n) => (&n.0).try_into_py_object().map_err(|e| e.0),
         IDLValue::Nat8(n) => basilisk_cpython::PyObjectRef::from_u64(*n as u64)
             .map_err(|e| e.to_rust_err_string()),
         IDLValue::Nat16(n) => basilisk_cpython::PyObjectRef::from_u64(*n as u64)
@@ -729,19 +1378,24 @@ fn idl_value_to_python_typed(
             .map_err(|e| e.to_rust_err_string()),
         IDLValue::None => Ok(basilisk_cpython::PyObjectRef::none()),
         IDLValue::Opt(inner) => {
-            let inner_type = resolved.and_then(|r| {
-                let r = r.trim();
-                if r.starts_with("opt ") { Some(&r[4..]) } else { None }
-            });
-            idl_value_to_python_typed(inner.as_ref(), inner_type, type_defs)
+            let inner_type = match resolved {
+                Some(TypeInner::Opt(t)) => Some(t),
+                _ => None,
+            };
+            let value = idl_value_to_python_typed(inner.as_ref(), inner_type, preserve_opt)?;
+            if preserve_opt {
+                candid_codec::wrap_opt(value).ok_or_else(|| "failed to create Opt".to_string())
+            } else {
+                Ok(value)
+            }
         }
         IDLValue::Vec(items) => {
-            let elem_type = resolved.and_then(|r| {
-                let r = r.trim();
-                if r.starts_with("vec ") { Some(&r[4..]) } else { None }
-            });
+            let elem_type = match resolved {
+                Some(TypeInner::Vec(t)) => Some(t),
+                _ => None,
+            };
             let py_items: Result<Vec<_>, _> =
-                items.iter().map(|item| idl_value_to_python_typed(item, elem_type, type_defs)).collect();
+                items.iter().map(|item| idl_value_to_python_typed(item, elem_type, preserve_opt)).collect();
             let items = py_items?;
             unsafe {
                 let list = basilisk_cpython::ffi::PyList_New(
@@ -762,12 +1416,10 @@ fn idl_value_to_python_typed(
             }
         }
         IDLValue::Record(fields) => {
-            // Parse field definitions from the expected type (if available)
-            let resolved_str = resolved.unwrap_or("");
-            let mut field_defs: Vec<(String, String)> = if let Some(inner) = strip_compound_wrapper(resolved_str, "record") {
-                parse_fields(inner)
-            } else {
-                Vec::new()
+            // Field definitions from the expected type (if available)
+            let mut field_defs: &[candid::types::Field] = match resolved {
+                Some(TypeInner::Record(defs)) => defs,
+                _ => &[],
             };
 
             // If no expected type, try to auto-detect from TYPE_DEFS by matching field hashes.
@@ -777,18 +1429,18 @@ fn idl_value_to_python_typed(
                     candid::types::Label::Id(id) | candid::types::Label::Unnamed(id) => Some(*id),
                     candid::types::Label::Named(name) => Some(candid_field_hash(name)),
                 }).collect();
-                field_defs = try_match_record_type_from_hashes(&field_ids, type_defs);
+                field_defs = try_match_record_type_from_hashes(&field_ids);
             }
 
-            // Build hash → (name, type) map for field-name resolution
-            let hash_to_field: HashMap<u32, (String, String)> = field_defs
+            // Build hash → (Python key, type) map for field-name resolution
+            let hash_to_field: HashMap<u32, (String, &candid::types::Type)> = field_defs
                 .iter()
-                .map(|(name, typ)| (candid_field_hash(name), (name.clone(), typ.clone())))
+                .map(|f| (f.id.get_id(), (label_to_py_key(&f.id), &f.ty)))
                 .collect();
 
             // Determine if this is a tuple record
             let is_tuple = if !field_defs.is_empty() {
-                is_tuple_record(&field_defs)
+                is_tuple_record(field_defs)
             } else {
                 // Fallback heuristic when no type info: consecutive numeric IDs
                 !fields.is_empty()
@@ -805,8 +1457,8 @@ fn idl_value_to_python_typed(
                     .iter()
                     .enumerate()
                     .map(|(i, f)| {
-                        let ft = field_defs.get(i).map(|(_, t)| t.as_str());
-                        idl_value_to_python_typed(&f.val, ft, type_defs)
+                        let ft = field_defs.get(i).map(|d| &d.ty);
+                        idl_value_to_python_typed(&f.val, ft, preserve_opt)
                     })
                     .collect();
                 let items = py_items?;
@@ -814,40 +1466,40 @@ fn idl_value_to_python_typed(
                     .map_err(|e| e.to_rust_err_string())?;
                 Ok(tuple.into_object())
             } else {
-                let dict = basilisk_cpython::PyDict::new()
-                    .map_err(|e| e.to_rust_err_string())?;
+                let record = candid_codec::new_record_object(field_defs)
+                    .ok_or_else(|| "Failed to create dict".to_string())?;
                 for field in fields {
-                    let (key, field_type): (String, Option<&str>) = match &field.id {
+                    let (key, field_type): (String, Option<&candid::types::Type>) = match &field.id {
                         candid::types::Label::Named(name) => {
                             let ft = hash_to_field.get(&candid_field_hash(name))
-                                .map(|(_, t)| t.as_str());
+                                .map(|(_, t)| *t);
                             // Add back keyword underscore for Python dict key
                             (add_keyword_underscore(name), ft)
                         }
                         candid::types::Label::Id(id) | candid::types::Label::Unnamed(id) => {
-                            if let Some((name, typ)) = hash_to_field.get(id) {
-                                (add_keyword_underscore(name), Some(typ.as_str()))
-                            } else if let Some(name) = reverse_field_hash(*id, type_defs) {
+                            if let Some((key, typ)) = hash_to_field.get(id) {
+                                (key.clone(), Some(*typ))
+                            } else if let Some(name) = reverse_field_hash(*id) {
                                 (add_keyword_underscore(&name), None)
                             } else {
                                 (format!("_{}", id), None)
                             }
                         }
                     };
-                    let value = idl_value_to_python_typed(&field.val, field_type, type_defs)?;
-                    dict.set_item_str(&key, &value)
+                    let value = idl_value_to_python_typed(&field.val, field_type, preserve_opt)?;
+                    let key = basilisk_cpython::PyObjectRef::from_str(&key)
+                        .map_err(|e| e.to_rust_err_string())?;
+                    record.set_item(&key, &value)
                         .map_err(|e| e.to_rust_err_string())?;
                 }
-                Ok(dict.into_object())
+                Ok(record)
             }
         }
         IDLValue::Variant(variant) => {
-            // Parse case definitions from the expected type
-            let resolved_str = resolved.unwrap_or("");
-            let mut case_defs: Vec<(String, String)> = if let Some(inner) = strip_compound_wrapper(resolved_str, "variant") {
-                parse_fields(inner)
-            } else {
-                Vec::new()
+            // Case definitions from the expected type
+            let mut case_defs: &[candid::types::Field] = match resolved {
+                Some(TypeInner::Variant(defs)) => defs,
+                _ => &[],
             };
 
             // If no expected type, try to auto-detect from TYPE_DEFS by matching case hashes.
@@ -856,39 +1508,39 @@ fn idl_value_to_python_typed(
                     candid::types::Label::Id(id) | candid::types::Label::Unnamed(id) => vec![*id],
                     candid::types::Label::Named(name) => vec![candid_field_hash(name)],
                 };
-                case_defs = try_match_variant_type_from_hashes(&case_ids, type_defs);
+                case_defs = try_match_variant_type_from_hashes(&case_ids);
             }
 
-            let hash_to_case: HashMap<u32, (String, String)> = case_defs
-                .into_iter()
-                .map(|(name, typ)| (candid_field_hash(&name), (name, typ)))
+            let hash_to_case: HashMap<u32, (String, &candid::types::Type)> = case_defs
+                .iter()
+                .map(|f| (f.id.get_id(), (label_to_py_key(&f.id), &f.ty)))
                 .collect();
 
-            let dict = basilisk_cpython::PyDict::new()
-                .map_err(|e| e.to_rust_err_string())?;
+            let dict = candid_codec::new_record_object(case_defs)
+                .ok_or_else(|| "Failed to create dict".to_string())?;
             let (key, case_type) = match &variant.0.id {
                 candid::types::Label::Named(name) => {
                     let ct = hash_to_case.get(&candid_field_hash(name))
-                        .map(|(_, t)| t.clone());
+                        .map(|(_, t)| *t);
                     // Add back keyword underscore for Python dict key
                     (add_keyword_underscore(name), ct)
                 }
                 candid::types::Label::Id(id) | candid::types::Label::Unnamed(id) => {
-                    if let Some((name, typ)) = hash_to_case.get(id) {
-                        (add_keyword_underscore(name), Some(typ.clone()))
-                    } else if let Some(name) = reverse_field_hash(*id, type_defs) {
+                    if let Some((key, typ)) = hash_to_case.get(id) {
+                        (key.clone(), Some(*typ))
+                    } else if let Some(name) = reverse_field_hash(*id) {
                         (add_keyword_underscore(&name), None)
                     } else {
                         (format!("_{}", id), None)
                     }
                 }
             };
-            let value = idl_value_to_python_typed(
-                &variant.0.val, case_type.as_deref(), type_defs
-            )?;
-            dict.set_item_str(&key, &value)
+            let value = idl_value_to_python_typed(&variant.0.val, case_type, preserve_opt)?;
+            let key = basilisk_cpython::PyObjectRef::from_str(&key)
                 .map_err(|e| e.to_rust_err_string())?;
-            Ok(dict.into_object())
+            dict.set_item(&key, &value)
+                .map_err(|e| e.to_rust_err_string())?;
+            Ok(dict)
         }
         IDLValue::Principal(p) => {
             let text = p.to_text();
@@ -926,15 +1578,19 @@ fn idl_value_to_python_typed(
             if let Ok(service_cls) = interpreter.get_global("Service") {
                 let ctor_args = basilisk_cpython::PyTuple::new(vec![principal_obj.clone()])
                     .map_err(|e| e.to_rust_err_string())?;
-                service_cls.call(&ctor_args.into_object(), None)
-                    .map_err(|e| e.to_rust_err_string())
+                let service = service_cls.call(&ctor_args.into_object(), None)
+                    .map_err(|e| e.to_rust_err_string())?;
+                if let Some(TypeInner::Service(methods)) = resolved {
+                    set_service_method_types(&service, methods)?;
+                }
+                Ok(service)
             } else {
                 // Fallback: return Principal if Service class not available
                 Ok(principal_obj)
             }
         }
         IDLValue::Func(p, method) => {
-            // Func → Python tuple (Principal, method_name_str)
+            // Func → Python FuncRef(Principal, method_name_str, signature), a tuple subclass
             let text = p.to_text();
             let principal_class = unsafe { crate::PRINCIPAL_CLASS_OPTION.as_ref() }
                 .ok_or_else(|| "Principal class not cached".to_string())?;
@@ -950,9 +1606,26 @@ fn idl_value_to_python_typed(
                 .map_err(|e| e.to_rust_err_string())?;
             let method_py = basilisk_cpython::PyObjectRef::from_str(method)
                 .map_err(|e| e.to_rust_err_string())?;
-            let tuple = basilisk_cpython::PyTuple::new(vec![principal_py, method_py])
-                .map_err(|e| e.to_rust_err_string())?;
-            Ok(tuple.into_object())
+            let signature = match resolved {
+                Some(TypeInner::Func(func)) => basilisk_cpython::PyObjectRef::from_str(&func.to_string())
+                    .map_err(|e| e.to_rust_err_string())?,
+                _ => basilisk_cpython::PyObjectRef::none(),
+            };
+            let interpreter = unsafe { crate::INTERPRETER_OPTION.as_mut() }
+                .ok_or_else(|| "missing interpreter".to_string())?;
+            match interpreter.get_global("FuncRef") {
+                Ok(func_ref_cls) => {
+                    let ctor_args = basilisk_cpython::PyTuple::new(vec![principal_py, method_py, signature])
+                        .map_err(|e| e.to_rust_err_string())?;
+                    func_ref_cls.call(&ctor_args.into_object(), None)
+                        .map_err(|e| e.to_rust_err_string())
+                }
+                Err(_) => {
+                    let tuple = basilisk_cpython::PyTuple::new(vec![principal_py, method_py])
+                        .map_err(|e| e.to_rust_err_string())?;
+                    Ok(tuple.into_object())
+                }
+            }
         }
         _ => {
             // Fallback: convert to string representation
@@ -963,320 +1636,233 @@ fn idl_value_to_python_typed(
     }
 }
 
-/// Convert a Python object to Candid bytes based on the expected return type.
+/// Give a decoded service reference (or a declared `Service` subclass) the
+/// `_arg_types` / `_return_types` maps of its Candid type, so calls through it
+/// encode and decode against that type. A method taking several arguments gets
+/// a type list such as `(nat64, text)`; only single-result methods get a
+/// return type, matching what reply decoding can use. Entries the target
+/// already has are kept.
+fn set_service_method_types(
+    service: &basilisk_cpython::PyObjectRef,
+    methods: &[(String, candid::types::Type)],
+) -> Result<(), String> {
+    let arg_types = basilisk_cpython::PyDict::new().map_err(|e| e.to_rust_err_string())?;
+    let return_types = basilisk_cpython::PyDict::new().map_err(|e| e.to_rust_err_string())?;
+    for (name, ty) in methods {
+        let Some(ty) = unroll(ty) else { continue };
+        let candid::types::TypeInner::Func(func) = ty.as_ref() else {
+            continue;
+        };
+        let arg_text = match func.args.as_slice() {
+            [] => None,
+            [arg] => Some(type_text(arg)),
+            args => Some(format!("({})", args.iter().map(type_text).collect::<Vec<_>>().join(", "))),
+        };
+        if let Some(arg_text) = arg_text {
+            let text = basilisk_cpython::PyObjectRef::from_str(&arg_text).map_err(|e| e.to_rust_err_string())?;
+            arg_types.set_item_str(name, &text).map_err(|e| e.to_rust_err_string())?;
+        }
+        if let [ret] = func.rets.as_slice() {
+            let text = basilisk_cpython::PyObjectRef::from_str(&type_text(ret)).map_err(|e| e.to_rust_err_string())?;
+            return_types.set_item_str(name, &text).map_err(|e| e.to_rust_err_string())?;
+        }
+    }
+    for (attr, types) in [("_arg_types", arg_types), ("_return_types", return_types)] {
+        let types = types.into_object();
+        if let Ok(declared) = service.get_attr(attr) {
+            let update = types.get_attr("update").map_err(|e| e.to_rust_err_string())?;
+            let update_args = basilisk_cpython::PyTuple::new(vec![declared]).map_err(|e| e.to_rust_err_string())?;
+            update.call(&update_args.into_object(), None).map_err(|e| e.to_rust_err_string())?;
+        }
+        service.set_attr(attr, &types).map_err(|e| e.to_rust_err_string())?;
+    }
+    Ok(())
+}
+
+/// A type string for `ty` that compiles back to it: the name of the named
+/// type it is (keeping its Record/Variant class), else its Candid text.
+fn type_text(ty: &candid::types::Type) -> String {
+    named_types()
+        .iter()
+        .find(|(_, named)| std::rc::Rc::ptr_eq(&named.0, &ty.0))
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| ty.to_string())
+}
+
+/// Convert a Python return value to Candid reply bytes for the declared return types.
+/// A method with several return types returns a tuple (or list) holding one value
+/// per return type; a method with none encodes an empty reply.
 pub fn encode_python_to_candid(
     py_result: &basilisk_cpython::PyObjectRef,
-    return_type: &str,
+    return_types: &[String],
 ) -> Vec<u8> {
-    // For void (empty return type), encode zero Candid args.
+    // For void (no return types), encode zero Candid args.
     // The .did declares () -> () so the agent expects no return values.
-    if return_type.is_empty() {
+    if return_types.is_empty() {
         let idl_args = candid::IDLArgs::new(&[]);
         return idl_args.to_bytes().unwrap_or_else(|e| {
             ic_cdk::trap(&format!("Failed to encode void Candid result: {}", e));
         });
     }
 
-    let idl_value = python_to_idl_value(py_result, return_type).unwrap_or_else(|e| {
-        let repr = py_result.str_repr().unwrap_or_else(|_| "<repr failed>".to_string());
-        ic_cdk::trap(&format!("Failed to convert Python result to Candid: {} (return_type='{}', repr='{}')", e, return_type, repr));
-    });
+    let values = if return_types.len() == 1 {
+        vec![py_result.clone()]
+    } else {
+        split_return_values(py_result, return_types.len()).unwrap_or_else(|e| {
+            ic_cdk::trap(&format!(
+                "Failed to convert Python result to Candid: {} (return_types='({})')",
+                e,
+                return_types.join(", ")
+            ));
+        })
+    };
 
-    let idl_args = candid::IDLArgs::new(&[idl_value]);
+    encode_python_values(&values, return_types).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!("Failed to convert Python result to Candid: {}", e));
+    })
+}
 
-    // Try typed serialization first — this correctly handles vecs of mixed
-    // variants by providing the full type to annotate_type which fixes
-    // variant indices.
-    let type_defs = unsafe { TYPE_DEFS.as_ref() }.map(|m| m.clone()).unwrap_or_default();
-    if let Some(candid_type) = type_str_to_candid_type(return_type, &type_defs) {
-        let env = candid::TypeEnv::new();
-        if let Ok(bytes) = idl_args.to_bytes_with_types(&env, &[candid_type]) {
-            return bytes;
+/// Encode Python values as a Candid message, one value per type string in
+/// `types`. The wire codec handles values of known types; the IDLValue path
+/// covers the rest and reports conversion errors.
+fn encode_python_values(
+    values: &[basilisk_cpython::PyObjectRef],
+    types: &[String],
+) -> Result<Vec<u8>, String> {
+    let candid_types: Option<Vec<candid::types::Type>> =
+        types.iter().map(|t| lookup_type(t)).collect();
+    if let Some(types) = &candid_types {
+        if let Some(bytes) = candid_codec::encode_args(values, types) {
+            return Ok(bytes);
         }
     }
 
-    // Fallback to untyped serialization (works for simple types / single variants)
-    idl_args.to_bytes().unwrap_or_else(|e| {
-        ic_cdk::trap(&format!("Failed to encode Candid result: {}", e));
-    })
-}
-
-/// Convert a Candid type string into a `candid::types::Type` for typed serialization.
-/// Returns None for types that cannot be represented (func, service, recursive, etc.).
-fn type_str_to_candid_type(
-    type_str: &str,
-    type_defs: &HashMap<String, String>,
-) -> Option<candid::types::Type> {
-    type_str_to_candid_type_inner(type_str, type_defs, 0)
-}
-
-fn type_str_to_candid_type_inner(
-    type_str: &str,
-    type_defs: &HashMap<String, String>,
-    depth: usize,
-) -> Option<candid::types::Type> {
-    if depth > 16 {
-        return None; // Prevent infinite recursion on recursive types
-    }
-    let resolved = resolve_type(type_str, type_defs);
-    use candid::types::internal::{TypeInner, Field};
-    let ty: candid::types::Type = match resolved {
-        "" | "null" => TypeInner::Null.into(),
-        "bool" => TypeInner::Bool.into(),
-        "nat" => TypeInner::Nat.into(),
-        "int" => TypeInner::Int.into(),
-        "nat8" => TypeInner::Nat8.into(),
-        "nat16" => TypeInner::Nat16.into(),
-        "nat32" => TypeInner::Nat32.into(),
-        "nat64" => TypeInner::Nat64.into(),
-        "int8" => TypeInner::Int8.into(),
-        "int16" => TypeInner::Int16.into(),
-        "int32" => TypeInner::Int32.into(),
-        "int64" => TypeInner::Int64.into(),
-        "float32" => TypeInner::Float32.into(),
-        "float64" => TypeInner::Float64.into(),
-        "text" => TypeInner::Text.into(),
-        "blob" => TypeInner::Vec(TypeInner::Nat8.into()).into(),
-        "principal" => TypeInner::Principal.into(),
-        "empty" => TypeInner::Empty.into(),
-        "reserved" => TypeInner::Reserved.into(),
-        s if s.starts_with("opt ") => {
-            let inner = type_str_to_candid_type_inner(&s[4..], type_defs, depth + 1)?;
-            TypeInner::Opt(inner).into()
-        }
-        s if s.starts_with("vec ") => {
-            let inner = type_str_to_candid_type_inner(&s[4..], type_defs, depth + 1)?;
-            TypeInner::Vec(inner).into()
-        }
-        s => {
-            if let Some(inner) = strip_compound_wrapper(s, "record") {
-                let fields = parse_fields(inner);
-                let mut candid_fields: Vec<Field> = fields.iter().map(|(name, ty)| {
-                    Field {
-                        id: std::rc::Rc::new(candid_name_to_label(name)),
-                        ty: type_str_to_candid_type_inner(ty, type_defs, depth + 1)
-                            .unwrap_or_else(|| TypeInner::Reserved.into()),
-                    }
-                }).collect();
-                candid_fields.sort_by(|a, b| a.id.get_id().cmp(&b.id.get_id()));
-                TypeInner::Record(candid_fields).into()
-            } else if let Some(inner) = strip_compound_wrapper(s, "variant") {
-                let cases = parse_fields(inner);
-                let mut candid_fields: Vec<Field> = cases.iter().map(|(name, ty)| {
-                    Field {
-                        id: std::rc::Rc::new(candid_name_to_label(name)),
-                        ty: type_str_to_candid_type_inner(ty, type_defs, depth + 1)
-                            .unwrap_or_else(|| TypeInner::Null.into()),
-                    }
-                }).collect();
-                candid_fields.sort_by(|a, b| a.id.get_id().cmp(&b.id.get_id()));
-                TypeInner::Variant(candid_fields).into()
-            } else if let Some(inner) = strip_compound_wrapper(s, "service") {
-                // Parse service { method_name : sig; ... }
-                let method_strs = parse_fields(inner);
-                let mut methods: Vec<(String, candid::types::Type)> = Vec::new();
-                for (name, sig) in &method_strs {
-                    if let Some(func_ty) = parse_func_signature(sig, type_defs, depth + 1) {
-                        methods.push((name.clone(), func_ty));
-                    }
-                }
-                TypeInner::Service(methods).into()
-            } else if s.starts_with("func ") {
-                let sig = &s[5..]; // strip "func "
-                return parse_func_signature(sig, type_defs, depth + 1);
-            } else {
-                return None; // Unknown type
+    // Slow path: convert through IDLValue (also reports conversion errors).
+    let idl_values = values
+        .iter()
+        .zip(types)
+        .map(|(value, type_str)| {
+            match lookup_type(type_str) {
+                Some(ty) => python_to_idl_value_inner(value, &ty),
+                None => python_to_idl_value(value, type_str),
             }
-        }
-    };
-    Some(ty)
-}
+            .map_err(|e| {
+                let repr = value.str_repr().unwrap_or_else(|_| "<repr failed>".to_string());
+                format!("{} (type='{}', repr='{}')", e, type_str, repr)
+            })
+        })
+        .collect::<Result<Vec<candid::IDLValue>, String>>()?;
 
-// ─── Candid type string parsing ──────────────────────────────────────────────
+    let idl_args = candid::IDLArgs::new(&idl_values);
 
-/// Resolve a Candid type string, expanding named type references via TYPE_DEFS.
-fn resolve_type<'a>(candid_type: &'a str, type_defs: &'a HashMap<String, String>) -> &'a str {
-    let trimmed = candid_type.trim();
-    // If it's a named type reference, look it up
-    if !trimmed.starts_with("record")
-        && !trimmed.starts_with("variant")
-        && !trimmed.starts_with("opt ")
-        && !trimmed.starts_with("vec ")
-        && !trimmed.contains(' ')
-        && !trimmed.contains('{')
-    {
-        if let Some(def) = type_defs.get(trimmed) {
-            return def.as_str();
+    // Try typed serialization first — this correctly handles vecs of mixed
+    // variants by providing the full type to annotate_type which fixes
+    // variant indices.
+    if let Some(types) = candid_types {
+        if let Ok(bytes) = idl_args.to_bytes_with_types(type_env(), &types) {
+            return Ok(bytes);
         }
     }
-    trimmed
-}
-
-/// Parse field definitions from inside `record { ... }` or `variant { ... }`.
-/// Returns a list of (field_name, field_type_string) pairs.
-fn parse_fields(inner: &str) -> Vec<(String, String)> {
-    let mut fields = Vec::new();
-    let mut depth = 0;
-    let mut current = String::new();
 
-    for ch in inner.chars() {
-        match ch {
-            '{' => {
-                depth += 1;
-                current.push(ch);
-            }
-            '}' => {
-                depth -= 1;
-                current.push(ch);
-            }
-            ';' if depth == 0 => {
-                let trimmed = current.trim().to_string();
-                if !trimmed.is_empty() {
-                    fields.push(trimmed);
-                }
-                current.clear();
-            }
-            _ => {
-                current.push(ch);
-            }
-        }
-    }
-    let trimmed = current.trim().to_string();
-    if !trimmed.is_empty() {
-        fields.push(trimmed);
-    }
+    // Fallback to untyped serialization (works for simple types / single variants)
+    idl_args.to_bytes().map_err(|e| format!("encoding failed: {}", e))
+}
 
-    fields
+/// Decode a Candid message into one Python value per type string in `types`,
+/// the same way method arguments are decoded: with Candid subtyping, the
+/// canister-wide decoding quotas and the default opt decoding mode.
+fn decode_python_values(
+    bytes: &[u8],
+    types: &[String],
+) -> Result<Vec<basilisk_cpython::PyObjectRef>, CandidDecodeError> {
+    let params: Vec<crate::wasm_data::ParamInfo> = types
         .iter()
-        .map(|f| {
-            if let Some(colon_pos) = f.find(':') {
-                let raw_name = f[..colon_pos].trim();
-                // Strip surrounding double quotes from Candid reserved-word field names
-                let name = if raw_name.starts_with('"') && raw_name.ends_with('"') && raw_name.len() >= 2 {
-                    raw_name[1..raw_name.len() - 1].to_string()
-                } else {
-                    raw_name.to_string()
-                };
-                let type_str = f[colon_pos + 1..].trim().to_string();
-                (name, type_str)
-            } else {
-                (f.clone(), "null".to_string())
-            }
+        .enumerate()
+        .map(|(i, candid_type)| crate::wasm_data::ParamInfo {
+            name: format!("arg{}", i),
+            candid_type: candid_type.clone(),
         })
-        .collect()
+        .collect();
+    decode_candid_args_to_python(
+        bytes,
+        &params,
+        candid_codec::preserve_opt_default(),
+        &crate::wasm_data::global_decoding_quotas(),
+    )
 }
 
-/// Find the position of the closing ')' that matches the '(' at `open_pos`.
-fn find_matching_paren(s: &str, open_pos: usize) -> Option<usize> {
-    let bytes = s.as_bytes();
-    let mut depth = 0i32;
-    for i in open_pos..bytes.len() {
-        match bytes[i] {
-            b'(' => depth += 1,
-            b')' => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
-                }
-            }
-            _ => {}
-        }
-    }
-    None
-}
-
-/// Parse a comma-separated list of Candid type strings into a Vec<Type>.
-fn parse_type_list(
-    s: &str,
-    type_defs: &HashMap<String, String>,
-    depth: usize,
-) -> Vec<candid::types::Type> {
-    let trimmed = s.trim();
-    if trimmed.is_empty() {
-        return Vec::new();
-    }
-    let mut types = Vec::new();
-    let mut current = String::new();
-    let mut paren_depth = 0i32;
-    let mut brace_depth = 0i32;
-    for ch in trimmed.chars() {
-        match ch {
-            '(' => { paren_depth += 1; current.push(ch); }
-            ')' => { paren_depth -= 1; current.push(ch); }
-            '{' => { brace_depth += 1; current.push(ch); }
-            '}' => { brace_depth -= 1; current.push(ch); }
-            ',' if paren_depth == 0 && brace_depth == 0 => {
-                let t = current.trim().to_string();
-                if !t.is_empty() {
-                    if let Some(ty) = type_str_to_candid_type_inner(&t, type_defs, depth + 1) {
-                        types.push(ty);
-                    }
-                }
-                current.clear();
-            }
-            _ => { current.push(ch); }
-        }
+/// `ic.candid_encode_values`: encode `values` as a Candid message of `types`,
+/// which is a single type (and `values` its value) or a parenthesised type
+/// list (and `values` a tuple or list with one value per type).
+pub fn encode_candid_values(
+    types: &str,
+    values: &basilisk_cpython::PyObjectRef,
+) -> Result<Vec<u8>, String> {
+    match crate::type_table::split_type_list(types) {
+        Some(types) => encode_python_values(&split_return_values(values, types.len())?, &types),
+        None => encode_python_values(std::slice::from_ref(values), &[types.trim().to_string()]),
     }
-    let t = current.trim().to_string();
-    if !t.is_empty() {
-        if let Some(ty) = type_str_to_candid_type_inner(&t, type_defs, depth + 1) {
-            types.push(ty);
+}
+
+/// `ic.candid_decode_values`: decode a Candid message of `types` into Python
+/// values, a single value for a single type and a tuple for a type list.
+pub fn decode_candid_values(
+    bytes: &[u8],
+    types: &str,
+) -> Result<basilisk_cpython::PyObjectRef, String> {
+    match crate::type_table::split_type_list(types) {
+        Some(types) => {
+            let values = decode_python_values(bytes, &types).map_err(|e| e.to_string())?;
+            basilisk_cpython::PyTuple::new(values)
+                .map(|tuple| tuple.into_object())
+                .map_err(|e| e.to_rust_err_string())
+        }
+        None => {
+            let mut values = decode_python_values(bytes, &[types.trim().to_string()])
+                .map_err(|e| e.to_string())?;
+            Ok(values.remove(0))
         }
     }
-    types
 }
 
-/// Parse a Candid function signature string like `(text, nat64) -> (bool) query`
-/// into a `candid::types::Type` (TypeInner::Func).
-fn parse_func_signature(
-    sig: &str,
-    type_defs: &HashMap<String, String>,
-    depth: usize,
-) -> Option<candid::types::Type> {
-    use candid::types::internal::TypeInner;
-    use candid::types::{Function, FuncMode};
-
-    let sig = sig.trim();
-    let args_start = sig.find('(')?;
-    let args_end = find_matching_paren(sig, args_start)?;
-    let args_str = &sig[args_start + 1..args_end];
-
-    let rest = sig[args_end + 1..].trim();
-    let rest = rest.strip_prefix("->")?;
-    let rest = rest.trim();
-
-    let rets_start = rest.find('(')?;
-    let rets_end = find_matching_paren(rest, rets_start)?;
-    let rets_str = &rest[rets_start + 1..rets_end];
-
-    let mode_str = rest[rets_end + 1..].trim();
-    let modes = match mode_str {
-        "query" => vec![FuncMode::Query],
-        "composite_query" => vec![FuncMode::CompositeQuery],
-        "oneway" => vec![FuncMode::Oneway],
-        _ => vec![],
-    };
-
-    let args = parse_type_list(args_str, type_defs, depth);
-    let rets = parse_type_list(rets_str, type_defs, depth);
-
-    Some(TypeInner::Func(Function { modes, args, rets }).into())
+/// The first type in a `candid_encode_values`/`candid_decode_values` type
+/// string (a single type or a type list) that doesn't compile, if any. These
+/// raise rather than fall back to untyped encoding or decoding, which would
+/// hide a typo in the type.
+pub fn unknown_value_type(types: &str) -> Option<String> {
+    let types = crate::type_table::split_type_list(types).unwrap_or_else(|| vec![types.trim().to_string()]);
+    types.into_iter().find(|t| lookup_type(t).is_none())
 }
 
-/// Strip the outer `record { ... }` or `variant { ... }` wrapper and return the inner content.
-fn strip_compound_wrapper<'a>(type_str: &'a str, keyword: &str) -> Option<&'a str> {
-    let trimmed = type_str.trim();
-    if !trimmed.starts_with(keyword) {
-        return None;
+/// Split a multi-value return (a tuple or list) into its `count` values.
+fn split_return_values(
+    py_result: &basilisk_cpython::PyObjectRef,
+    count: usize,
+) -> Result<Vec<basilisk_cpython::PyObjectRef>, String> {
+    let type_name = py_result.type_name();
+    if type_name != "tuple" && type_name != "list" {
+        return Err(format!("expected a tuple of {} values, got {}", count, type_name));
     }
-    let rest = trimmed[keyword.len()..].trim();
-    if rest.starts_with('{') && rest.ends_with('}') {
-        Some(&rest[1..rest.len() - 1])
-    } else {
-        None
+    let len = unsafe { basilisk_cpython::ffi::PySequence_Length(py_result.as_ptr()) };
+    if len != count as basilisk_cpython::ffi::Py_ssize_t {
+        return Err(format!("expected {} values, got {}", count, len));
     }
+    (0..count)
+        .map(|i| unsafe {
+            let item = basilisk_cpython::ffi::PySequence_GetItem(
+                py_result.as_ptr(),
+                i as basilisk_cpython::ffi::Py_ssize_t,
+            );
+            if item.is_null() {
+                basilisk_cpython::ffi::PyErr_Clear();
+            }
+            basilisk_cpython::PyObjectRef::from_owned(item)
+                .ok_or_else(|| format!("null return value {}", i))
+        })
+        .collect()
 }
 
+
 const PY_KEYWORDS: &[&str] = &[
     "False", "None", "True", "and", "as", "assert", "async", "await",
     "break", "class", "continue", "def", "del", "elif", "else",
@@ -1317,7 +1903,7 @@ fn add_keyword_underscore(name: &str) -> String {
 /// Convert a field name to a Candid Label.
 /// Names matching `_N_` pattern or plain numeric names become numeric Label::Id(N).
 /// Trailing underscores on Python keywords are stripped (e.g. "False_" → "False").
-fn field_name_to_label(name: &str) -> candid::types::Label {
+pub fn field_name_to_label(name: &str) -> candid::types::Label {
     if name.starts_with('_') && name.ends_with('_') && name.len() > 2 {
         if let Ok(id) = name[1..name.len() - 1].parse::<u32>() {
             return candid::types::Label::Id(id);
@@ -1331,89 +1917,72 @@ fn field_name_to_label(name: &str) -> candid::types::Label {
     candid::types::Label::Named(clean.to_string())
 }
 
-/// Convert a Candid field name (already in Candid form) to a Label.
-/// Unlike field_name_to_label, this does NOT strip keyword underscores because
-/// the name has already been converted from Python form to Candid form.
-fn candid_name_to_label(name: &str) -> candid::types::Label {
-    if name.starts_with('_') && name.ends_with('_') && name.len() > 2 {
-        if let Ok(id) = name[1..name.len() - 1].parse::<u32>() {
-            return candid::types::Label::Id(id);
-        }
-    }
-    if let Ok(id) = name.parse::<u32>() {
-        return candid::types::Label::Id(id);
-    }
-    candid::types::Label::Named(name.to_string())
-}
-
-/// Check if a record type string represents a tuple (all fields are positional: _0_, _1_, ...).
-fn is_tuple_record(fields: &[(String, String)]) -> bool {
+/// Check if a record type represents a tuple (all fields are positional: _0_, _1_, ...).
+pub fn is_tuple_record(fields: &[candid::types::Field]) -> bool {
     if fields.is_empty() {
         return false;
     }
-    fields.iter().enumerate().all(|(i, (name, _))| {
-        // Check _N_ pattern (e.g. _0_, _1_)
-        let is_underscore = name.starts_with('_')
-            && name.ends_with('_')
-            && name.len() > 2
-            && name[1..name.len() - 1].parse::<u32>().ok() == Some(i as u32);
-        // Also check plain numeric field names (e.g. "0", "1")
-        let is_plain_numeric = name.parse::<u32>().ok() == Some(i as u32);
-        is_underscore || is_plain_numeric
+    fields.iter().enumerate().all(|(i, f)| {
+        matches!(f.id.as_ref(),
+            candid::types::Label::Id(id) | candid::types::Label::Unnamed(id)
+            if *id == i as u32
+        )
     })
 }
 
+/// Python dict key for a record field / variant case label.
+/// Named labels get the keyword underscore back (e.g. "from" → "from_"),
+/// numeric labels use the `_N_` form accepted by `field_name_to_label`.
+pub fn label_to_py_key(label: &candid::types::Label) -> String {
+    match label {
+        candid::types::Label::Named(name) => add_keyword_underscore(name),
+        candid::types::Label::Id(id) | candid::types::Label::Unnamed(id) => format!("_{}_", id),
+    }
+}
+
 /// Try to find a matching record type from TYPE_DEFS by matching field hashes.
 /// Used when decoding cross-canister responses where the expected type is unknown.
-/// Returns parsed field definitions if a match is found.
-fn try_match_record_type_from_hashes(
-    field_ids: &[u32],
-    type_defs: &HashMap<String, String>,
-) -> Vec<(String, String)> {
+/// Returns the compiled field definitions if a match is found.
+fn try_match_record_type_from_hashes(field_ids: &[u32]) -> &'static [candid::types::Field] {
+    use candid::types::internal::TypeInner;
+
     if field_ids.is_empty() {
-        return Vec::new();
+        return &[];
     }
     let mut target: Vec<u32> = field_ids.to_vec();
     target.sort();
 
-    for (_name, def) in type_defs.iter() {
-        let resolved = def.trim();
-        if let Some(inner) = strip_compound_wrapper(resolved, "record") {
-            let fields = parse_fields(inner);
+    for (_name, ty) in named_types() {
+        if let TypeInner::Record(fields) = ty.as_ref() {
             if fields.len() != target.len() {
                 continue;
             }
-            let mut hashes: Vec<u32> = fields.iter().map(|(n, _)| candid_field_hash(n)).collect();
-            hashes.sort();
-            if hashes == target {
+            // Compiled record fields are already sorted by hash
+            if fields.iter().map(|f| f.id.get_id()).eq(target.iter().copied()) {
                 return fields;
             }
         }
     }
-    Vec::new()
+    &[]
 }
 
 /// Try to find a matching variant type from TYPE_DEFS by matching case hashes.
-fn try_match_variant_type_from_hashes(
-    case_ids: &[u32],
-    type_defs: &HashMap<String, String>,
-) -> Vec<(String, String)> {
+fn try_match_variant_type_from_hashes(case_ids: &[u32]) -> &'static [candid::types::Field] {
+    use candid::types::internal::TypeInner;
+
     if case_ids.is_empty() {
-        return Vec::new();
+        return &[];
     }
     // For variant, we only have ONE case at a time, but the type def has all cases.
     // We need to find a variant type that contains ALL of the provided case IDs.
-    for (_name, def) in type_defs.iter() {
-        let resolved = def.trim();
-        if let Some(inner) = strip_compound_wrapper(resolved, "variant") {
-            let cases = parse_fields(inner);
-            let case_hashes: Vec<u32> = cases.iter().map(|(n, _)| candid_field_hash(n)).collect();
-            if case_ids.iter().all(|id| case_hashes.contains(id)) {
+    for (_name, ty) in named_types() {
+        if let TypeInner::Variant(cases) = ty.as_ref() {
+            if case_ids.iter().all(|id| cases.iter().any(|c| c.id.get_id() == *id)) {
                 return cases;
             }
         }
     }
-    Vec::new()
+    &[]
 }
 
 /// Compute the Candid field-name hash (same algorithm as the candid crate).
@@ -1425,183 +1994,119 @@ fn candid_field_hash(name: &str) -> u32 {
     hash
 }
 
-/// Well-known IC management canister and common field/variant names.
-/// These cover create_canister, canister_status, install_code, http_request, etc.
-const WELL_KNOWN_FIELD_NAMES: &[&str] = &[
-    // Management canister
-    "canister_id", "controllers", "compute_allocation", "memory_allocation",
-    "freezing_threshold", "settings", "mode", "wasm_module", "arg", "amount",
-    "status", "module_hash", "memory_size", "cycles", "idle_cycles_burned_per_day",
-    "sender_canister_version", "reserved_cycles", "reserved_cycles_limit",
-    "install", "reinstall", "upgrade", "running", "stopping", "stopped",
-    "query_stats", "num_calls_total", "num_instructions_total",
-    "request_payload_bytes_total", "response_payload_bytes_total",
-    // HTTP outcalls
-    "url", "method", "headers", "body", "max_response_bytes", "transform",
-    "name", "value", "function", "context",
-    // Threshold ECDSA / Schnorr
-    "public_key", "chain_code", "signature", "key_id", "curve",
-    "derivation_path", "message_hash", "message",
-    "secp256k1", "ed25519",
-    // Bitcoin
-    "network", "address", "min_confirmations", "utxos", "outpoint",
-    "txid", "vout", "height", "block_hash", "tip_block_hash", "tip_height",
-    "next_page", "satoshi", "mainnet", "testnet", "regtest",
-    // Ledger
-    "memo", "from", "to", "fee", "timestamp", "created_at_time",
-    "e8s", "amount_e8s", "block_index",
-    // Common
-    "Ok", "Err", "err", "ok", "result", "data", "error", "code", "description",
-    "id", "key", "token", "principal", "caller", "time", "source", "target",
-];
-
-/// Try to reverse a Candid field hash to its original name.
-/// First checks TYPE_DEFS field names, then falls back to well-known names.
-fn reverse_field_hash(hash: u32, type_defs: &HashMap<String, String>) -> Option<String> {
-    // Check all field names from TYPE_DEFS
-    for (_name, def) in type_defs.iter() {
-        let resolved = def.trim();
-        // Check record fields
-        if let Some(inner) = strip_compound_wrapper(resolved, "record") {
-            for (field_name, _) in parse_fields(inner) {
-                if candid_field_hash(&field_name) == hash {
-                    return Some(field_name);
-                }
-            }
-        }
-        // Check variant cases
-        if let Some(inner) = strip_compound_wrapper(resolved, "variant") {
-            for (case_name, _) in parse_fields(inner) {
-                if candid_field_hash(&case_name) == hash {
-                    return Some(case_name);
-                }
-            }
-        }
-    }
-    // Check well-known field names
-    for &name in WELL_KNOWN_FIELD_NAMES {
-        if candid_field_hash(name) == hash {
-            return Some(name.to_string());
-        }
-    }
-    None
+/// Try to reverse a Candid field hash to its original name
+/// using the field-name registry built from every compiled type.
+fn reverse_field_hash(hash: u32) -> Option<String> {
+    field_name(hash).map(str::to_string)
 }
 
 // ─── Python → Candid conversion ─────────────────────────────────────────────
 
 /// Convert a Python object to a candid::IDLValue based on the type string.
-/// Supports named type references (resolved via TYPE_DEFS), records, variants,
-/// tuples, opt, vec, and all primitive types.
+/// The type string is looked up in the compiled type table; strings that do
+/// not compile to a known Candid type fall back to text.
 fn python_to_idl_value(
     obj: &basilisk_cpython::PyObjectRef,
     candid_type: &str,
 ) -> Result<candid::IDLValue, String> {
-    let type_defs = unsafe { TYPE_DEFS.as_ref() };
-    let empty_map = HashMap::new();
-    let type_defs = type_defs.unwrap_or(&empty_map);
-
-    python_to_idl_value_inner(obj, candid_type, type_defs)
+    match lookup_type(candid_type) {
+        Some(ty) => python_to_idl_value_inner(obj, &ty),
+        None => {
+            let s = obj.extract_str().map_err(|e| {
+                format!("Cannot convert Python object to Candid type '{}': {}", candid_type, e.to_rust_err_string())
+            })?;
+            Ok(candid::IDLValue::Text(s))
+        }
+    }
 }
 
-fn python_to_idl_value_inner(
+/// Convert a Python object to a candid::IDLValue based on a compiled type.
+/// Supports records, variants, tuples, opt, vec, and all primitive types.
+pub fn python_to_idl_value_inner(
     obj: &basilisk_cpython::PyObjectRef,
-    candid_type: &str,
-    type_defs: &HashMap<String, String>,
+    candid_type: &candid::types::Type,
 ) -> Result<candid::IDLValue, String> {
-    // First resolve named type references
-    let resolved = resolve_type(candid_type, type_defs);
-
-    // Check for record { ... }
-    if let Some(inner) = strip_compound_wrapper(resolved, "record") {
-        let fields = parse_fields(inner);
-        let is_tuple = is_tuple_record(&fields);
+    use candid::types::internal::TypeInner;
 
-        if is_tuple {
-            // Python tuple → positional Candid record
-            return python_tuple_to_record(obj, &fields, type_defs);
-        } else {
-            // Python dict → named Candid record
-            return python_dict_to_record(obj, &fields, type_defs);
+    match candid_type.as_ref() {
+        TypeInner::Record(fields) => {
+            if is_tuple_record(fields) {
+                // Python tuple → positional Candid record
+                python_tuple_to_record(obj, fields)
+            } else {
+                // Python dict → named Candid record
+                python_dict_to_record(obj, fields)
+            }
         }
-    }
-
-    // Check for variant { ... }
-    if let Some(inner) = strip_compound_wrapper(resolved, "variant") {
-        let cases = parse_fields(inner);
-        let result = python_dict_to_variant(obj, &cases, type_defs);
-        if result.is_err() {
-            let repr = obj.str_repr().unwrap_or_else(|_| "<repr failed>".to_string());
-            return Err(format!("{} [candid_type='{}', resolved='{}', repr='{}']",
-                result.unwrap_err(), candid_type, resolved, repr));
+        TypeInner::Variant(cases) => {
+            python_dict_to_variant(obj, cases).map_err(|e| {
+                let repr = obj.str_repr().unwrap_or_else(|_| "<repr failed>".to_string());
+                format!("{} [candid_type='{}', repr='{}']", e, candid_type, repr)
+            })
         }
-        return result;
-    }
-
-    // Primitive and compound types
-    match resolved {
-        "" | "null" => Ok(candid::IDLValue::Null),
-        "bool" => Ok(candid::IDLValue::Bool(obj.extract_bool())),
-        "text" => {
+        TypeInner::Null => Ok(candid::IDLValue::Null),
+        TypeInner::Bool => Ok(candid::IDLValue::Bool(obj.extract_bool())),
+        TypeInner::Text => {
             let s = obj.extract_str().map_err(|e| e.to_rust_err_string())?;
             Ok(candid::IDLValue::Text(s))
         }
-        "nat" => {
-            let s = obj.str_repr().map_err(|e| e.to_rust_err_string())?;
-            let n: num_bigint::BigUint =
-                s.parse().map_err(|e| format!("parse nat: {}", e))?;
+        TypeInner::Nat => {
+            let n = num_bigint::BigUint::try_from_py_object(obj.clone())
+                .map_err(|e| format!("nat: {}", e.to_rust_err_string()))?;
             Ok(candid::IDLValue::Nat(candid::Nat(n)))
         }
-        "int" => {
-            let s = obj.str_repr().map_err(|e| e.to_rust_err_string())?;
-            let n: num_bigint::BigInt =
-                s.parse().map_err(|e| format!("parse int: {}", e))?;
+        TypeInner::Int => {
+            let n = num_bigint::BigInt::try_from_py_object(obj.clone())
+                .map_err(|e| format!("int: {}", e.to_rust_err_string()))?;
             Ok(candid::IDLValue::Int(candid::Int(n)))
         }
-        "nat8" => {
+        TypeInner::Nat8 => {
             let v = obj.extract_u64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Nat8(v as u8))
+            let v = u8::try_from(v).map_err(|_| format!("nat8: {} is out of range", v))?;
+            Ok(candid::IDLValue::Nat8(v))
         }
-        "nat16" => {
+        TypeInner::Nat16 => {
             let v = obj.extract_u64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Nat16(v as u16))
+            let v = u16::try_from(v).map_err(|_| format!("nat16: {} is out of range", v))?;
+            Ok(candid::IDLValue::Nat16(v))
         }
-        "nat32" => {
+        TypeInner::Nat32 => {
             let v = obj.extract_u64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Nat32(v as u32))
+            let v = u32::try_from(v).map_err(|_| format!("nat32: {} is out of range", v))?;
+            Ok(candid::IDLValue::Nat32(v))
         }
-        "nat64" => {
+        TypeInner::Nat64 => {
             let v = obj.extract_u64().map_err(|e| e.to_rust_err_string())?;
             Ok(candid::IDLValue::Nat64(v))
         }
-        "int8" => {
+        TypeInner::Int8 => {
             let v = obj.extract_i64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Int8(v as i8))
+            let v = i8::try_from(v).map_err(|_| format!("int8: {} is out of range", v))?;
+            Ok(candid::IDLValue::Int8(v))
         }
-        "int16" => {
+        TypeInner::Int16 => {
             let v = obj.extract_i64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Int16(v as i16))
+            let v = i16::try_from(v).map_err(|_| format!("int16: {} is out of range", v))?;
+            Ok(candid::IDLValue::Int16(v))
         }
-        "int32" => {
+        TypeInner::Int32 => {
             let v = obj.extract_i64().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Int32(v as i32))
+            let v = i32::try_from(v).map_err(|_| format!("int32: {} is out of range", v))?;
+            Ok(candid::IDLValue::Int32(v))
         }
-        "int64" => {
+        TypeInner::Int64 => {
             let v = obj.extract_i64().map_err(|e| e.to_rust_err_string())?;
             Ok(candid::IDLValue::Int64(v))
         }
-        "float32" => {
+        TypeInner::Float32 => {
             let v = obj.extract_f64().map_err(|e| e.to_rust_err_string())?;
             Ok(candid::IDLValue::Float32(v as f32))
         }
-        "float64" => {
+        TypeInner::Float64 => {
             let v = obj.extract_f64().map_err(|e| e.to_rust_err_string())?;
             Ok(candid::IDLValue::Float64(v))
         }
-        "blob" => {
-            let bytes = obj.extract_bytes().map_err(|e| e.to_rust_err_string())?;
-            Ok(candid::IDLValue::Blob(bytes))
-        }
-        "principal" => {
+        TypeInner::Principal => {
             let to_str = obj.get_attr("to_str").map_err(|e| e.to_rust_err_string())?;
             let args = basilisk_cpython::PyTuple::empty()
                 .map_err(|e| e.to_rust_err_string())?;
@@ -1613,9 +2118,9 @@ fn python_to_idl_value_inner(
                 .map_err(|e| format!("invalid principal: {}", e))?;
             Ok(candid::IDLValue::Principal(p))
         }
-        "empty" => Ok(candid::IDLValue::Null),
-        "reserved" => Ok(candid::IDLValue::Reserved),
-        other if other.starts_with("func ") => {
+        TypeInner::Empty => Ok(candid::IDLValue::Null),
+        TypeInner::Reserved => Ok(candid::IDLValue::Reserved),
+        TypeInner::Func(_) => {
             // Func type: Python tuple (Principal, method_name_str)
             unsafe {
                 let len = basilisk_cpython::ffi::PySequence_Length(obj.as_ptr());
@@ -1647,17 +2152,26 @@ fn python_to_idl_value_inner(
                 Ok(candid::IDLValue::Func(p, method_name))
             }
         }
-        other if other.starts_with("opt ") => {
-            if obj.is_none() {
-                Ok(candid::IDLValue::None)
-            } else {
-                let inner_type = &other[4..];
-                let inner = python_to_idl_value_inner(obj, inner_type, type_defs)?;
-                Ok(candid::IDLValue::Opt(Box::new(inner)))
+        TypeInner::Opt(inner_type) => {
+            // `Opt(value)` is one explicit layer; other values are wrapped implicitly
+            let payload = match candid_codec::opt_payload(obj) {
+                Some(p) if p.is_none() && !candid_codec::opt_holds_none(inner_type) => {
+                    return Ok(candid::IDLValue::None)
+                }
+                Some(p) => p,
+                None if obj.is_none() => return Ok(candid::IDLValue::None),
+                None => obj.clone(),
+            };
+            let inner = python_to_idl_value_inner(&payload, inner_type)?;
+            Ok(candid::IDLValue::Opt(Box::new(inner)))
+        }
+        TypeInner::Vec(inner_type) => {
+            // blob: accept bytes-like objects directly
+            if matches!(inner_type.as_ref(), TypeInner::Nat8) {
+                if let Ok(bytes) = obj.extract_bytes() {
+                    return Ok(candid::IDLValue::Blob(bytes));
+                }
             }
-        }
-        other if other.starts_with("vec ") => {
-            let inner_type = &other[4..];
             unsafe {
                 let len = basilisk_cpython::ffi::PySequence_Length(obj.as_ptr());
                 if len < 0 {
@@ -1671,12 +2185,12 @@ fn python_to_idl_value_inner(
                     }
                     let py_obj = basilisk_cpython::PyObjectRef::from_owned(item)
                         .ok_or_else(|| "null item".to_string())?;
-                    items.push(python_to_idl_value_inner(&py_obj, inner_type, type_defs)?);
+                    items.push(python_to_idl_value_inner(&py_obj, inner_type)?);
                 }
                 Ok(candid::IDLValue::Vec(items))
             }
         }
-        other if other.starts_with("service ") => {
+        TypeInner::Service(_) => {
             // Service type: extract principal text.
             // Order matters: Service.__getattr__ intercepts non-underscore names (returns
             // _ServiceMethodProxy), so we must check _principal/canister_id BEFORE to_str.
@@ -1716,10 +2230,14 @@ fn python_to_idl_value_inner(
                 .map_err(|e| format!("service principal: {}", e))?;
             Ok(candid::IDLValue::Service(p))
         }
+        TypeInner::Var(name) => {
+            let ty = unroll(candid_type).ok_or_else(|| format!("unbound recursive type '{}'", name))?;
+            python_to_idl_value_inner(obj, &ty)
+        }
         _ => {
             // Fallback: try str_repr for display, then extract_str for actual string
             let s = obj.extract_str().map_err(|e| {
-                format!("Cannot convert Python object to Candid type '{}': {}", resolved, e.to_rust_err_string())
+                format!("Cannot convert Python object to Candid type '{}': {}", candid_type, e.to_rust_err_string())
             })?;
             Ok(candid::IDLValue::Text(s))
         }
@@ -1729,14 +2247,17 @@ fn python_to_idl_value_inner(
 /// Convert a Python dict to a Candid Record.
 fn python_dict_to_record(
     obj: &basilisk_cpython::PyObjectRef,
-    fields: &[(String, String)],
-    type_defs: &HashMap<String, String>,
+    fields: &[candid::types::Field],
 ) -> Result<candid::IDLValue, String> {
     let mut idl_fields = Vec::with_capacity(fields.len());
 
-    for (field_name, field_type) in fields {
-        // Try the Candid field name first, then the Python keyword-escaped version
-        let py_key = add_keyword_underscore(field_name);
+    for field in fields {
+        let field_name = match field.id.as_ref() {
+            candid::types::Label::Named(name) => name.clone(),
+            label => format!("_{}_", label.get_id()),
+        };
+        // Try the Python keyword-escaped name first, then the Candid field name
+        let py_key = label_to_py_key(&field.id);
         let key = basilisk_cpython::PyObjectRef::from_str(&py_key)
             .map_err(|e| e.to_rust_err_string())?;
         let value = unsafe {
@@ -1744,7 +2265,7 @@ fn python_dict_to_record(
             if item.is_null() {
                 basilisk_cpython::ffi::PyErr_Clear();
                 // Fallback: try the original Candid field name
-                let key2 = basilisk_cpython::PyObjectRef::from_str(field_name)
+                let key2 = basilisk_cpython::PyObjectRef::from_str(&field_name)
                     .map_err(|e| e.to_rust_err_string())?;
                 let item2 = basilisk_cpython::ffi::PyObject_GetItem(obj.as_ptr(), key2.as_ptr());
                 if item2.is_null() {
@@ -1759,9 +2280,9 @@ fn python_dict_to_record(
             }
         };
 
-        let idl_val = python_to_idl_value_inner(&value, field_type, type_defs)?;
+        let idl_val = python_to_idl_value_inner(&value, &field.ty)?;
         idl_fields.push(candid::types::value::IDLField {
-            id: candid_name_to_label(field_name),
+            id: field.id.as_ref().clone(),
             val: idl_val,
         });
     }
@@ -1774,12 +2295,11 @@ fn python_dict_to_record(
 /// Convert a Python tuple to a positional Candid Record (tuple encoding).
 fn python_tuple_to_record(
     obj: &basilisk_cpython::PyObjectRef,
-    fields: &[(String, String)],
-    type_defs: &HashMap<String, String>,
+    fields: &[candid::types::Field],
 ) -> Result<candid::IDLValue, String> {
     let mut idl_fields = Vec::with_capacity(fields.len());
 
-    for (i, (_field_name, field_type)) in fields.iter().enumerate() {
+    for (i, field) in fields.iter().enumerate() {
         let value = unsafe {
             let item = basilisk_cpython::ffi::PySequence_GetItem(
                 obj.as_ptr(),
@@ -1797,7 +2317,7 @@ fn python_tuple_to_record(
                 .ok_or_else(|| format!("null tuple element {}", i))?
         };
 
-        let idl_val = python_to_idl_value_inner(&value, field_type, type_defs)?;
+        let idl_val = python_to_idl_value_inner(&value, &field.ty)?;
         idl_fields.push(candid::types::value::IDLField {
             id: candid::types::Label::Id(i as u32),
             val: idl_val,
@@ -1810,15 +2330,14 @@ fn python_tuple_to_record(
 /// Convert a Python dict (single key) to a Candid Variant.
 fn python_dict_to_variant(
     obj: &basilisk_cpython::PyObjectRef,
-    cases: &[(String, String)],
-    type_defs: &HashMap<String, String>,
+    cases: &[candid::types::Field],
 ) -> Result<candid::IDLValue, String> {
     // Get the keys of the dict to find which variant case is active
     let keys = unsafe {
         let keys_obj = basilisk_cpython::ffi::PyDict_Keys(obj.as_ptr());
         if keys_obj.is_null() {
             let repr = obj.str_repr().unwrap_or_else(|_| "<repr failed>".to_string());
-            let case_names: Vec<&str> = cases.iter().map(|(n,_)| n.as_str()).collect();
+            let case_names: Vec<String> = cases.iter().map(|c| c.id.to_string()).collect();
             return Err(format!("Variant value is not a dict (repr='{}', cases={:?})", repr, case_names));
         }
         let len = basilisk_cpython::ffi::PyList_Size(keys_obj);
@@ -1837,14 +2356,14 @@ fn python_dict_to_variant(
         key_str
     };
 
-    // Find the matching case and its type
-    // Strip keyword underscore from Python key (e.g. "False_" -> "False")
-    let clean_key = strip_keyword_underscore(&keys);
-    let (case_idx, case_type) = cases
+    // Find the matching case and its type.
+    // Strip keyword underscore from Python key (e.g. "False_" -> "False"),
+    // but also accept the key verbatim.
+    let clean_id = field_name_to_label(&keys).get_id();
+    let raw_id = candid_name_to_label(&keys).get_id();
+    let case = cases
         .iter()
-        .enumerate()
-        .find(|(_, (name, _))| *name == clean_key || *name == keys)
-        .map(|(i, (_, t))| (i, t.as_str()))
+        .find(|c| c.id.get_id() == clean_id || c.id.get_id() == raw_id)
         .ok_or_else(|| format!("Unknown variant case '{}'", keys))?;
 
     // Get the value for this case
@@ -1860,14 +2379,14 @@ fn python_dict_to_variant(
             .ok_or_else(|| "null variant value".to_string())?
     };
 
-    let idl_val = if value.is_none() && case_type == "null" {
+    let idl_val = if value.is_none() && matches!(case.ty.as_ref(), candid::types::internal::TypeInner::Null) {
         candid::IDLValue::Null
     } else {
-        python_to_idl_value_inner(&value, case_type, type_defs)?
+        python_to_idl_value_inner(&value, &case.ty)?
     };
 
     let field = candid::types::value::IDLField {
-        id: candid_name_to_label(&clean_key),
+        id: case.id.as_ref().clone(),
         val: idl_val,
     };
 
@@ -60,8 +60,9 @@ pub fn cpython_full_init(python_code: &str) {
              \x20\x20\x20\x20_P = getattr(_bmod, 'Principal', None)\n\
              \x20\x20\x20\x20_mgmt = _sys.modules.get('basilisk.canisters.management')\n\
              \x20\x20\x20\x20if _mgmt and _P:\n\
-             \x20\x20\x20\x20\x20\x20\x20\x20_mc = _S(_P.from_str('aaaaa-aa'))\n\
-             \x20\x20\x20\x20\x20\x20\x20\x20_mc._return_types = {\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20class _MgmtSvc(_S):\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20pass\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20_MgmtSvc._return_types = {\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'create_canister': 'record { canister_id : principal }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'canister_status': 'record { status : variant { running : null; stopping : null; stopped : null }; settings : record { controllers : vec principal; compute_allocation : nat; memory_allocation : nat; freezing_threshold : nat }; module_hash : opt blob; memory_size : nat; cycles : nat }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'raw_rand': 'blob',\n\
@@ -72,7 +73,7 @@ pub fn cpython_full_init(python_code: &str) {
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'bitcoin_get_utxos': 'record { next_page : opt blob; tip_block_hash : blob; tip_height : nat32; utxos : vec record { height : nat32; outpoint : record { txid : blob; vout : nat32 }; value : nat64 } }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'bitcoin_get_current_fee_percentiles': 'vec nat64',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20}\n\
-             \x20\x20\x20\x20\x20\x20\x20\x20_mc._arg_types = {\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20_MgmtSvc._arg_types = {\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'create_canister': 'record { settings : opt record { controllers : opt vec principal; compute_allocation : opt nat; memory_allocation : opt nat; freezing_threshold : opt nat } }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'update_settings': 'record { canister_id : principal; settings : record { controllers : opt vec principal; compute_allocation : opt nat; memory_allocation : opt nat; freezing_threshold : opt nat } }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'install_code': 'record { mode : variant { install : null; reinstall : null; upgrade : null }; canister_id : principal; wasm_module : blob; arg : blob }',\n\
@@ -92,8 +93,8 @@ pub fn cpython_full_init(python_code: &str) {
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'ecdsa_public_key': 'record { canister_id : opt principal; derivation_path : vec blob; key_id : record { curve : variant { secp256k1 : null }; name : text } }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20'sign_with_ecdsa': 'record { message_hash : blob; derivation_path : vec blob; key_id : record { curve : variant { secp256k1 : null }; name : text } }',\n\
              \x20\x20\x20\x20\x20\x20\x20\x20}\n\
-             \x20\x20\x20\x20\x20\x20\x20\x20_mgmt.management_canister = _mc\n\
-             \x20\x20\x20\x20\x20\x20\x20\x20_mgmt.ManagementCanister = _S\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20_mgmt.management_canister = _MgmtSvc(_P.from_str('aaaaa-aa'))\n\
+             \x20\x20\x20\x20\x20\x20\x20\x20_mgmt.ManagementCanister = _MgmtSvc\n\
              \x20\x20\x20\x20_ledger = _sys.modules.get('basilisk.canisters.ledger')\n\
              \x20\x20\x20\x20if _ledger:\n\
              \x20\x20\x20\x20\x20\x20\x20\x20class _LedgerSvc(_S):\n\
@@ -193,21 +194,57 @@ _mod.blob = bytes
 _mod.null = None
 _mod.void = None
 class _Opt(_Sub):
-    """Opt wrapper: Opt[T] for type annotations, Opt(value) for runtime opt encoding."""
+    """Opt wrapper: Opt[T] for type annotations, Opt(value) for one explicit opt
+    layer when encoding, and the decoded form of present opt values when opt
+    nesting is preserved (None stays the absent value)."""
     __slots__ = ('value',)
     def __init__(self, value=None):
         self.value = value
+    def __eq__(self, other):
+        return isinstance(other, _Opt) and self.value == other.value
+    def __hash__(self):
+        return hash((_Opt, self.value))
+    def __repr__(self):
+        return f"Opt({self.value!r})"
 _mod.Opt = _Opt
 _mod.Vec = list
 class _Record(dict):
+    """Record base: a dict whose fields can also be read and set as attributes."""
     def __class_getitem__(cls, params): return cls
     def __init_subclass__(cls, **kw): pass
+    def __getattr__(self, name):
+        try:
+            return self[name]
+        except KeyError:
+            raise AttributeError(name) from None
+    def __setattr__(self, name, value):
+        self[name] = value
 class _Variant(dict):
+    """Variant base: a single-key dict. The active case reads as an attribute;
+    the other declared cases read as None."""
     def __class_getitem__(cls, params): return cls
     def __init_subclass__(cls, **kw): pass
+    def __getattr__(self, name):
+        if name in self:
+            return self[name]
+        if not name.startswith('__') and any(name in c.__dict__.get('__annotations__', {}) for c in type(self).__mro__):
+            return None
+        raise AttributeError(name)
 _mod.Record = _Record
 _mod.Variant = _Variant
+
+def _basilisk_candid_classes():
+    """User Record/Variant subclasses by class name; decoded values of the
+    Candid named type with that name are built as their instances."""
+    classes = {}
+    bases = [_Record, _Variant]
+    while bases:
+        for sub in bases.pop().__subclasses__():
+            classes.setdefault(sub.__name__, sub)
+            bases.append(sub)
+    return classes
 _mod.Tuple = tuple
+_mod.Returns = tuple
 _mod.reserved = _Sub
 _mod.empty = _Sub
 _mod.Async = _Sub
@@ -224,6 +261,7 @@ _mod.RejectionCode = int
 _mod.FuncTuple = tuple
 _mod.StableGrowResult = int
 _mod.Stable64GrowResult = int
+_mod.BackgroundError = dict
 
 # === Decorators ===
 def _dec(_func=None, **kw):
@@ -238,10 +276,64 @@ _mod.post_upgrade = lambda f: f
 _mod.inspect_message = _dec
 _mod.composite_query = _dec
 _mod.canister = lambda c: c
-_mod.service_method = lambda f: f
+def _service_method(func):
+    # Older Service classes decorate their methods with @service_method, whose
+    # calls build "call" descriptors
+    def method(self, *args):
+        # Service's __getattr__ answers any public name, so look in the instance dict
+        canister_id = vars(self).get("canister_id") or getattr(self, "_principal", None)
+        return AsyncInfo("call", [canister_id, func.__qualname__, *args])
+    return method
+_mod.service_method = _service_method
 _mod.service_query = lambda f: f
 _mod.service_update = lambda f: f
 
+# === Exceptions ===
+class Reject(Exception):
+    pass
+_mod.Reject = Reject
+# Dispatch looks Reject up under this name, which user code won't shadow
+_Reject = Reject
+
+class LockBusy(Reject):
+    """Raised by Lock.acquire() when another call holds the lock. Uncaught, it
+    rejects the call like Reject."""
+_mod.LockBusy = LockBusy
+
+class Lock:
+    """A named lock that a method can hold across yields and awaits, so other
+    messages that run in between can't interleave with its state changes:
+
+        with Lock("balances"):
+            yield ledger.transfer(...)
+
+    Acquiring never waits: if another call holds the lock, acquire() raises
+    LockBusy. The lock is released on leaving the with block, by release(), or
+    when the method holding it finishes, raises or is dropped after a trap."""
+    def __init__(self, key):
+        self.key = str(key)
+    @classmethod
+    def for_caller(cls, scope=""):
+        """A lock per caller: one call at a time from each principal."""
+        return cls(f"{scope}@{_basilisk_ic.caller()}")
+    def acquire(self):
+        if not _basilisk_ic.lock_acquire(self.key):
+            raise LockBusy(f"Lock '{self.key}' is held by another call; try again later")
+        return self
+    def release(self):
+        return _basilisk_ic.lock_release(self.key)
+    @property
+    def locked(self):
+        return _basilisk_ic.lock_held(self.key)
+    def __enter__(self):
+        return self.acquire()
+    def __exit__(self, *exc_info):
+        self.release()
+        return False
+    def __repr__(self):
+        return f"Lock({self.key!r})"
+_mod.Lock = Lock
+
 # === Principal class ===
 class Principal:
     _CRC_TABLE = None
@@ -439,6 +531,25 @@ class CallResult(dict):
             return None
     def __setattr__(self, name, value):
         self[name] = value
+    @property
+    def timed_out(self):
+        return isinstance(self.get('Err'), CallTimeout)
+    # Set by the runtime on the result of each awaited call; kept outside the
+    # dict so the result still reads (and encodes) as just Ok or Err.
+    def _set_call_info(self, cycles_refunded, instructions_before, instructions_after):
+        object.__setattr__(self, '_call_info', (cycles_refunded, instructions_before, instructions_after))
+    @property
+    def cycles_refunded(self):
+        """Cycles refunded to this canister for this call (all of them if it was never sent)."""
+        return vars(self).get('_call_info', (None, None, None))[0]
+    @property
+    def instructions_before(self):
+        """The call context instruction counter (ic.performance_counter(1)) when the call was made."""
+        return vars(self).get('_call_info', (None, None, None))[1]
+    @property
+    def instructions_after(self):
+        """The call context instruction counter when the response was handed back."""
+        return vars(self).get('_call_info', (None, None, None))[2]
     @staticmethod
     def from_dict(d):
         cr = CallResult()
@@ -446,6 +557,56 @@ class CallResult(dict):
         return cr
 _mod.CallResult = CallResult
 
+class CallError(str):
+    """The Err of a rejected service call. It reads as the string
+    "Rejection code N, message", and carries the parts separately:
+    reject_code is the RejectionCode variant (as ic.reject_code() returns it,
+    e.g. {"CanisterReject": None}), code_number its number, reject_message the
+    message, and error_code the IC error code (e.g. "IC0503") or None."""
+    def __new__(cls, code_number, code_name, reject_message, error_code=None):
+        self = super().__new__(cls, f"Rejection code {code_number}, {reject_message}")
+        self.code_number = code_number
+        self.reject_code = {code_name: None}
+        self.reject_message = reject_message
+        self.error_code = error_code
+        return self
+    @property
+    def code_name(self):
+        return next(iter(self.reject_code))
+    def __repr__(self):
+        return f"{type(self).__name__}({self.code_name}, {self.reject_message!r})"
+_mod.CallError = CallError
+
+class CallTimeout(CallError):
+    """The Err of a bounded-wait call (see _ServiceCall.with_timeout) that got
+    no response in time. The callee may or may not have acted on the call."""
+_mod.CallTimeout = CallTimeout
+# Dispatch builds call errors from these names, which user code won't shadow
+_CallError = CallError
+_CallTimeout = CallTimeout
+
+class CallRejected(Exception):
+    """Raised at the yield (or await) of a rejected service call in methods
+    declared with raise_on_reject=True. result is the call's CallResult, error
+    its CallError; for a list of calls, results holds every CallResult."""
+    def __init__(self, result, results=None):
+        self.result = result
+        self.error = result['Err']
+        self.results = results
+        super().__init__(str(self.error))
+    @property
+    def reject_code(self):
+        return self.error.reject_code
+    @property
+    def reject_message(self):
+        return self.error.reject_message
+    @property
+    def timed_out(self):
+        return isinstance(self.error, CallTimeout)
+_mod.CallRejected = CallRejected
+# Dispatch raises CallRejected under this name, which user code won't shadow
+_CallRejected = CallRejected
+
 # === Stable structures (Rust-backed via _basilisk_ic) ===
 # All data structures persist directly in stable memory via ic-stable-structures.
 # No pre_upgrade/post_upgrade serialization needed.
@@ -968,6 +1129,48 @@ class _UpdateType:
 def Func(sig):
     return _FuncType(sig)
 
+def _func_signature_parts(sig):
+    """Split func signature text `(A, B) -> (C) query` into ([A, B], [C])."""
+    groups, parts, depth, start = [], [], 0, 0
+    for i, ch in enumerate(sig or ''):
+        if ch in '({':
+            if depth == 0 and ch == '(':
+                parts, start = [], i + 1
+            depth += 1
+        elif ch in ')}':
+            depth -= 1
+            if depth == 0 and ch == ')':
+                if sig[start:i].strip():
+                    parts.append(sig[start:i].strip())
+                groups.append(parts)
+        elif ch == ',' and depth == 1:
+            parts.append(sig[start:i].strip())
+            start = i + 1
+    groups += [[], []]
+    return groups[0], groups[1]
+
+class FuncRef(tuple):
+    """A Candid func value: (principal, method), with the func type's signature
+    text when it is known. Calling it makes a call to the referenced method."""
+    def __new__(cls, principal, method, signature=None):
+        ref = tuple.__new__(cls, (principal, method))
+        ref.signature = signature
+        return ref
+    @property
+    def principal(self):
+        return self[0]
+    @property
+    def method(self):
+        return self[1]
+    def __call__(self, *args):
+        params, results = _func_signature_parts(self.signature)
+        call = _ServiceCall(self[0], self[1], args, arg_type=params[0] if len(params) == 1 else None)
+        if len(results) == 1:
+            call._return_candid_type = results[0]
+        return call
+    def __repr__(self):
+        return f"FuncRef({self[0]!r}, {self[1]!r})"
+
 def _parse_record_fields(th):
     """Parse a Candid record type hint into {field_name: field_type}."""
     if not th or not th.strip().startswith('record'):
@@ -1028,9 +1231,10 @@ def _to_candid_text(v, type_hint=None):
         escaped = "".join("\\{:02x}".format(b) for b in v)
         return 'blob "' + escaped + '"'
     if isinstance(v, _Opt):
-        if v.value is None:
-            return 'null'
         inner_hint = type_hint[3:].strip() if type_hint and type_hint.strip().startswith('opt') else None
+        # Opt(None) is `opt null` only where the payload type can be null
+        if v.value is None and (inner_hint or '').split(' ')[0] not in ('null', 'opt', 'reserved'):
+            return 'null'
         return f'opt {_to_candid_text(v.value, inner_hint)}'
     if isinstance(v, Principal):
         return f'principal "{v.to_str()}"'
@@ -1065,13 +1269,18 @@ class _ServiceCall:
         self._candid_arg_type = arg_type
         # Also encode via text path as fallback
         if call_args:
-            parts = [_to_candid_text(a, arg_type) for a in call_args]
+            # A type list such as "(nat64, text)" types each argument separately,
+            # which only the typed path uses
+            hint = None if arg_type and arg_type.lstrip().startswith('(') else arg_type
+            parts = [_to_candid_text(a, hint) for a in call_args]
             candid_text = f"({', '.join(parts)})"
             try:
                 raw_args = _basilisk_ic.candid_encode(candid_text)
-            except Exception:
-                # Fallback: empty args
-                raw_args = b'DIDL\x00\x00'
+            except Exception as e:
+                # Without typed encoding the call can't be made; the Rust side
+                # traps with this error rather than send the call without them
+                raw_args = None
+                self._encode_error = str(e)
         else:
             raw_args = b'DIDL\x00\x00'
         # Keep original attributes for drive_generator / perform_service_call in method_dispatch.rs
@@ -1079,11 +1288,12 @@ class _ServiceCall:
         self.method_name = method_name
         self._raw_args = raw_args
         self.payment = payment
-        # Also set .name and .args for async_handler.rs call_raw protocol
+        # Also set .name and .args, the shape of a call_raw AsyncInfo descriptor
         principal_text = str(canister_principal) if not isinstance(canister_principal, str) else canister_principal
         self.name = "call_raw"
         self.args = [principal_text, method_name, raw_args, payment]
         self._payment = payment
+        self.timeout = None
     def with_cycles(self, cycles):
         self.payment = cycles
         self.args[3] = cycles
@@ -1095,8 +1305,48 @@ class _ServiceCall:
         self.args[3] = cycles
         self._payment = cycles
         return self
+    def with_timeout(self, seconds):
+        # Bounded-wait call: give up on the response after `seconds` (the IC
+        # caps this at 300), with a CallTimeout Err if none arrived in time.
+        if not isinstance(seconds, int) or isinstance(seconds, bool) or seconds <= 0:
+            raise ValueError(f"with_timeout: expected a positive number of seconds, got {seconds!r}")
+        self.timeout = seconds
+        return self
     def notify(self):
         return _basilisk_ic.notify_service_call(self)
+    def __await__(self):
+        # `await call` in an async def: the coroutine yields the call to the
+        # Rust driver, which makes it and sends back the CallResult.
+        return (yield self)
+
+class AsyncInfo:
+    """A call descriptor as older basilisk code builds and yields it: name is
+    "call", "call_with_payment", "call_with_payment128", "call_raw" or
+    "call_raw128", and args the callee, the method and then the arguments
+    (and payment). drive_generator turns it into a _ServiceCall."""
+    def __init__(self, name, args):
+        self.name = name
+        self.args = args
+        self.timeout = None
+    def with_cycles(self, cycles):
+        return AsyncInfo("call_with_payment", [*self.args, cycles])
+    def with_cycles128(self, cycles):
+        return AsyncInfo("call_with_payment128", [*self.args, cycles])
+    def with_timeout(self, seconds):
+        self.timeout = seconds
+        return self
+    def notify(self):
+        return _service_call_from_descriptor(self).notify()
+    def __await__(self):
+        return (yield self)
+
+class gather:
+    """Several service calls made concurrently. `await gather(a, b)` in an async
+    def (like `yield [a, b]` in a generator) returns their CallResults in order."""
+    def __init__(self, *calls):
+        self.calls = list(calls)
+    def __await__(self):
+        return (yield self.calls)
 
 class _ServiceMethodProxy:
     """Proxy for a service method that creates _ServiceCall descriptors."""
@@ -1144,8 +1394,35 @@ class Service:
         at = self._arg_types.get(name) if self._arg_types else None
         return _ServiceMethodProxy(self._principal, name, rt, at)
 
+def _basilisk_service_candid_types():
+    """Type strings from the _arg_types/_return_types maps of every Service subclass."""
+    types = []
+    classes = [Service]
+    while classes:
+        cls = classes.pop()
+        classes.extend(cls.__subclasses__())
+        for attr in ('_arg_types', '_return_types'):
+            mapping = cls.__dict__.get(attr)
+            if isinstance(mapping, dict):
+                types.extend(t for t in mapping.values() if isinstance(t, str))
+    return types
+
+def _basilisk_service_classes():
+    """User Service subclasses by class name; bind_service_classes gives each
+    the method types of the Candid service type with that name."""
+    classes = {}
+    bases = [Service]
+    while bases:
+        for sub in bases.pop().__subclasses__():
+            classes.setdefault(sub.__name__, sub)
+            bases.append(sub)
+    return classes
+
 _mod._ServiceCall = _ServiceCall
+_mod.gather = gather
+_mod.AsyncInfo = AsyncInfo
 _mod.Func = Func
+_mod.FuncRef = FuncRef
 _mod.Service = Service
 _mod.Query = _QueryType
 _mod.Update = _UpdateType
@@ -1184,6 +1461,8 @@ class ic:
     canister_balance128 = staticmethod(_basilisk_ic.canister_balance128)
     candid_decode = staticmethod(_basilisk_ic.candid_decode)
     candid_encode = staticmethod(_basilisk_ic.candid_encode)
+    candid_decode_values = staticmethod(_basilisk_ic.candid_decode_values)
+    candid_encode_values = staticmethod(_basilisk_ic.candid_encode_values)
     data_certificate = staticmethod(_basilisk_ic.data_certificate)
     id = staticmethod(_basilisk_ic.id)
     method_name = staticmethod(_basilisk_ic.method_name)
@@ -1222,9 +1501,18 @@ class ic:
         if result < 0:
             return {"Err": {"OutOfMemory": None}}
         return {"Ok": result}
+    @staticmethod
+    def register_field_names(*names):
+        for name in names:
+            _basilisk_ic.register_field_name(name)
+    @staticmethod
+    def preserve_opt_nesting(enabled=True):
+        _basilisk_ic.preserve_opt_nesting(bool(enabled))
     set_timer = staticmethod(_basilisk_ic.set_timer)
     set_timer_interval = staticmethod(_basilisk_ic.set_timer_interval)
     clear_timer = staticmethod(_basilisk_ic.clear_timer)
+    set_error_handler = staticmethod(_basilisk_ic.set_error_handler)
+    background_errors = staticmethod(_basilisk_ic.background_errors)
     is_controller = staticmethod(_basilisk_ic.is_controller)
     call_raw = staticmethod(_basilisk_ic.call_raw)
     call_raw128 = staticmethod(_basilisk_ic.call_raw128)
@@ -1257,6 +1545,31 @@ def _ic_call_raw128(canister_id, method, args_raw, cycles=0):
 ic.call_raw = _ic_call_raw
 ic.call_raw128 = _ic_call_raw128
 
+def _service_call_from_descriptor(info):
+    """The _ServiceCall for a yielded AsyncInfo-style descriptor (see AsyncInfo)."""
+    name, args = info.name, list(info.args)
+    if name in ("call_raw", "call_raw128"):
+        call = getattr(ic, name)(*args)
+    elif name in ("call", "call_with_payment", "call_with_payment128"):
+        payment = args.pop() if name != "call" else 0
+        # Typed descriptors name the method by its qualname, e.g. "Ledger.transfer",
+        # and take its Candid types from that Service class
+        owner, _, method = str(args[1]).rpartition(".")
+        service = _basilisk_service_classes().get(owner.rsplit(".", 1)[-1]) if owner else None
+        arg_type = service._arg_types.get(method) if service else None
+        call = _ServiceCall(args[0], method, tuple(args[2:]), arg_type=arg_type)
+        return_type = service._return_types.get(method) if service else None
+        if return_type:
+            call._return_candid_type = return_type
+        if name == "call_with_payment":
+            call.with_cycles(payment)
+        elif name == "call_with_payment128":
+            call.with_cycles128(payment)
+    else:
+        raise ValueError(f"async operation '{name}' not supported")
+    timeout = getattr(info, "timeout", None)
+    return call.with_timeout(timeout) if timeout is not None else call
+
 @staticmethod
 def _ic_notify_raw(canister_id, method, args_raw, cycles=0):
     return _basilisk_ic.notify_raw(canister_id, method, args_raw, int(cycles))
@@ -1295,6 +1608,8 @@ _mod.time = _basilisk_ic.time
 _mod.trap = _basilisk_ic.trap
 _mod.candid_decode = _basilisk_ic.candid_decode
 _mod.candid_encode = _basilisk_ic.candid_encode
+_mod.candid_decode_values = _basilisk_ic.candid_decode_values
+_mod.candid_encode_values = _basilisk_ic.candid_encode_values
 
 _sys.modules["basilisk"] = _mod
 _sys.modules["bsk"] = _mod  # convenience alias (like np for numpy)
@@ -1376,6 +1691,11 @@ _sys.modules['basilisk.canisters.ledger'] = _ledger_mod
 # Make key classes available at top level for user code
 Principal = _mod.Principal
 CallResult = _mod.CallResult
+CallError = _mod.CallError
+CallTimeout = _mod.CallTimeout
+CallRejected = _mod.CallRejected
+Lock = _mod.Lock
+LockBusy = _mod.LockBusy
 StableBTreeMap = _mod.StableBTreeMap
 Func = _mod.Func
 Service = _mod.Service
@@ -0,0 +1,319 @@
+//! Candid subtyping for decoded argument values.
+//!
+//! Incoming arguments are decoded from the wire and then coerced to the
+//! parameter types declared in method metadata, following the Candid
+//! subtyping rules: missing `opt` fields and arguments become `null`, extra
+//! record fields and arguments are ignored, and numbers widen into larger
+//! declared types (`nat8` → `nat64`, `nat` → `int`, `float32` → `float64`).
+//! Anything else is reported as a `CandidDecodeError` naming the offending
+//! argument path, so dispatch can reject the call instead of trapping.
+
+use crate::type_table::unroll;
+use candid::types::internal::TypeInner;
+use candid::types::value::{IDLField, VariantValue};
+use candid::types::{Label, Type};
+use candid::IDLValue;
+
+/// Why a Candid argument payload could not be decoded against the declared types.
+#[derive(Debug, Clone)]
+pub enum CandidDecodeError {
+    /// The bytes are not a valid Candid message.
+    Malformed(String),
+    /// A value was present but does not fit the declared type.
+    TypeMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    /// A required (non-opt) argument or record field is absent.
+    Missing { path: String, expected: String },
+    /// Decoding the message would exceed a decoding quota.
+    QuotaExceeded(String),
+}
+
+impl CandidDecodeError {
+    /// Prefix the error path with an enclosing segment (argument name, field, index).
+    fn within(mut self, segment: &str) -> Self {
+        match &mut self {
+            CandidDecodeError::Malformed(_) | CandidDecodeError::QuotaExceeded(_) => {}
+            CandidDecodeError::TypeMismatch { path, .. } | CandidDecodeError::Missing { path, .. } => {
+                *path = if path.is_empty() {
+                    segment.to_string()
+                } else if path.starts_with('[') {
+                    format!("{}{}", segment, path)
+                } else {
+                    format!("{}.{}", segment, path)
+                };
+            }
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for CandidDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CandidDecodeError::Malformed(msg) => write!(f, "Failed to decode Candid args: {}", msg),
+            CandidDecodeError::QuotaExceeded(quota) => {
+                write!(f, "Failed to decode Candid args: {} quota exceeded", quota)
+            }
+            CandidDecodeError::TypeMismatch { path, expected, found } => write!(
+                f,
+                "Invalid Candid argument '{}': expected {}, found {}",
+                path, expected, found
+            ),
+            CandidDecodeError::Missing { path, expected } => write!(
+                f,
+                "Missing Candid argument '{}': expected {}",
+                path, expected
+            ),
+        }
+    }
+}
+
+/// Coerce a decoded argument to its declared parameter type.
+/// `param_name` is used as the root of the error path.
+pub fn coerce_arg(
+    value: Option<IDLValue>,
+    expected: &Type,
+    param_name: &str,
+) -> Result<IDLValue, CandidDecodeError> {
+    match value {
+        Some(value) => coerce_value(value, expected),
+        None => default_for_missing(expected).ok_or_else(|| CandidDecodeError::Missing {
+            path: String::new(),
+            expected: expected.to_string(),
+        }),
+    }
+    .map_err(|e| e.within(param_name))
+}
+
+/// Coerce a decoded value to `expected` using Candid subtyping plus numeric widening.
+pub fn coerce_value(value: IDLValue, expected: &Type) -> Result<IDLValue, CandidDecodeError> {
+    let mismatch = |value: &IDLValue| CandidDecodeError::TypeMismatch {
+        path: String::new(),
+        expected: expected.to_string(),
+        found: value.value_ty().to_string(),
+    };
+
+    if let TypeInner::Var(_) = expected.as_ref() {
+        let unrolled = unroll(expected).ok_or_else(|| mismatch(&value))?;
+        return coerce_value(value, &unrolled);
+    }
+
+    match (expected.as_ref(), value) {
+        (TypeInner::Reserved, _) => Ok(IDLValue::Reserved),
+
+        // opt: null / reserved / absent → None, otherwise coerce the inner value
+        // and fall back to None when it does not fit (Candid's opt rule).
+        (TypeInner::Opt(_), IDLValue::None | IDLValue::Null | IDLValue::Reserved) => Ok(IDLValue::None),
+        (TypeInner::Opt(inner), IDLValue::Opt(v)) => Ok(coerce_value(*v, inner)
+            .map(|v| IDLValue::Opt(Box::new(v)))
+            .unwrap_or(IDLValue::None)),
+        (TypeInner::Opt(inner), v) => {
+            let inner_unrolled = unroll(inner);
+            if matches!(
+                inner_unrolled.as_ref().map(|t| t.as_ref()),
+                Some(TypeInner::Null | TypeInner::Reserved | TypeInner::Opt(_))
+            ) {
+                Ok(IDLValue::None)
+            } else {
+                Ok(coerce_value(v, inner)
+                    .map(|v| IDLValue::Opt(Box::new(v)))
+                    .unwrap_or(IDLValue::None))
+            }
+        }
+
+        (TypeInner::Null, IDLValue::Null) => Ok(IDLValue::Null),
+        (TypeInner::Bool, v @ IDLValue::Bool(_)) => Ok(v),
+        (TypeInner::Text, v @ IDLValue::Text(_)) => Ok(v),
+        (TypeInner::Principal, v @ IDLValue::Principal(_)) => Ok(v),
+        (TypeInner::Float64, IDLValue::Float32(f)) => Ok(IDLValue::Float64(f as f64)),
+        (TypeInner::Float64, v @ IDLValue::Float64(_)) => Ok(v),
+        (TypeInner::Float32, v @ IDLValue::Float32(_)) => Ok(v),
+        (TypeInner::Func(_), v @ IDLValue::Func(..)) => Ok(v),
+        (TypeInner::Service(_), v @ IDLValue::Service(_)) => Ok(v),
+
+        (
+            TypeInner::Nat | TypeInner::Nat8 | TypeInner::Nat16 | TypeInner::Nat32 | TypeInner::Nat64
+            | TypeInner::Int | TypeInner::Int8 | TypeInner::Int16 | TypeInner::Int32 | TypeInner::Int64,
+            v,
+        ) => widen_number(&v, expected.as_ref()).ok_or_else(|| mismatch(&v)),
+
+        (TypeInner::Vec(inner), IDLValue::Blob(bytes)) => {
+            if matches!(inner.as_ref(), TypeInner::Nat8) {
+                Ok(IDLValue::Blob(bytes))
+            } else {
+                let items = bytes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        coerce_value(IDLValue::Nat8(b), inner).map_err(|e| e.within(&format!("[{}]", i)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(IDLValue::Vec(items))
+            }
+        }
+        (TypeInner::Vec(inner), IDLValue::Vec(items)) => {
+            let items = items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| coerce_value(item, inner).map_err(|e| e.within(&format!("[{}]", i))))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(IDLValue::Vec(items))
+        }
+
+        (TypeInner::Record(expected_fields), IDLValue::Record(fields)) => {
+            let mut fields: Vec<Option<IDLField>> = fields.into_iter().map(Some).collect();
+            let mut out = Vec::with_capacity(expected_fields.len());
+            for expected_field in expected_fields {
+                let id = expected_field.id.get_id();
+                let found = fields
+                    .iter_mut()
+                    .find(|f| f.as_ref().map(|f| f.id.get_id()) == Some(id))
+                    .and_then(|f| f.take());
+                let val = match found {
+                    Some(field) => coerce_value(field.val, &expected_field.ty),
+                    None => default_for_missing(&expected_field.ty).ok_or_else(|| CandidDecodeError::Missing {
+                        path: String::new(),
+                        expected: expected_field.ty.to_string(),
+                    }),
+                }
+                .map_err(|e| e.within(&label_segment(&expected_field.id)))?;
+                out.push(IDLField {
+                    id: expected_field.id.as_ref().clone(),
+                    val,
+                });
+            }
+            // Extra fields in the payload are ignored.
+            Ok(IDLValue::Record(out))
+        }
+
+        (TypeInner::Variant(cases), IDLValue::Variant(VariantValue(field, _))) => {
+            let id = field.id.get_id();
+            match cases.iter().enumerate().find(|(_, c)| c.id.get_id() == id) {
+                Some((index, case)) => {
+                    let val = coerce_value(field.val, &case.ty)
+                        .map_err(|e| e.within(&label_segment(&case.id)))?;
+                    Ok(IDLValue::Variant(VariantValue(
+                        Box::new(IDLField {
+                            id: case.id.as_ref().clone(),
+                            val,
+                        }),
+                        index as u64,
+                    )))
+                }
+                None => Err(CandidDecodeError::TypeMismatch {
+                    path: String::new(),
+                    expected: expected.to_string(),
+                    found: format!("unknown variant case {}", field.id),
+                }),
+            }
+        }
+
+        (_, v) => Err(mismatch(&v)),
+    }
+}
+
+/// Value used for an absent argument or record field, if its type allows one.
+fn default_for_missing(expected: &Type) -> Option<IDLValue> {
+    match unroll(expected)?.as_ref() {
+        TypeInner::Opt(_) => Some(IDLValue::None),
+        TypeInner::Null => Some(IDLValue::Null),
+        TypeInner::Reserved => Some(IDLValue::Reserved),
+        _ => None,
+    }
+}
+
+fn label_segment(label: &Label) -> String {
+    match label {
+        Label::Named(name) => name.clone(),
+        Label::Id(id) | Label::Unnamed(id) => id.to_string(),
+    }
+}
+
+/// Widen a numeric value into the expected numeric type.
+/// Only lossless conversions are allowed: a fixed-width value never narrows,
+/// and unbounded `nat`/`int` values only fit `nat`/`int`.
+fn widen_number(value: &IDLValue, expected: &TypeInner) -> Option<IDLValue> {
+    // (is_signed, width in bits; 0 = unbounded)
+    let (signed, width) = match value {
+        IDLValue::Nat8(_) => (false, 8),
+        IDLValue::Nat16(_) => (false, 16),
+        IDLValue::Nat32(_) => (false, 32),
+        IDLValue::Nat64(_) => (false, 64),
+        IDLValue::Nat(_) => (false, 0),
+        IDLValue::Int8(_) => (true, 8),
+        IDLValue::Int16(_) => (true, 16),
+        IDLValue::Int32(_) => (true, 32),
+        IDLValue::Int64(_) => (true, 64),
+        IDLValue::Int(_) => (true, 0),
+        _ => return None,
+    };
+    let fits = |target_signed: bool, target_width: u32| -> bool {
+        if target_width == 0 {
+            // nat accepts any nat; int accepts everything
+            return target_signed || !signed;
+        }
+        if width == 0 {
+            return false;
+        }
+        match (signed, target_signed) {
+            (false, false) | (true, true) => width <= target_width,
+            (false, true) => width < target_width,
+            (true, false) => false,
+        }
+    };
+
+    match expected {
+        TypeInner::Nat if fits(false, 0) => Some(IDLValue::Nat(candid::Nat(as_biguint(value)?))),
+        TypeInner::Int if fits(true, 0) => Some(IDLValue::Int(candid::Int(as_bigint(value)))),
+        TypeInner::Nat8 if fits(false, 8) => Some(IDLValue::Nat8(as_u64(value)? as u8)),
+        TypeInner::Nat16 if fits(false, 16) => Some(IDLValue::Nat16(as_u64(value)? as u16)),
+        TypeInner::Nat32 if fits(false, 32) => Some(IDLValue::Nat32(as_u64(value)? as u32)),
+        TypeInner::Nat64 if fits(false, 64) => Some(IDLValue::Nat64(as_u64(value)?)),
+        TypeInner::Int8 if fits(true, 8) => Some(IDLValue::Int8(as_i64(value)? as i8)),
+        TypeInner::Int16 if fits(true, 16) => Some(IDLValue::Int16(as_i64(value)? as i16)),
+        TypeInner::Int32 if fits(true, 32) => Some(IDLValue::Int32(as_i64(value)? as i32)),
+        TypeInner::Int64 if fits(true, 64) => Some(IDLValue::Int64(as_i64(value)?)),
+        _ => None,
+    }
+}
+
+fn as_u64(value: &IDLValue) -> Option<u64> {
+    match value {
+        IDLValue::Nat8(n) => Some(*n as u64),
+        IDLValue::Nat16(n) => Some(*n as u64),
+        IDLValue::Nat32(n) => Some(*n as u64),
+        IDLValue::Nat64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn as_i64(value: &IDLValue) -> Option<i64> {
+    match value {
+        IDLValue::Int8(n) => Some(*n as i64),
+        IDLValue::Int16(n) => Some(*n as i64),
+        IDLValue::Int32(n) => Some(*n as i64),
+        IDLValue::Int64(n) => Some(*n),
+        other => as_u64(other).and_then(|n| i64::try_from(n).ok()),
+    }
+}
+
+fn as_biguint(value: &IDLValue) -> Option<num_bigint::BigUint> {
+    match value {
+        IDLValue::Nat(n) => Some(n.0.clone()),
+        other => as_u64(other).map(num_bigint::BigUint::from),
+    }
+}
+
+fn as_bigint(value: &IDLValue) -> num_bigint::BigInt {
+    match value {
+        IDLValue::Int(n) => n.0.clone(),
+        IDLValue::Nat(n) => num_bigint::BigInt::from(n.0.clone()),
+        other => match as_i64(other) {
+            Some(n) => num_bigint::BigInt::from(n),
+            None => num_bigint::BigInt::from(as_u64(other).unwrap_or(0)),
+        },
+    }
+}
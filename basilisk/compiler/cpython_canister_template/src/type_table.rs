@@ -0,0 +1,570 @@
+//! Compiled Candid type table.
+//!
+//! Method metadata describes Candid types as strings (`ParamInfo.candid_type`,
+//! `MethodInfo.returns` and the `TYPE_DEFS` entries). Parsing those strings is
+//! far too expensive to repeat on every call, so `compile_type_table` turns them
+//! into `candid::types::Type` values once in `init`/`post_upgrade`, and dispatch
+//! looks the compiled types up by their string afterwards.
+//!
+//! Self-referential named types (trees, linked lists, JSON-like values) are
+//! compiled to `TypeInner::Var(name)` references, with the definition stored
+//! in the table's `TypeEnv`; use `unroll` to follow such a reference.
+//!
+//! Every record field and variant case name in a compiled type is also entered
+//! into a field-name registry, so values decoded without a type (where Candid
+//! only carries the label hash) can still get their names back via `field_name`.
+
+use crate::wasm_data::{METHOD_METADATA, TYPE_DEFS, LIFECYCLE};
+use candid::types::{Type, TypeEnv};
+use std::collections::{HashMap, HashSet};
+
+/// Compiled types, keyed by their (trimmed) Candid type string.
+pub struct TypeTable {
+    types: HashMap<String, Type>,
+    /// Named types from TYPE_DEFS, sorted by name (used for field-hash matching).
+    named: Vec<(String, Type)>,
+    env: TypeEnv,
+    /// Field and case names by Candid label hash.
+    field_names: HashMap<u32, String>,
+}
+
+impl TypeTable {
+    fn new() -> Self {
+        let mut table = TypeTable {
+            types: HashMap::new(),
+            named: Vec::new(),
+            env: TypeEnv::new(),
+            field_names: HashMap::new(),
+        };
+        for name in WELL_KNOWN_FIELD_NAMES {
+            table.register_field_name(name);
+        }
+        table
+    }
+
+    /// Compile `type_str` (if not already cached) and return the cached type.
+    fn compile(&mut self, type_str: &str, type_defs: &HashMap<String, String>) -> Option<Type> {
+        let key = type_str.trim();
+        if let Some(ty) = self.types.get(key) {
+            return Some(ty.clone());
+        }
+        let ty = type_str_to_candid_type(key, type_defs, &mut self.env, &mut self.types)?;
+        self.register_field_names(&ty);
+        self.types.insert(key.to_string(), ty.clone());
+        Some(ty)
+    }
+
+    fn register_field_name(&mut self, name: &str) {
+        let label = candid_name_to_label(name);
+        if let candid::types::Label::Named(name) = label {
+            self.field_names.entry(candid::idl_hash(&name)).or_insert(name);
+        }
+    }
+
+    /// Register the labels of every record and variant reachable from `ty`.
+    /// `Var` references are not followed: their definitions are compiled (and
+    /// registered) as named types.
+    fn register_field_names(&mut self, ty: &Type) {
+        use candid::types::internal::TypeInner;
+
+        match ty.as_ref() {
+            TypeInner::Record(fields) | TypeInner::Variant(fields) => {
+                for field in fields.iter() {
+                    if let candid::types::Label::Named(name) = field.id.as_ref() {
+                        self.field_names
+                            .entry(field.id.get_id())
+                            .or_insert_with(|| name.clone());
+                    }
+                    self.register_field_names(&field.ty);
+                }
+            }
+            TypeInner::Opt(inner) | TypeInner::Vec(inner) => self.register_field_names(inner),
+            TypeInner::Func(func) => {
+                for t in func.args.iter().chain(&func.rets) {
+                    self.register_field_names(t);
+                }
+            }
+            TypeInner::Service(methods) => {
+                for (_, t) in methods.iter() {
+                    self.register_field_names(t);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// IC management canister and common field/variant names that responses may
+/// carry even when no type string in the canister mentions them.
+const WELL_KNOWN_FIELD_NAMES: &[&str] = &[
+    // Management canister
+    "canister_id", "controllers", "compute_allocation", "memory_allocation",
+    "freezing_threshold", "settings", "mode", "wasm_module", "arg", "amount",
+    "status", "module_hash", "memory_size", "cycles", "idle_cycles_burned_per_day",
+    "sender_canister_version", "reserved_cycles", "reserved_cycles_limit",
+    "install", "reinstall", "upgrade", "running", "stopping", "stopped",
+    "query_stats", "num_calls_total", "num_instructions_total",
+    "request_payload_bytes_total", "response_payload_bytes_total",
+    // HTTP outcalls
+    "url", "method", "headers", "body", "max_response_bytes", "transform",
+    "name", "value", "function", "context",
+    // Threshold ECDSA / Schnorr
+    "public_key", "chain_code", "signature", "key_id", "curve",
+    "derivation_path", "message_hash", "message",
+    "secp256k1", "ed25519",
+    // Bitcoin
+    "network", "address", "min_confirmations", "utxos", "outpoint",
+    "txid", "vout", "height", "block_hash", "tip_block_hash", "tip_height",
+    "next_page", "satoshi", "mainnet", "testnet", "regtest",
+    // Ledger
+    "memo", "from", "to", "fee", "timestamp", "created_at_time",
+    "e8s", "amount_e8s", "block_index",
+    // Common
+    "Ok", "Err", "err", "ok", "result", "data", "error", "code", "description",
+    "id", "key", "token", "principal", "caller", "time", "source", "target",
+];
+
+/// Global compiled type table (populated at init / post_upgrade).
+pub static mut TYPE_TABLE: Option<TypeTable> = None;
+
+/// Compile every type string found in METHOD_METADATA, LIFECYCLE and TYPE_DEFS.
+/// Must run after those globals have been populated.
+pub fn compile_type_table() {
+    let empty_map = HashMap::new();
+    let type_defs = unsafe { TYPE_DEFS.as_ref() }.unwrap_or(&empty_map);
+    let mut table = TypeTable::new();
+    // Keep field names registered by module-level Python code, which runs first
+    if let Some(previous) = unsafe { TYPE_TABLE.take() } {
+        table.field_names.extend(previous.field_names);
+    }
+
+    let mut names: Vec<&String> = type_defs.keys().collect();
+    names.sort();
+    for name in names {
+        if let Some(ty) = table.compile(name, type_defs) {
+            table.named.push((name.clone(), ty));
+        }
+    }
+
+    let methods = unsafe { METHOD_METADATA.as_ref() }.into_iter().flatten();
+    let hooks = unsafe { LIFECYCLE.as_ref() }.into_iter().flat_map(|lc| lc.values());
+    for method in methods.chain(hooks) {
+        for param in &method.params {
+            table.compile(&param.candid_type, type_defs);
+        }
+        for return_type in &method.returns {
+            table.compile(return_type, type_defs);
+        }
+    }
+
+    unsafe {
+        TYPE_TABLE = Some(table);
+    }
+}
+
+fn type_table() -> &'static mut TypeTable {
+    unsafe { TYPE_TABLE.get_or_insert_with(TypeTable::new) }
+}
+
+/// Look up the compiled type for a Candid type string.
+/// Strings not seen at init (e.g. ad-hoc `_ServiceCall` argument types) are
+/// compiled on first use and cached.
+pub fn lookup_type(type_str: &str) -> Option<Type> {
+    let table = type_table();
+    if let Some(ty) = table.types.get(type_str.trim()) {
+        return Some(ty.clone());
+    }
+    let empty_map = HashMap::new();
+    let type_defs = unsafe { TYPE_DEFS.as_ref() }.unwrap_or(&empty_map);
+    table.compile(type_str, type_defs)
+}
+
+/// Split a parenthesised Candid type list such as `(nat, record { a : text })`
+/// into its type strings. Returns `None` for a single type (Candid types never
+/// start with a parenthesis).
+pub fn split_type_list(types: &str) -> Option<Vec<String>> {
+    let inner = types.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, ch) in inner.char_indices() {
+        match ch {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(inner[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = inner[start..].trim();
+    if !last.is_empty() {
+        parts.push(last.to_string());
+    }
+    Some(parts)
+}
+
+/// Recover a record field or variant case name from its Candid label hash.
+pub fn field_name(hash: u32) -> Option<&'static str> {
+    type_table().field_names.get(&hash).map(String::as_str)
+}
+
+/// Add `name` to the field-name registry (for labels no known type mentions).
+pub fn register_field_name(name: &str) {
+    type_table().register_field_name(name);
+}
+
+/// Compiled named types from TYPE_DEFS.
+pub fn named_types() -> &'static [(String, Type)] {
+    &type_table().named
+}
+
+/// Type environment for typed (de)serialization of compiled types.
+/// Holds the definitions of recursive named types referenced through `Var`.
+pub fn type_env() -> &'static TypeEnv {
+    &type_table().env
+}
+
+/// Follow a `Var` reference to a recursive named type to its definition.
+/// Any other type is returned unchanged.
+pub fn unroll(ty: &Type) -> Option<Type> {
+    match ty.as_ref() {
+        candid::types::internal::TypeInner::Var(_) => type_env().trace_type(ty).ok(),
+        _ => Some(ty.clone()),
+    }
+}
+
+// ─── Candid type string parsing ──────────────────────────────────────────────
+
+/// State for compiling one type string.
+struct TypeContext<'a> {
+    type_defs: &'a HashMap<String, String>,
+    env: &'a mut TypeEnv,
+    /// Named types currently being expanded, outermost first.
+    expanding: Vec<String>,
+    /// Names referenced from within their own expansion.
+    recursive: HashSet<String>,
+    /// Already compiled types by string; named types are reused from here so
+    /// every reference to a name shares one `Type` (see `candid_codec` classes).
+    compiled: &'a mut HashMap<String, Type>,
+}
+
+/// Convert a Candid type string into a `candid::types::Type` for typed serialization.
+/// Definitions of recursive named types are added to `env`.
+/// Returns None for types that cannot be represented.
+fn type_str_to_candid_type(
+    type_str: &str,
+    type_defs: &HashMap<String, String>,
+    env: &mut TypeEnv,
+    compiled: &mut HashMap<String, Type>,
+) -> Option<candid::types::Type> {
+    let mut cx = TypeContext {
+        type_defs,
+        env,
+        expanding: Vec::new(),
+        recursive: HashSet::new(),
+        compiled,
+    };
+    type_str_to_candid_type_inner(type_str, &mut cx)
+}
+
+fn type_str_to_candid_type_inner(
+    type_str: &str,
+    cx: &mut TypeContext,
+) -> Option<candid::types::Type> {
+    let trimmed = type_str.trim();
+    if let Some(def) = named_type_def(trimmed, cx.type_defs) {
+        return named_type_to_candid_type(trimmed, def, cx);
+    }
+    use candid::types::internal::{TypeInner, Field};
+    let ty: candid::types::Type = match trimmed {
+        "" | "null" => TypeInner::Null.into(),
+        "bool" => TypeInner::Bool.into(),
+        "nat" => TypeInner::Nat.into(),
+        "int" => TypeInner::Int.into(),
+        "nat8" => TypeInner::Nat8.into(),
+        "nat16" => TypeInner::Nat16.into(),
+        "nat32" => TypeInner::Nat32.into(),
+        "nat64" => TypeInner::Nat64.into(),
+        "int8" => TypeInner::Int8.into(),
+        "int16" => TypeInner::Int16.into(),
+        "int32" => TypeInner::Int32.into(),
+        "int64" => TypeInner::Int64.into(),
+        "float32" => TypeInner::Float32.into(),
+        "float64" => TypeInner::Float64.into(),
+        "text" => TypeInner::Text.into(),
+        "blob" => TypeInner::Vec(TypeInner::Nat8.into()).into(),
+        "principal" => TypeInner::Principal.into(),
+        "empty" => TypeInner::Empty.into(),
+        "reserved" => TypeInner::Reserved.into(),
+        s if s.starts_with("opt ") => {
+            let inner = type_str_to_candid_type_inner(&s[4..], cx)?;
+            TypeInner::Opt(inner).into()
+        }
+        s if s.starts_with("vec ") => {
+            let inner = type_str_to_candid_type_inner(&s[4..], cx)?;
+            TypeInner::Vec(inner).into()
+        }
+        s => {
+            if let Some(inner) = strip_compound_wrapper(s, "record") {
+                let fields = parse_fields(inner);
+                let mut candid_fields: Vec<Field> = fields.iter().map(|(name, ty)| {
+                    Field {
+                        id: std::rc::Rc::new(candid_name_to_label(name)),
+                        ty: type_str_to_candid_type_inner(ty, cx)
+                            .unwrap_or_else(|| TypeInner::Reserved.into()),
+                    }
+                }).collect();
+                candid_fields.sort_by(|a, b| a.id.get_id().cmp(&b.id.get_id()));
+                TypeInner::Record(candid_fields).into()
+            } else if let Some(inner) = strip_compound_wrapper(s, "variant") {
+                let cases = parse_fields(inner);
+                let mut candid_fields: Vec<Field> = cases.iter().map(|(name, ty)| {
+                    Field {
+                        id: std::rc::Rc::new(candid_name_to_label(name)),
+                        ty: type_str_to_candid_type_inner(ty, cx)
+                            .unwrap_or_else(|| TypeInner::Null.into()),
+                    }
+                }).collect();
+                candid_fields.sort_by(|a, b| a.id.get_id().cmp(&b.id.get_id()));
+                TypeInner::Variant(candid_fields).into()
+            } else if let Some(inner) = strip_compound_wrapper(s, "service") {
+                // Parse service { method_name : sig; ... }
+                let method_strs = parse_fields(inner);
+                let mut methods: Vec<(String, candid::types::Type)> = Vec::new();
+                for (name, sig) in &method_strs {
+                    if let Some(func_ty) = parse_func_signature(sig, cx) {
+                        methods.push((name.clone(), func_ty));
+                    }
+                }
+                TypeInner::Service(methods).into()
+            } else if s.starts_with("func ") {
+                let sig = &s[5..]; // strip "func "
+                return parse_func_signature(sig, cx);
+            } else {
+                return None; // Unknown type
+            }
+        }
+    };
+    Some(ty)
+}
+
+/// Look up the TYPE_DEFS definition if `trimmed` is a named type reference.
+fn named_type_def<'a>(trimmed: &str, type_defs: &'a HashMap<String, String>) -> Option<&'a str> {
+    if trimmed.starts_with("record")
+        || trimmed.starts_with("variant")
+        || trimmed.starts_with("opt ")
+        || trimmed.starts_with("vec ")
+        || trimmed.contains(' ')
+        || trimmed.contains('{')
+    {
+        return None;
+    }
+    type_defs.get(trimmed).map(|def| def.as_str())
+}
+
+/// Expand a named type reference, or reuse its earlier expansion.
+/// A reference back to a name that is still being expanded becomes
+/// `TypeInner::Var(name)`, and that name's definition is added to the env.
+fn named_type_to_candid_type(
+    name: &str,
+    def: &str,
+    cx: &mut TypeContext,
+) -> Option<candid::types::Type> {
+    use candid::types::internal::TypeInner;
+    if let Some(ty) = cx.compiled.get(name) {
+        return Some(ty.clone());
+    }
+    if cx.expanding.iter().any(|n| n == name) {
+        cx.recursive.insert(name.to_string());
+        return Some(TypeInner::Var(name.to_string()).into());
+    }
+    cx.expanding.push(name.to_string());
+    let ty = type_str_to_candid_type_inner(def, cx);
+    cx.expanding.pop();
+    let recursive = cx.recursive.remove(name);
+    let ty = ty?;
+    if recursive {
+        if matches!(ty.as_ref(), TypeInner::Var(_)) {
+            return None; // Alias cycle with no structure, e.g. `type A = B; type B = A`
+        }
+        cx.env.0.insert(name.to_string(), ty.clone());
+    }
+    // A bare Var is an alias of a name still being expanded, which may yet fail
+    if !matches!(ty.as_ref(), TypeInner::Var(_)) {
+        cx.compiled.insert(name.to_string(), ty.clone());
+    }
+    Some(ty)
+}
+
+/// Parse field definitions from inside `record { ... }` or `variant { ... }`.
+/// Returns a list of (field_name, field_type_string) pairs.
+fn parse_fields(inner: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+
+    for ch in inner.chars() {
+        match ch {
+            '{' => {
+                depth += 1;
+                current.push(ch);
+            }
+            '}' => {
+                depth -= 1;
+                current.push(ch);
+            }
+            ';' if depth == 0 => {
+                let trimmed = current.trim().to_string();
+                if !trimmed.is_empty() {
+                    fields.push(trimmed);
+                }
+                current.clear();
+            }
+            _ => {
+                current.push(ch);
+            }
+        }
+    }
+    let trimmed = current.trim().to_string();
+    if !trimmed.is_empty() {
+        fields.push(trimmed);
+    }
+
+    fields
+        .iter()
+        .map(|f| {
+            if let Some(colon_pos) = f.find(':') {
+                let raw_name = f[..colon_pos].trim();
+                // Strip surrounding double quotes from Candid reserved-word field names
+                let name = if raw_name.starts_with('"') && raw_name.ends_with('"') && raw_name.len() >= 2 {
+                    raw_name[1..raw_name.len() - 1].to_string()
+                } else {
+                    raw_name.to_string()
+                };
+                let type_str = f[colon_pos + 1..].trim().to_string();
+                (name, type_str)
+            } else {
+                (f.clone(), "null".to_string())
+            }
+        })
+        .collect()
+}
+
+/// Find the position of the closing ')' that matches the '(' at `open_pos`.
+fn find_matching_paren(s: &str, open_pos: usize) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0i32;
+    for i in open_pos..bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse a comma-separated list of Candid type strings into a Vec<Type>.
+fn parse_type_list(s: &str, cx: &mut TypeContext) -> Vec<candid::types::Type> {
+    let trimmed = s.trim();
+    if trimmed.is_empty() {
+        return Vec::new();
+    }
+    let mut types = Vec::new();
+    let mut current = String::new();
+    let mut paren_depth = 0i32;
+    let mut brace_depth = 0i32;
+    for ch in trimmed.chars() {
+        match ch {
+            '(' => { paren_depth += 1; current.push(ch); }
+            ')' => { paren_depth -= 1; current.push(ch); }
+            '{' => { brace_depth += 1; current.push(ch); }
+            '}' => { brace_depth -= 1; current.push(ch); }
+            ',' if paren_depth == 0 && brace_depth == 0 => {
+                let t = current.trim().to_string();
+                if !t.is_empty() {
+                    if let Some(ty) = type_str_to_candid_type_inner(&t, cx) {
+                        types.push(ty);
+                    }
+                }
+                current.clear();
+            }
+            _ => { current.push(ch); }
+        }
+    }
+    let t = current.trim().to_string();
+    if !t.is_empty() {
+        if let Some(ty) = type_str_to_candid_type_inner(&t, cx) {
+            types.push(ty);
+        }
+    }
+    types
+}
+
+/// Parse a Candid function signature string like `(text, nat64) -> (bool) query`
+/// into a `candid::types::Type` (TypeInner::Func).
+fn parse_func_signature(sig: &str, cx: &mut TypeContext) -> Option<candid::types::Type> {
+    use candid::types::internal::TypeInner;
+    use candid::types::{Function, FuncMode};
+
+    let sig = sig.trim();
+    let args_start = sig.find('(')?;
+    let args_end = find_matching_paren(sig, args_start)?;
+    let args_str = &sig[args_start + 1..args_end];
+
+    let rest = sig[args_end + 1..].trim();
+    let rest = rest.strip_prefix("->")?;
+    let rest = rest.trim();
+
+    let rets_start = rest.find('(')?;
+    let rets_end = find_matching_paren(rest, rets_start)?;
+    let rets_str = &rest[rets_start + 1..rets_end];
+
+    let mode_str = rest[rets_end + 1..].trim();
+    let modes = match mode_str {
+        "query" => vec![FuncMode::Query],
+        "composite_query" => vec![FuncMode::CompositeQuery],
+        "oneway" => vec![FuncMode::Oneway],
+        _ => vec![],
+    };
+
+    let args = parse_type_list(args_str, cx);
+    let rets = parse_type_list(rets_str, cx);
+
+    Some(TypeInner::Func(Function { modes, args, rets }).into())
+}
+
+/// Strip the outer `record { ... }` or `variant { ... }` wrapper and return the inner content.
+fn strip_compound_wrapper<'a>(type_str: &'a str, keyword: &str) -> Option<&'a str> {
+    let trimmed = type_str.trim();
+    if !trimmed.starts_with(keyword) {
+        return None;
+    }
+    let rest = trimmed[keyword.len()..].trim();
+    if rest.starts_with('{') && rest.ends_with('}') {
+        Some(&rest[1..rest.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Convert a Candid field name (already in Candid form) to a Label.
+/// Unlike field_name_to_label, this does NOT strip keyword underscores because
+/// the name has already been converted from Python form to Candid form.
+pub fn candid_name_to_label(name: &str) -> candid::types::Label {
+    if name.starts_with('_') && name.ends_with('_') && name.len() > 2 {
+        if let Ok(id) = name[1..name.len() - 1].parse::<u32>() {
+            return candid::types::Label::Id(id);
+        }
+    }
+    if let Ok(id) = name.parse::<u32>() {
+        return candid::types::Label::Id(id);
+    }
+    candid::types::Label::Named(name.to_string())
+}
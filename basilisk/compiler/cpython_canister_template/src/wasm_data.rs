@@ -19,21 +19,78 @@ pub struct Metadata {
     /// Lifecycle hooks: init, pre_upgrade, post_upgrade, heartbeat, inspect_message
     #[serde(default)]
     pub lifecycle: HashMap<String, MethodInfo>,
+    /// Canister-wide decoding quotas, for methods that don't set their own
+    #[serde(default)]
+    pub decoding_quotas: DecodingQuotas,
 }
 
 /// Method metadata for a single canister method.
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct MethodInfo {
     pub name: String,
-    pub method_type: String, // "query" or "update"
+    pub method_type: String, // "query", "composite_query" or "update"
     pub params: Vec<ParamInfo>,
-    pub returns: String,     // Candid type string (may reference named types)
+    pub returns: Vec<String>, // Candid return type strings, one per return value (may reference named types)
     #[serde(default)]
     pub guard: Option<String>, // Optional guard function name
     #[serde(default)]
     pub manual_reply: bool, // If true, Python function calls ic.reply() itself
     #[serde(default)]
     pub is_async: bool, // If true, function is a generator (uses yield for cross-canister calls)
+    #[serde(default)]
+    pub err_on_exception: bool, // If true, uncaught exceptions reply with the Err case of the return variant
+    #[serde(default)]
+    pub raise_on_reject: bool, // If true, rejected calls raise CallRejected in the generator; uncaught exceptions after the first await reject
+    #[serde(default)]
+    pub preserve_opt: Option<bool>, // Decode opt values as Opt(...) wrappers; None follows ic.preserve_opt_nesting()
+    #[serde(default)]
+    pub decoding_quotas: DecodingQuotas, // Limits on decoding the arguments; unset ones follow the canister-wide quotas
+}
+
+impl MethodInfo {
+    /// The quotas for decoding this method's arguments.
+    pub fn effective_decoding_quotas(&self) -> DecodingQuotas {
+        self.decoding_quotas.or(global_decoding_quotas())
+    }
+}
+
+/// Limits on the work of decoding one Candid message, guarding against
+/// payloads that are small on the wire but expensive to decode (a long
+/// `vec null`, a deeply nested value). `None` means no limit.
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+pub struct DecodingQuotas {
+    /// Decoding cost, in the units of candid's `DecoderConfig::set_decoding_quota`.
+    #[serde(default)]
+    pub decoding: Option<usize>,
+    /// Cost of wire data that is skipped: extra arguments and fields, mismatched opts.
+    #[serde(default)]
+    pub skipping: Option<usize>,
+    /// Number of zero-sized values (null, reserved, empty records).
+    #[serde(default)]
+    pub zero_sized: Option<usize>,
+}
+
+impl DecodingQuotas {
+    /// These quotas, with unset ones taken from `fallback`.
+    pub fn or(self, fallback: DecodingQuotas) -> DecodingQuotas {
+        DecodingQuotas {
+            decoding: self.decoding.or(fallback.decoding),
+            skipping: self.skipping.or(fallback.skipping),
+            zero_sized: self.zero_sized.or(fallback.zero_sized),
+        }
+    }
+
+    /// A candid decoder config enforcing the decoding and skipping quotas.
+    pub fn decoder_config(&self) -> candid::de::DecoderConfig {
+        let mut config = candid::de::DecoderConfig::new();
+        if let Some(n) = self.decoding {
+            config.set_decoding_quota(n);
+        }
+        if let Some(n) = self.skipping {
+            config.set_skipping_quota(n);
+        }
+        config
+    }
 }
 
 /// Parameter metadata.
@@ -52,6 +109,14 @@ pub static mut TYPE_DEFS: Option<HashMap<String, String>> = None;
 /// Global storage for lifecycle hooks (populated at init).
 pub static mut LIFECYCLE: Option<HashMap<String, MethodInfo>> = None;
 
+/// Global storage for the canister-wide decoding quotas (populated at init).
+pub static mut DECODING_QUOTAS: Option<DecodingQuotas> = None;
+
+/// The canister-wide decoding quotas; they also apply to inter-canister call results.
+pub fn global_decoding_quotas() -> DecodingQuotas {
+    unsafe { DECODING_QUOTAS }.unwrap_or_default()
+}
+
 // ─── Placeholder functions (defined in C: cpython_init_helper.c) ────────────
 // These are compiled by WASI SDK clang as opaque object code, immune to
 // Rust's LTO. The wasm manipulator patches their bodies at build time.
@@ -75,12 +140,20 @@ pub fn get_python_code() -> String {
     })
 }
 
+/// Method metadata as returned by `get_method_metadata`:
+/// (methods, type_defs, lifecycle, decoding_quotas).
+pub type MethodMetadata = (
+    Vec<MethodInfo>,
+    HashMap<String, String>,
+    HashMap<String, MethodInfo>,
+    DecodingQuotas,
+);
+
 /// Read the method metadata JSON from the passive data segment.
-/// Returns (methods, type_defs, lifecycle).
-pub fn get_method_metadata() -> (Vec<MethodInfo>, HashMap<String, String>, HashMap<String, MethodInfo>) {
+pub fn get_method_metadata() -> MethodMetadata {
     let size = unsafe { method_meta_passive_data_size() } as usize;
     if size == 0 {
-        return (Vec::new(), HashMap::new(), HashMap::new());
+        return (Vec::new(), HashMap::new(), HashMap::new(), DecodingQuotas::default());
     }
     let mut buffer = vec![0u8; size];
     unsafe { init_method_meta_passive_data(buffer.as_mut_ptr() as i32) };
@@ -90,12 +163,12 @@ pub fn get_method_metadata() -> (Vec<MethodInfo>, HashMap<String, String>, HashM
 
     // Try new format first (wrapped object with methods + type_defs + lifecycle)
     if let Ok(metadata) = serde_json::from_str::<Metadata>(&json_str) {
-        return (metadata.methods, metadata.type_defs, metadata.lifecycle);
+        return (metadata.methods, metadata.type_defs, metadata.lifecycle, metadata.decoding_quotas);
     }
 
     // Fall back to old format (bare array of methods) for backwards compatibility
     let methods: Vec<MethodInfo> = serde_json::from_str(&json_str).unwrap_or_else(|e| {
         ic_cdk::trap(&format!("Invalid method metadata JSON: {}", e));
     });
-    (methods, HashMap::new(), HashMap::new())
+    (methods, HashMap::new(), HashMap::new(), DecodingQuotas::default())
 }
@@ -167,6 +167,7 @@ def manipulate_wasm(
     methods: List[Dict],
     type_defs: Optional[Dict[str, str]] = None,
     lifecycle: Optional[Dict[str, Dict]] = None,
+    decoding_quotas: Optional[Dict[str, int]] = None,
 ) -> None:
     """
     Inject Python source and method metadata into the template wasm.
@@ -176,11 +177,14 @@ def manipulate_wasm(
         output_wasm_path: Path to write the modified wasm
         python_source: The user's Python source code (UTF-8)
         methods: List of method dicts with keys: name, method_type, params, returns
-            e.g. [{"name": "greet", "method_type": "query", "params": [{"name": "name", "candid_type": "text"}], "returns": "text"}]
+            e.g. [{"name": "greet", "method_type": "query", "params": [{"name": "name", "candid_type": "text"}], "returns": ["text"]}]
         type_defs: Optional dict mapping type name -> Candid type definition string
             e.g. {"User": "record { id : text; username : text }"}
         lifecycle: Optional dict mapping lifecycle hook name -> method metadata
             e.g. {"init": {"name": "init_", "params": [...]}, "post_upgrade": {...}}
+        decoding_quotas: Optional canister-wide Candid decoding quotas, used by
+            methods that set none of their own
+            e.g. {"decoding": 10_000_000, "skipping": 10_000, "zero_sized": 1000}
     """
     with open(template_wasm_path, "rb") as f:
         wasm = f.read()
@@ -206,6 +210,7 @@ def manipulate_wasm(
         "methods": methods,
         "type_defs": type_defs or {},
         "lifecycle": lifecycle or {},
+        "decoding_quotas": decoding_quotas or {},
     }
     method_meta_json = json.dumps(metadata).encode("utf-8")
 
@@ -812,7 +817,8 @@ def _build_type_registry(tree) -> Tuple[Dict[str, str], Dict[str, str]]:
                                 dec_id = dec.attr
                             if dec_id == "service_query":
                                 svc_mode = "query"
-                            elif dec_id == "service_update":
+                            elif dec_id in ("service_update", "service_method"):
+                                # Older @service_method calls are made as updates
                                 svc_mode = "update"
                         if svc_mode:
                             # Extract params (skip self)
@@ -1002,6 +1008,7 @@ def _build_type_registry(tree) -> Tuple[Dict[str, str], Dict[str, str]]:
         "RejectionCode": "variant { NoError : null; SysFatal : null; SysTransient : null; DestinationInvalid : null; CanisterReject : null; CanisterError : null }",
         "NotifyResult": "variant { Ok : null; Err : variant { NoError : null; SysFatal : null; SysTransient : null; DestinationInvalid : null; CanisterReject : null; CanisterError : null } }",
         "GuardResult": "variant { Ok : null; Err : text }",
+        "BackgroundError": "record { callback : text; exception_type : text; message : text; time : nat64 }",
         "KeyTooLarge": "record { given : nat32; max : nat32 }",
         "ValueTooLarge": "record { given : nat32; max : nat32 }",
         "InsertError": "variant { KeyTooLarge : record { given : nat32; max : nat32 }; ValueTooLarge : record { given : nat32; max : nat32 } }",
@@ -1115,6 +1122,15 @@ def _extract_subscript_elements(slice_node) -> list:
     return [slice_node]
 
 
+# Decorator kwargs that set a method's Candid decoding quotas, mapped to the
+# quota names the template reads from the method metadata.
+_DECODING_QUOTA_KWARGS = {
+    "decoding_quota": "decoding",
+    "skipping_quota": "skipping",
+    "zero_sized_quota": "zero_sized",
+}
+
+
 def extract_methods_from_python(python_source: str) -> List[Dict]:
     """
     Extract method declarations from Python source code.
@@ -1135,7 +1151,7 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
     Produces:
         [{"name": "get_user", "method_type": "query",
           "params": [],
-          "returns": "record { name : text; age : nat32 }"}]
+          "returns": ["record { name : text; age : nat32 }"]}]
     """
     import ast
 
@@ -1202,11 +1218,15 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
         return "text"  # fallback
 
     for node in ast.walk(tree):
-        if not isinstance(node, ast.FunctionDef):
+        if not isinstance(node, (ast.FunctionDef, ast.AsyncFunctionDef)):
             continue
 
         method_type = None
         guard_name = None
+        err_on_exception = False
+        raise_on_reject = False
+        preserve_opt = None
+        decoding_quotas = {}
         for decorator in node.decorator_list:
             dec_name = None
             dec_kwargs = {}
@@ -1221,6 +1241,15 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
                 for kw in decorator.keywords:
                     if kw.arg == "guard" and isinstance(kw.value, ast.Name):
                         dec_kwargs["guard"] = kw.value.id
+                    elif kw.arg == "err_on_exception" and isinstance(kw.value, ast.Constant):
+                        dec_kwargs["err_on_exception"] = kw.value.value is True
+                    elif kw.arg == "raise_on_reject" and isinstance(kw.value, ast.Constant):
+                        dec_kwargs["raise_on_reject"] = kw.value.value is True
+                    elif kw.arg == "preserve_opt" and isinstance(kw.value, ast.Constant):
+                        dec_kwargs["preserve_opt"] = kw.value.value is True
+                    elif kw.arg in _DECODING_QUOTA_KWARGS and isinstance(kw.value, ast.Constant) \
+                            and isinstance(kw.value.value, int):
+                        dec_kwargs[kw.arg] = kw.value.value
             elif isinstance(decorator, ast.Attribute):
                 dec_name = decorator.attr
 
@@ -1230,6 +1259,14 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
                 method_type = dec_name
                 if "guard" in dec_kwargs:
                     guard_name = dec_kwargs["guard"]
+                err_on_exception = dec_kwargs.get("err_on_exception", False)
+                raise_on_reject = dec_kwargs.get("raise_on_reject", False)
+                preserve_opt = dec_kwargs.get("preserve_opt")
+                decoding_quotas = {
+                    quota: dec_kwargs[kwarg]
+                    for kwarg, quota in _DECODING_QUOTA_KWARGS.items()
+                    if kwarg in dec_kwargs
+                }
 
         if method_type is None:
             continue
@@ -1244,7 +1281,8 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
 
         # Check for Manual[T], Async[T] return types, and yield in body
         manual_reply = False
-        is_async = False
+        # An async def returns a coroutine, driven like a generator
+        is_async = isinstance(node, ast.AsyncFunctionDef)
         ret_annotation = node.returns
         # Unwrap Async[T] -> T (or Async[Manual[T]] -> Manual[T])
         if isinstance(ret_annotation, ast.Subscript):
@@ -1261,8 +1299,21 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
         if isinstance(ret_annotation, ast.Subscript):
             if isinstance(ret_annotation.value, ast.Name) and ret_annotation.value.id == "Manual":
                 manual_reply = True
+                ret_annotation = ret_annotation.slice
 
-        return_type = get_candid_type(ret_annotation)
+        # Returns[T1, T2] declares several Candid return values, Returns[()] none.
+        # Any other annotation is a single value, except void which is none.
+        if (isinstance(ret_annotation, ast.Subscript)
+                and isinstance(ret_annotation.value, ast.Name)
+                and ret_annotation.value.id == "Returns"):
+            if isinstance(ret_annotation.slice, ast.Tuple) and not ret_annotation.slice.elts:
+                return_types = []
+            else:
+                return_types = [get_candid_type(elem)
+                                for elem in _extract_subscript_elements(ret_annotation.slice)]
+        else:
+            return_type = get_candid_type(ret_annotation)
+            return_types = [return_type] if return_type else []
 
         # Auto-promote @query with async (yield/Async) to composite_query.
         # The IC requires queries that make inter-canister calls to be exported
@@ -1274,7 +1325,7 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
             "name": node.name,
             "method_type": method_type,
             "params": params,
-            "returns": return_type,
+            "returns": return_types,
         }
         if guard_name:
             entry["guard"] = guard_name
@@ -1282,6 +1333,14 @@ def extract_methods_from_python(python_source: str) -> List[Dict]:
             entry["manual_reply"] = True
         if is_async:
             entry["is_async"] = True
+        if err_on_exception:
+            entry["err_on_exception"] = True
+        if raise_on_reject:
+            entry["raise_on_reject"] = True
+        if preserve_opt is not None:
+            entry["preserve_opt"] = preserve_opt
+        if decoding_quotas:
+            entry["decoding_quotas"] = decoding_quotas
 
         if method_type in ("query", "update", "composite_query"):
             methods.append(entry)
@@ -1430,7 +1489,7 @@ def generate_candid_from_methods(
         params = ", ".join(
             p["candid_type"] for p in method["params"]
         )
-        returns = method["returns"] if method["returns"] not in ("",) else ""
+        returns = ", ".join(method["returns"])
         if method["method_type"] == "query":
             mode = " query"
         elif method["method_type"] == "composite_query":
@@ -1475,6 +1534,6 @@ if __name__ == "__main__":
 
     print(f"Extracted {len(methods)} canister methods:")
     for m in methods:
-        print(f"  @{m['method_type']} {m['name']}({', '.join(p['name'] + ': ' + p['candid_type'] for p in m['params'])}) -> {m['returns']}")
+        print(f"  @{m['method_type']} {m['name']}({', '.join(p['name'] + ': ' + p['candid_type'] for p in m['params'])}) -> ({', '.join(m['returns'])})")
 
     manipulate_wasm(template_path, output_path, python_source, methods, type_defs, lifecycle)
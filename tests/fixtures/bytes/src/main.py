@@ -1,5 +1,10 @@
-from basilisk import blob, update
+from basilisk import blob, nat64, query, update
 
 @update
 def get_bytes(bytes: blob) -> blob:
     return bytes
+
+
+@query
+def get_bytes_length(bytes: blob) -> nat64:
+    return len(bytes)
@@ -6,9 +6,13 @@ from basilisk import (
     match,
     nat,
     nat64,
+    Opt,
     Principal,
+    query,
+    Record,
     update,
     Variant,
+    Vec,
 )
 
 
@@ -54,3 +58,60 @@ def execute_call_raw128(
             "Err": lambda err: {"Err": err},
         },
     )
+
+
+class Profile(Record):
+    name: str
+    scores: Vec[nat]
+    nickname: Opt[str]
+
+
+@query
+def greet(name: str, times: nat) -> str:
+    return " ".join([f"Hello, {name}!"] * times)
+
+
+@update
+def execute_typed_call_raw(name: str) -> Async[str]:
+    call_result: CallResult[blob] = yield ic.call_raw(
+        ic.id(), "greet", ic.candid_encode_values("(text, nat)", (name, 2)), 0
+    )
+
+    return match(
+        call_result,
+        {
+            "Ok": lambda ok: ic.candid_decode_values(ok, "text"),
+            "Err": lambda err: f"Err: {err}",
+        },
+    )
+
+
+@query
+def round_trip_values(profile: Profile) -> Profile:
+    encoded = ic.candid_encode_values("(Profile, nat)", (profile, 7))
+    decoded, count = ic.candid_decode_values(encoded, "(Profile, nat)")
+    assert count == 7
+    return decoded
+
+
+@query
+def encode_values_error() -> str:
+    try:
+        ic.candid_encode_values("(nat, text)", (-1, "x"))
+    except ValueError as e:
+        return str(e)
+    return "no error"
+
+
+@query
+def unknown_type_errors() -> Vec[str]:
+    errors = []
+    try:
+        ic.candid_encode_values("(Porfile, nat)", ({"name": "Ada"}, 7))
+    except ValueError as e:
+        errors.append(str(e))
+    try:
+        ic.candid_decode_values(ic.candid_encode_values("nat", 7), "Porfile")
+    except ValueError as e:
+        errors.append(str(e))
+    return errors
@@ -0,0 +1,55 @@
+from basilisk import (
+    Async,
+    CallResult,
+    composite_query,
+    ic,
+    match,
+    nat,
+    query,
+    Service,
+    service_query,
+    update,
+)
+
+
+class ThisCanister(Service):
+    @service_query
+    def get_counter(self) -> nat:
+        ...
+
+
+counter = 0
+
+
+@update
+def increment() -> nat:
+    global counter
+    counter += 1
+    return counter
+
+
+@query
+def get_counter() -> nat:
+    return counter
+
+
+@composite_query
+def get_counter_via_call() -> Async[nat]:
+    result: CallResult[nat] = yield ThisCanister(ic.id()).get_counter()
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda err: ic.trap(err)})
+
+
+@composite_query
+def get_counter_twice() -> Async[nat]:
+    first: CallResult[nat] = yield ThisCanister(ic.id()).get_counter()
+    second: CallResult[nat] = yield ThisCanister(ic.id()).get_counter()
+
+    return first["Ok"] + second["Ok"]
+
+
+@composite_query
+def get_counter_refund() -> Async[nat]:
+    result: CallResult[nat] = yield ThisCanister(ic.id()).get_counter()
+
+    return result.cycles_refunded
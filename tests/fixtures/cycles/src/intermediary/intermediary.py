@@ -1,5 +1,6 @@
 from basilisk import (
     Async,
+    blob,
     CallResult,
     ic,
     init,
@@ -11,11 +12,13 @@ from basilisk import (
     query,
     update,
     Variant,
+    Vec,
     void,
 )
 from src.cycles.types import Cycles
 
-cycles = Cycles(Principal.from_str("aaaaa-aa"))
+cycles_id = Principal.from_str("aaaaa-aa")
+cycles = Cycles(cycles_id)
 
 
 class SendCyclesResult(Variant, total=False):
@@ -29,8 +32,9 @@ class SendCyclesResult128(Variant, total=False):
 
 
 @init
-def init_(cycles_id: Principal) -> void:
-    global cycles
+def init_(cycles_canister_id: Principal) -> void:
+    global cycles, cycles_id
+    cycles_id = cycles_canister_id
     cycles = Cycles(cycles_id)
 
 
@@ -71,6 +75,67 @@ def send_cycles128() -> Async[SendCyclesResult128]:
     )
 
 
+# Sends an exact 128-bit amount through ic.call_raw128 and reports the refund
+
+
+@update
+def send_cycles128_raw(amount: nat) -> Async[SendCyclesResult128]:
+    result: CallResult[blob] = yield ic.call_raw128(
+        cycles_id, "receive_cycles128", ic.candid_encode("()"), amount
+    )
+
+    return match(
+        result,
+        {
+            "Ok": lambda _: {"Ok": ic.msg_cycles_refunded128()},
+            "Err": lambda err: {"Err": err},
+        },
+    )
+
+
+# More cycles than fit in 64 bits: the call must not go out with a truncated amount
+
+
+@update
+def send_cycles128_over_u64() -> Async[SendCyclesResult128]:
+    result: CallResult[nat] = yield cycles.receive_cycles128().with_cycles128(2**64 + 1_000_000)
+
+    return match(
+        result,
+        {
+            "Ok": lambda _: {"Ok": ic.msg_cycles_refunded128()},
+            "Err": lambda err: {"Err": err},
+        },
+    )
+
+
+# Sends two amounts at once and reports what came back for each call
+
+
+@update
+def send_cycles128_concurrently(first: nat, second: nat) -> Async[str]:
+    results: Vec[CallResult[blob]] = yield [
+        ic.call_raw128(cycles_id, "receive_cycles128", ic.candid_encode("()"), first),
+        ic.call_raw128(cycles_id, "receive_cycles128", ic.candid_encode("()"), second),
+    ]
+
+    return "|".join(str(result.cycles_refunded) for result in results)
+
+
+@update
+def send_cycles128_instructions() -> Async[str]:
+    first: CallResult[nat] = yield cycles.receive_cycles128().with_cycles128(1_000_000)
+    second: CallResult[nat] = yield cycles.receive_cycles128().with_cycles128(1_000_000)
+
+    counters = [
+        first.instructions_before,
+        first.instructions_after,
+        second.instructions_before,
+        second.instructions_after,
+    ]
+    return "|".join(str(counter) for counter in counters)
+
+
 @update
 def send_cycles128_notify() -> NotifyResult:
     return cycles.receive_cycles128().with_cycles128(1_000_000).notify()
@@ -0,0 +1,66 @@
+from basilisk import (
+    CallRejected,
+    ic,
+    Manual,
+    nat,
+    null,
+    query,
+    Service,
+    service_query,
+    text,
+    update,
+    Vec,
+)
+
+
+class ThisCanister(Service):
+    @service_query
+    def not_candid(self) -> nat:
+        ...
+
+
+calls = 0
+
+
+@query(zero_sized_quota=100)
+def count_nulls(items: Vec[null]) -> nat:
+    return len(items)
+
+
+@query
+def count_nulls_unlimited(items: Vec[null]) -> nat:
+    return len(items)
+
+
+@update(decoding_quota=2_000)
+def record_sum(values: Vec[nat]) -> nat:
+    global calls
+    calls += 1
+    return sum(values)
+
+
+@query
+def get_calls() -> nat:
+    return calls
+
+
+@query
+def not_candid() -> Manual[nat]:
+    ic.reply_raw(b"not candid")
+
+
+@update
+async def call_not_candid() -> text:
+    result = await ThisCanister(ic.id()).not_candid()
+    if result.Err is None:
+        return f"Ok {result.Ok!r}"
+    return f"Err {result.Err.code_name}: {result.Err.reject_message}"
+
+
+@update(raise_on_reject=True)
+async def call_not_candid_raising() -> text:
+    try:
+        await ThisCanister(ic.id()).not_candid()
+    except CallRejected as e:
+        return f"caught {e.error.code_name}"
+    return "not rejected"
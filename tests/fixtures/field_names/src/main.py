@@ -0,0 +1,43 @@
+from basilisk import (
+    Async,
+    CallResult,
+    empty,
+    ic,
+    Manual,
+    query,
+    Service,
+    service_query,
+    text,
+    update,
+)
+
+
+class ThisCanister(Service):
+    @service_query
+    def get_profile(self) -> text:
+        ...
+
+
+# A service whose type map mentions a field the canister's own methods never use
+class Directory(Service):
+    _return_types = {"lookup": "record { service_map_name : text }"}
+
+
+ic.register_field_names("registered_name")
+
+
+@query
+def get_profile() -> Manual[empty]:
+    ic.reply_raw(
+        ic.candid_encode(
+            '(record { service_map_name = "a"; registered_name = "b"; unknown_name = "c" })'
+        )
+    )
+
+
+@update
+def get_profile_keys() -> Async[text]:
+    # No return type is declared for the call, so field names come from the registry
+    result: CallResult[text] = yield ThisCanister(ic.id()).get_profile()
+
+    return ",".join(sorted(result["Ok"].keys()))
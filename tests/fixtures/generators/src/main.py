@@ -1,12 +1,25 @@
 from basilisk import (
     Async,
+    AsyncInfo,
     blob,
     CallResult,
+    gather,
+    ic,
     match,
+    nat,
+    nat8,
+    nat64,
     Principal,
+    query,
+    Record,
     Service,
+    service_method,
+    service_query,
     service_update,
+    text,
     update,
+    Vec,
+    void,
 )
 
 # TODO create a special system canisters module like in Azle
@@ -18,6 +31,42 @@ class ManagementCanister(Service):
         ...
 
 
+class Pair(Record):
+    n: nat
+    doubled: nat
+
+
+class ThisCanister(Service):
+    @service_query
+    def double(self, n: nat) -> nat:
+        ...
+
+    @service_query
+    def pair(self, n: nat) -> Pair:
+        ...
+
+    @service_update
+    def stall(self, seconds: nat) -> nat:
+        ...
+
+
+ThisCanister._return_types = {"pair": "Pair"}
+
+
+class LegacyThisCanister(Service):
+    @service_method
+    def double(self, n: nat) -> nat:
+        ...
+
+    @service_method
+    def pair(self, n: nat) -> Pair:
+        ...
+
+    @service_method
+    def shift(self, n: nat64, by: nat8) -> nat64:
+        ...
+
+
 @update
 def get_randomness_directly() -> Async[blob]:
     management_canister = ManagementCanister(Principal.from_str("aaaaa-aa"))
@@ -64,3 +113,185 @@ def get_randomness() -> Async[blob]:
     randomness_result: CallResult[blob] = yield management_canister.raw_rand()
 
     return match(randomness_result, {"Ok": lambda ok: ok, "Err": lambda _: bytes()})
+
+
+@update
+async def get_randomness_awaited() -> blob:
+    management_canister = ManagementCanister(Principal.from_str("aaaaa-aa"))
+
+    randomness_result: CallResult[blob] = await management_canister.raw_rand()
+
+    return match(randomness_result, {"Ok": lambda ok: ok, "Err": lambda _: bytes()})
+
+
+@update
+async def get_randomness_awaited_indirectly() -> blob:
+    randomness0 = await get_randomness_coroutine()
+    randomness1 = await get_randomness_coroutine()
+
+    return randomness0 + randomness1
+
+
+@update
+def get_randomness_from_coroutine() -> Async[blob]:
+    randomness: blob = yield get_randomness_coroutine()
+    return randomness
+
+
+async def get_randomness_coroutine() -> blob:
+    management_canister = ManagementCanister(Principal.from_str("aaaaa-aa"))
+
+    randomness_result: CallResult[blob] = await management_canister.raw_rand()
+
+    return match(randomness_result, {"Ok": lambda ok: ok, "Err": lambda _: bytes()})
+
+
+timer_randomness = bytes()
+
+
+async def store_timer_randomness():
+    global timer_randomness
+    timer_randomness = await get_randomness_coroutine()
+
+
+@update
+def set_async_timer() -> void:
+    ic.set_timer(0, store_timer_randomness)
+
+
+@query
+def get_timer_randomness_length() -> nat:
+    return len(timer_randomness)
+
+
+@query
+def double(n: nat) -> nat:
+    return n * 2
+
+
+@query
+def pair(n: nat) -> Pair:
+    return {"n": n, "doubled": n * 2}
+
+
+@query
+def shift(n: nat64, by: nat8) -> nat64:
+    return n << by
+
+
+@update
+async def pair_reply_class(n: nat) -> text:
+    result = await ThisCanister(ic.id()).pair(n)
+
+    return f"{type(result.Ok).__name__} {result.Ok['doubled']}"
+
+
+@update
+def double_concurrently(numbers: Vec[nat]) -> Async[Vec[nat]]:
+    this_canister = ThisCanister(ic.id())
+
+    results: list[CallResult[nat]] = yield [this_canister.double(n) for n in numbers]
+
+    return [match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0}) for result in results]
+
+
+@update
+async def double_gathered(numbers: Vec[nat]) -> Vec[nat]:
+    this_canister = ThisCanister(ic.id())
+
+    results = await gather(*[this_canister.double(n) for n in numbers])
+
+    return [match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0}) for result in results]
+
+
+@update
+def get_randomness_concurrently() -> Async[Vec[blob]]:
+    management_canister = ManagementCanister(Principal.from_str("aaaaa-aa"))
+
+    results: list[CallResult[blob]] = yield (
+        management_canister.raw_rand(),
+        management_canister.raw_rand(),
+    )
+
+    return [match(result, {"Ok": lambda ok: ok, "Err": lambda _: bytes()}) for result in results]
+
+
+@update
+async def stall(seconds: nat) -> nat:
+    # Keep the caller waiting by round-tripping through this canister until
+    # `seconds` have passed
+    this_canister = ThisCanister(ic.id())
+    deadline = ic.time() + seconds * 1_000_000_000
+    while ic.time() < deadline:
+        await this_canister.double(0)
+    return seconds
+
+
+@update
+async def double_with_timeout(n: nat) -> nat:
+    this_canister = ThisCanister(ic.id())
+
+    result: CallResult[nat] = await this_canister.double(n).with_timeout(10)
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+async def stall_with_timeout() -> text:
+    this_canister = ThisCanister(ic.id())
+
+    result: CallResult[nat] = await this_canister.stall(10).with_timeout(1)
+
+    return "timed out" if result.timed_out else f"not timed out: {result}"
+
+
+@update
+def double_with_descriptor(n: nat) -> Async[nat]:
+    result: CallResult[nat] = yield LegacyThisCanister(ic.id()).double(n)
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+def double_with_paid_descriptor(n: nat) -> Async[nat]:
+    result: CallResult[nat] = yield AsyncInfo("call", [ic.id(), "ThisCanister.double", n]).with_cycles128(0)
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+def double_with_raw_descriptor(n: nat) -> Async[nat]:
+    args_raw = ic.candid_encode(f"({n} : nat)")
+    result: CallResult[blob] = yield AsyncInfo("call_raw", [ic.id(), "double", args_raw, 0])
+
+    return match(result, {"Ok": lambda ok: ic.candid_decode_values(ok, "nat"), "Err": lambda _: 0})
+
+
+@update
+async def double_descriptors_concurrently(numbers: Vec[nat]) -> Vec[nat]:
+    this_canister = LegacyThisCanister(ic.id())
+
+    results = await gather(*[this_canister.double(n) for n in numbers])
+
+    return [match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0}) for result in results]
+
+
+@update
+def shift_with_descriptor(n: nat64, by: nat8) -> Async[nat64]:
+    result: CallResult[nat64] = yield LegacyThisCanister(ic.id()).shift(n, by)
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+def shift_with_paid_descriptor(n: nat64, by: nat8) -> Async[nat64]:
+    result: CallResult[nat64] = yield AsyncInfo("call", [ic.id(), "LegacyThisCanister.shift", n, by]).with_cycles(0)
+
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+def pair_with_descriptor(n: nat) -> Async[text]:
+    result: CallResult[Pair] = yield LegacyThisCanister(ic.id()).pair(n)
+
+    return f"{type(result.Ok).__name__} {result.Ok['doubled']}"
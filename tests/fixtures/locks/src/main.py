@@ -0,0 +1,79 @@
+from basilisk import (
+    CallResult,
+    gather,
+    ic,
+    Lock,
+    match,
+    nat,
+    query,
+    Service,
+    service_query,
+    service_update,
+    text,
+    update,
+    Vec,
+)
+
+
+class ThisCanister(Service):
+    @service_query
+    def double(self, n: nat) -> nat:
+        ...
+
+    @service_update
+    def locked_double(self, n: nat) -> nat:
+        ...
+
+    @service_update
+    def double_per_caller(self, n: nat) -> nat:
+        ...
+
+
+@query
+def double(n: nat) -> nat:
+    return n * 2
+
+
+@update
+async def locked_double(n: nat) -> nat:
+    with Lock("double"):
+        result: CallResult[nat] = await ThisCanister(ic.id()).double(n)
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+@update
+async def double_per_caller(n: nat) -> nat:
+    with Lock.for_caller("double"):
+        result: CallResult[nat] = await ThisCanister(ic.id()).double(n)
+    return match(result, {"Ok": lambda ok: ok, "Err": lambda _: 0})
+
+
+def describe(result: CallResult[nat]) -> text:
+    return match(result, {"Ok": lambda ok: f"ok {ok}", "Err": lambda err: f"err {err.reject_message}"})
+
+
+@update
+async def contend() -> Vec[text]:
+    # Both calls come from this canister, so the second finds the lock taken
+    this_canister = ThisCanister(ic.id())
+    results = await gather(this_canister.locked_double(1), this_canister.locked_double(2))
+    return [describe(result) for result in results]
+
+
+@update
+async def contend_per_caller() -> Vec[text]:
+    this_canister = ThisCanister(ic.id())
+    results = await gather(this_canister.double_per_caller(1), this_canister.double_per_caller(2))
+    return [describe(result) for result in results]
+
+
+@update
+async def trap_while_locked() -> nat:
+    Lock("trap").acquire()
+    await ThisCanister(ic.id()).double(1)
+    raise ValueError("failed while holding the lock")
+
+
+@query
+def is_locked(key: text) -> bool:
+    return Lock(key).locked
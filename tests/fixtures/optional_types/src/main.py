@@ -1,4 +1,4 @@
-from basilisk import Opt, query, Record, Vec
+from basilisk import nat, null, Opt, query, Record, Vec
 
 
 class Element(Record):
@@ -31,3 +31,22 @@ def get_head_with_elements() -> Opt[Head]:
 @query
 def get_element(element: Opt[Opt[Element]]) -> Opt[Opt[Element]]:
     return element
+
+
+@query(preserve_opt=True)
+def echo_nested(value: Opt[Opt[nat]]) -> Opt[Opt[nat]]:
+    return value
+
+
+@query(preserve_opt=True)
+def describe_nested(value: Opt[Opt[nat]]) -> str:
+    if value is None:
+        return "absent"
+    if value.value is None:
+        return "opt null"
+    return f"opt opt {value.value.value}"
+
+
+@query(preserve_opt=True)
+def describe_opt_null(value: Opt[null]) -> str:
+    return "absent" if value is None else "opt null"
@@ -0,0 +1,110 @@
+from basilisk import float64, ic, int64, nat, nat32, null, Opt, query, Record, Tuple, Variant, Vec
+
+
+class Tree(Variant, total=False):
+    Leaf: nat
+    Node: "Node"
+
+
+class Node(Record):
+    left: Tree
+    right: Tree
+
+
+class LinkedList(Record):
+    head: int64
+    tail: Opt["LinkedList"]
+
+
+class Json(Variant, total=False):
+    Null: null
+    Num: float64
+    Str: str
+    Arr: Vec["Json"]
+    Obj: Vec["JsonEntry"]
+
+
+JsonEntry = Tuple[str, Json]
+
+
+def _tree(depth: int) -> Tree:
+    if depth == 0:
+        return {"Leaf": 1}
+    return {"Node": {"left": _tree(depth - 1), "right": {"Leaf": depth}}}
+
+
+@query
+def make_tree(depth: nat32) -> Tree:
+    return _tree(depth)
+
+
+@query
+def sum_tree(tree: Tree) -> nat:
+    total = 0
+    stack = [tree]
+    while stack:
+        t = stack.pop()
+        if "Leaf" in t:
+            total += t["Leaf"]
+        else:
+            stack.append(t["Node"]["left"])
+            stack.append(t["Node"]["right"])
+    return total
+
+
+@query
+def make_list(length: nat32) -> Opt[LinkedList]:
+    lst = None
+    for i in range(length):
+        lst = {"head": i, "tail": lst}
+    return lst
+
+
+@query
+def list_length(lst: Opt[LinkedList]) -> nat32:
+    n = 0
+    while lst is not None:
+        n += 1
+        lst = lst["tail"]
+    return n
+
+
+@query
+def echo_json(value: Json) -> Json:
+    return value
+
+
+@query
+def sum_tree_typed(tree: Tree) -> nat:
+    # Decoded values are Tree / Node instances with attribute access
+    total = 0
+    stack = [tree]
+    while stack:
+        t = stack.pop()
+        if not isinstance(t, Tree):
+            ic.trap(f"expected Tree, got {type(t).__name__}")
+        if t.Leaf is not None:
+            total += t.Leaf
+        else:
+            if not isinstance(t.Node, Node):
+                ic.trap(f"expected Node, got {type(t.Node).__name__}")
+            stack.append(t.Node.left)
+            stack.append(t.Node.right)
+    return total
+
+
+@query
+def mirror_tree(tree: Tree) -> Tree:
+    stack = [tree]
+    while stack:
+        t = stack.pop()
+        if t.Node is not None:
+            t.Node.left, t.Node.right = t.Node.right, t.Node.left
+            stack.append(t.Node.left)
+            stack.append(t.Node.right)
+    return tree
+
+
+@query
+def list_type_names(lst: LinkedList) -> str:
+    return f"{type(lst).__name__}/{type(lst.tail).__name__}"
@@ -1,12 +1,24 @@
 from basilisk import (
     Async,
+    CallError,
+    CallRejected,
+    CallResult,
+    empty,
+    gather,
     ic,
     init,
+    nat32,
+    nat64,
+    Opt,
     Principal,
+    query,
+    Record,
+    Reject,
     RejectionCode,
     Service,
     service_update,
     update,
+    Variant,
     void,
 )
 from src.some_service.types import SomeService
@@ -56,3 +68,153 @@ def get_rejection_code_canister_error() -> Async[RejectionCode]:
 def get_rejection_message(message: str) -> Async[str]:
     yield some_service.reject(message)
     return ic.reject_message()
+
+
+class CallErrorInfo(Record):
+    code_name: str
+    code_number: nat32
+    reject_message: str
+    error_code: Opt[str]
+    matches_reject_code: bool
+    as_text: str
+
+
+def call_error_info(err: CallError) -> CallErrorInfo:
+    return {
+        "code_name": err.code_name,
+        "code_number": err.code_number,
+        "reject_message": err.reject_message,
+        "error_code": err.error_code,
+        "matches_reject_code": err.reject_code == ic.reject_code(),
+        "as_text": err,
+    }
+
+
+@update
+def get_call_error_canister_reject(message: str) -> Async[CallErrorInfo]:
+    result: CallResult[empty] = yield some_service.reject(message)
+    return call_error_info(result.Err)
+
+
+@update
+def get_call_error_canister_error() -> Async[CallErrorInfo]:
+    result: CallResult[empty] = yield some_service.error()
+    return call_error_info(result.Err)
+
+
+@update
+def get_call_error_destination_invalid() -> Async[CallErrorInfo]:
+    result: CallResult[void] = yield nonexistent_canister.method()
+    return call_error_info(result.Err)
+
+
+@update(raise_on_reject=True)
+def catch_rejection(message: str) -> Async[str]:
+    try:
+        yield some_service.reject(message)
+    except CallRejected as e:
+        return f"caught {e.error.code_name}: {e.reject_message}"
+    return "not rejected"
+
+
+@update(raise_on_reject=True)
+async def catch_rejection_awaited(message: str) -> str:
+    try:
+        await some_service.accept()
+        await some_service.reject(message)
+    except CallRejected as e:
+        return f"caught {e.error.code_name}: {e.reject_message}"
+    return "not rejected"
+
+
+@update(raise_on_reject=True)
+async def catch_gathered_rejection() -> str:
+    try:
+        await gather(some_service.accept(), some_service.reject("second"))
+    except CallRejected as e:
+        return f"caught {e.reject_message} after {e.results[0].Ok}"
+    return "not rejected"
+
+
+cleanups = 0
+
+
+@update(raise_on_reject=True)
+def reject_after_cleanup() -> Async[str]:
+    global cleanups
+    yield some_service.accept()
+    try:
+        yield some_service.reject("downstream failed")
+    finally:
+        cleanups += 1
+    return "not rejected"
+
+
+@query
+def get_cleanups() -> nat64:
+    return cleanups
+
+
+class CountResult(Variant, total=False):
+    Ok: nat64
+    Err: str
+
+
+count = 0
+
+
+@update
+def raise_reject(message: str) -> str:
+    raise Reject(message)
+
+
+@update
+def raise_reject_after_call(message: str) -> Async[str]:
+    yield some_service.accept()
+    raise Reject(message)
+
+
+class InsufficientFunds(Reject):
+    pass
+
+
+@update
+def raise_reject_subclass(message: str) -> str:
+    raise InsufficientFunds(message)
+
+
+@update
+def raise_lookalike_reject(message: str) -> str:
+    class Reject(Exception):
+        pass
+
+    raise Reject(message)
+
+
+@update(err_on_exception=True)
+def increment_then_fail() -> CountResult:
+    global count
+    count += 1
+    raise ValueError("count is now " + str(count))
+
+
+@update
+def increment_then_trap() -> CountResult:
+    global count
+    count += 1
+    raise ValueError("count is now " + str(count))
+
+
+@update(raise_on_reject=True)
+def increment_then_fail_before_await() -> Async[str]:
+    global count
+    count += 1
+    if count > 0:
+        raise ValueError("count is now " + str(count))
+    yield some_service.accept()
+    return "unreachable"
+
+
+@query
+def get_count() -> nat64:
+    return count
@@ -1,8 +1,11 @@
 from basilisk import (
     Async,
     CallResult,
+    Func,
+    FuncRef,
     ic,
     Principal,
+    Query,
     query,
     Service,
     service_query,
@@ -90,3 +93,31 @@ def test_candid_encode_error() -> text:
         return "ERROR: should have raised"
     except Exception as e:
         return f"caught: {type(e).__name__}: {e}"
+
+
+EventCallback = Func(Query[[text], text])
+
+
+@query
+def make_callback(target: Principal) -> EventCallback:
+    return FuncRef(target, "on_event")
+
+
+@query
+def describe_callback(callback: EventCallback) -> text:
+    return f"{callback.principal.to_str()}.{callback.method} : {callback.signature}"
+
+
+@update
+def invoke_callback(callback: EventCallback, value: text) -> Async[Update1Result]:
+    result: CallResult[text] = yield callback(value)
+
+    if result.Err is not None:
+        return {"Err": result.Err}
+
+    return {"Ok": result.Ok}
+
+
+@query
+def service_ref_arg_type(some_service: SomeService, method: text) -> text:
+    return some_service._arg_types.get(method, "")
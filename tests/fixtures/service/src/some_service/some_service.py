@@ -14,3 +14,8 @@ def update1() -> str:
 @update
 def echo_text(payload: text) -> text:
     return payload
+
+
+@query
+def on_event(value: text) -> text:
+    return f"handled {value}"
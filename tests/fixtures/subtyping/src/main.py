@@ -0,0 +1,26 @@
+from basilisk import nat32, nat64, Opt, query, Record, text
+
+
+class Profile(Record):
+    name: text
+    nickname: Opt[text]
+
+
+@query
+def widen(n: nat64) -> nat64:
+    return n
+
+
+@query
+def nickname(profile: Profile) -> text:
+    return profile["nickname"] if profile["nickname"] is not None else "none"
+
+
+@query
+def profile_name(profile: Profile) -> text:
+    return profile["name"]
+
+
+@query
+def count(n: nat32) -> nat32:
+    return n
@@ -1,5 +1,6 @@
 from basilisk import (
     Async,
+    BackgroundError,
     blob,
     CallResult,
     Duration,
@@ -10,6 +11,7 @@ from basilisk import (
     Record,
     TimerId,
     update,
+    Vec,
     void,
 )
 from basilisk.canisters.management import management_canister
@@ -33,6 +35,16 @@ class TimerIds(Record):
     repeat_cross_canister: TimerId
 
 
+handled_errors: list = []
+
+
+def record_error(error: BackgroundError):
+    handled_errors.append(f"{error['callback']}: {error['exception_type']}")
+
+
+ic.set_error_handler(record_error)
+
+
 status: StatusReport = {
     "single": False,
     "inline": 0,
@@ -132,3 +144,28 @@ def repeat_cross_canister_timer_callback() -> Async[void]:
         status["repeat_cross_canister"] += ok
 
     match(result, {"Ok": handle_ok, "Err": lambda err: ic.print(err)})
+
+
+@update
+def set_failing_timers(delay: Duration) -> void:
+    ic.set_timer(delay, failing_timer_callback)
+    ic.set_timer(delay, failing_cross_canister_timer_callback)
+
+
+@query
+def background_errors() -> Vec[BackgroundError]:
+    return ic.background_errors()
+
+
+@query
+def get_handled_errors() -> Vec[str]:
+    return handled_errors
+
+
+def failing_timer_callback():
+    raise ValueError("timer failed")
+
+
+def failing_cross_canister_timer_callback() -> Async[void]:
+    yield management_canister.raw_rand()
+    raise KeyError("missing after the call")
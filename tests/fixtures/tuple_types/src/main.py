@@ -1,6 +1,6 @@
 # TODO Add in Canister types like in the Azle tuple_types example
 
-from basilisk import nat8, nat64, null, Principal, query, Record, Tuple, Vec, Variant
+from basilisk import nat8, nat64, null, Principal, query, Record, Returns, Tuple, Vec, Variant
 
 
 class User(Record):
@@ -216,3 +216,18 @@ def nested_tuple_query(
     param: Tuple[Tuple[str, Tuple[nat8, nat8]], int]
 ) -> Tuple[Tuple[str, Tuple[nat8, nat8]], int]:
     return param
+
+
+@query
+def multiple_return_values() -> Returns[str, nat64]:
+    return ("Hello", 42)
+
+
+@query
+def multiple_return_values_param(param: PrimitiveTwoTuple) -> Returns[str, nat64]:
+    return param
+
+
+@query
+def no_return_values() -> Returns[()]:
+    return None
@@ -359,7 +359,7 @@ def _get_canister_id(example_dir, canister_name):
 # Canister call helpers
 # ---------------------------------------------------------------------------
 
-def call_canister(canister_id, method, args=None, *, example_dir=None, update=False):
+def call_canister(canister_id, method, args=None, *, example_dir=None, update=False, candid=None, raw=False):
     """Call a canister method via dfx and return the parsed result.
 
     Args:
@@ -368,6 +368,9 @@ def call_canister(canister_id, method, args=None, *, example_dir=None, update=Fa
         args: Optional Candid argument string, e.g. '("hello")'.
         example_dir: Working directory for dfx (needed for local replica).
         update: If True, force update call. By default dfx auto-detects.
+        candid: Optional .did file to encode the arguments (and decode the
+            reply) with instead of the canister's own interface.
+        raw: If True, args is a hex-encoded Candid message sent as is.
 
     Returns:
         The raw Candid response string from dfx.
@@ -381,6 +384,10 @@ def call_canister(canister_id, method, args=None, *, example_dir=None, update=Fa
         cmd.append(args)
     if update:
         cmd.append("--update")
+    if candid:
+        cmd.extend(["--candid", candid])
+    if raw:
+        cmd.extend(["--type", "raw"])
 
     cwd = (info["example_dir"] if info else None) or example_dir or EXAMPLES_DIR
     result = subprocess.run(
@@ -399,13 +406,17 @@ def call_canister(canister_id, method, args=None, *, example_dir=None, update=Fa
     return result.stdout.strip()
 
 
-def call_canister_expect_trap(canister_id, method, args=None, *, example_dir=None):
+def call_canister_expect_trap(canister_id, method, args=None, *, example_dir=None, candid=None, raw=False):
     """Call a canister method expecting it to trap. Returns the error message."""
     info = _CANDID_MAP.get(canister_id)
     target = info["name"] if info else canister_id
     cmd = ["dfx", "canister", "call", target, method]
     if args:
         cmd.append(args)
+    if candid:
+        cmd.extend(["--candid", candid])
+    if raw:
+        cmd.extend(["--type", "raw"])
 
     cwd = (info["example_dir"] if info else None) or example_dir or EXAMPLES_DIR
     result = subprocess.run(
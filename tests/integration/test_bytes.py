@@ -1,7 +1,7 @@
 """Integration tests for tests/fixtures/bytes — bytes roundtrip."""
 
 import pytest
-from .conftest import deploy_example, call_canister, EXAMPLES_DIR
+from .conftest import deploy_example, call_canister, parse_candid_text, EXAMPLES_DIR
 import os
 
 EXAMPLE = "bytes"
@@ -22,3 +22,9 @@ def test_get_bytes_roundtrip(canister):
 def test_get_bytes_empty(canister):
     raw = call_canister(canister, "get_bytes", '(blob "")', example_dir=EXAMPLE_DIR)
     assert "blob" in raw
+
+
+def test_get_bytes_length_large(canister):
+    payload = "".join("\\%02x" % (i % 256) for i in range(8192))
+    raw = call_canister(canister, "get_bytes_length", f'(blob "{payload}")', example_dir=EXAMPLE_DIR)
+    assert parse_candid_text(raw) == 8192
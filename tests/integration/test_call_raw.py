@@ -30,3 +30,27 @@ def test_execute_call_raw128(canister):
         example_dir=EXAMPLE_DIR,
     )
     assert "Ok" in raw
+
+
+def test_execute_typed_call_raw(canister):
+    raw = call_canister(canister, "execute_typed_call_raw", '("Ada")', example_dir=EXAMPLE_DIR)
+    assert "Hello, Ada! Hello, Ada!" in raw
+
+
+def test_round_trip_values(canister):
+    raw = call_canister(
+        canister, "round_trip_values",
+        '(record { name = "Ada"; scores = vec { 1; 2; 3 }; nickname = opt "ada" })',
+        example_dir=EXAMPLE_DIR,
+    )
+    assert '"Ada"' in raw and '"ada"' in raw and "3" in raw
+
+
+def test_encode_values_error(canister):
+    raw = call_canister(canister, "encode_values_error", example_dir=EXAMPLE_DIR)
+    assert "candid_encode_values" in raw
+
+
+def test_unknown_value_type_raises(canister):
+    raw = call_canister(canister, "unknown_type_errors", example_dir=EXAMPLE_DIR)
+    assert raw.count("unknown Candid type 'Porfile'") == 2
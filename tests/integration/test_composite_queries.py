@@ -0,0 +1,37 @@
+"""Integration tests for tests/fixtures/composite_queries — @composite_query with inter-canister query calls."""
+
+import pytest
+from .conftest import deploy_example, call_canister, parse_candid_text, EXAMPLES_DIR
+import os
+
+EXAMPLE = "composite_queries"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_get_counter_via_call(canister):
+    call_canister(canister, "increment", example_dir=EXAMPLE_DIR)
+    direct = parse_candid_text(call_canister(canister, "get_counter", example_dir=EXAMPLE_DIR))
+    via_call = parse_candid_text(call_canister(canister, "get_counter_via_call", example_dir=EXAMPLE_DIR))
+    assert via_call == direct
+
+
+def test_get_counter_twice(canister):
+    direct = parse_candid_text(call_canister(canister, "get_counter", example_dir=EXAMPLE_DIR))
+    twice = parse_candid_text(call_canister(canister, "get_counter_twice", example_dir=EXAMPLE_DIR))
+    assert twice == 2 * direct
+
+
+def test_composite_query_call_reports_no_refund(canister):
+    refund = parse_candid_text(call_canister(canister, "get_counter_refund", example_dir=EXAMPLE_DIR))
+    assert refund == 0
+
+
+def test_candid_interface_marks_composite_query(canister):
+    raw = call_canister(canister, "__get_candid_interface_tmp_hack", example_dir=EXAMPLE_DIR)
+    assert "() -> (nat) composite_query;" in raw
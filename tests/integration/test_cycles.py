@@ -1,7 +1,7 @@
 """Integration tests for tests/fixtures/cycles — cycle transfer between canisters."""
 
 import pytest
-from .conftest import deploy_example, call_canister, EXAMPLES_DIR
+from .conftest import deploy_example, call_canister, call_canister_expect_trap, parse_candid_text, EXAMPLES_DIR
 import os
 
 EXAMPLE = "cycles"
@@ -18,3 +18,41 @@ def test_send_cycles(canisters):
     intermediary = canisters.get("intermediary") or list(canisters.values())[-1]
     raw = call_canister(intermediary, "send_cycles", example_dir=EXAMPLE_DIR, update=True)
     assert len(raw) > 0
+
+
+def test_send_cycles128_raw_carries_exact_amount(canisters):
+    intermediary = canisters.get("intermediary") or list(canisters.values())[-1]
+    raw = call_canister(intermediary, "send_cycles128_raw", "(1_000_000 : nat)", example_dir=EXAMPLE_DIR, update=True)
+    # The cycles canister accepts half of what it was sent and refunds the rest
+    assert "500_000" in raw or "500000" in raw
+
+
+def test_send_cycles128_over_u64_is_not_truncated(canisters):
+    intermediary = canisters.get("intermediary") or list(canisters.values())[-1]
+    # The intermediary cannot afford 2**64 + 1_000_000 cycles, so the call must
+    # fail instead of going out with the amount cut to 64 bits (or to 0)
+    call_canister_expect_trap(intermediary, "send_cycles128_over_u64", example_dir=EXAMPLE_DIR)
+
+
+def test_call_results_report_their_own_refund(canisters):
+    intermediary = canisters.get("intermediary") or list(canisters.values())[-1]
+    raw = call_canister(
+        intermediary,
+        "send_cycles128_concurrently",
+        "(1_000_000 : nat, 3_000_000 : nat)",
+        example_dir=EXAMPLE_DIR,
+        update=True,
+    )
+    # Each call gets back half of its own amount, not whatever arrived last
+    refunds = [int(refund) for refund in parse_candid_text(raw).split("|")]
+    assert refunds == [500_000, 1_500_000]
+
+
+def test_call_results_report_instruction_counters(canisters):
+    intermediary = canisters.get("intermediary") or list(canisters.values())[-1]
+    raw = call_canister(intermediary, "send_cycles128_instructions", example_dir=EXAMPLE_DIR, update=True)
+    first_before, first_after, second_before, second_after = (
+        int(counter) for counter in parse_candid_text(raw).split("|")
+    )
+    # The counter grows across each call and keeps growing between them
+    assert first_before < first_after <= second_before < second_after
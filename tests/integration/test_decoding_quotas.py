@@ -0,0 +1,62 @@
+"""Integration tests for tests/fixtures/decoding_quotas — per-method Candid decoding quotas."""
+
+import pytest
+from .conftest import deploy_example, call_canister, call_canister_expect_trap, EXAMPLES_DIR
+import os
+
+EXAMPLE = "decoding_quotas"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+
+
+def _vec(item, count):
+    return "(vec { " + "; ".join([item] * count) + " })"
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_zero_sized_within_quota(canister):
+    raw = call_canister(canister, "count_nulls", _vec("null", 100), example_dir=EXAMPLE_DIR)
+    assert "100" in raw
+
+
+def test_zero_sized_over_quota(canister):
+    err = call_canister_expect_trap(canister, "count_nulls", _vec("null", 101), example_dir=EXAMPLE_DIR)
+    assert "zero-sized values quota exceeded" in err
+
+
+def test_zero_sized_quota_stops_huge_vector_before_decoding(canister):
+    # vec null claiming a billion elements, in a 14-byte message
+    message = "4449444c016d7f0100" + "8094ebdc03"
+    err = call_canister_expect_trap(canister, "count_nulls", message, example_dir=EXAMPLE_DIR, raw=True)
+    assert "zero-sized values quota exceeded" in err
+
+
+def test_no_quota_by_default(canister):
+    raw = call_canister(canister, "count_nulls_unlimited", _vec("null", 1000), example_dir=EXAMPLE_DIR)
+    assert "1_000" in raw or "1000" in raw
+
+
+def test_decoding_within_quota(canister):
+    raw = call_canister(canister, "record_sum", _vec("1", 10), example_dir=EXAMPLE_DIR, update=True)
+    assert "10" in raw
+
+
+def test_decoding_over_quota_rejects_before_python_runs(canister):
+    before = call_canister(canister, "get_calls", example_dir=EXAMPLE_DIR)
+    err = call_canister_expect_trap(canister, "record_sum", _vec("1", 1000), example_dir=EXAMPLE_DIR)
+    assert "decoding quota exceeded" in err
+    assert call_canister(canister, "get_calls", example_dir=EXAMPLE_DIR) == before
+
+
+def test_undecodable_reply_is_err(canister):
+    raw = call_canister(canister, "call_not_candid", example_dir=EXAMPLE_DIR, update=True)
+    assert "Err CanisterError: failed to decode canister response" in raw
+
+
+def test_undecodable_reply_raises_with_raise_on_reject(canister):
+    raw = call_canister(canister, "call_not_candid_raising", example_dir=EXAMPLE_DIR, update=True)
+    assert "caught CanisterError" in raw
@@ -0,0 +1,22 @@
+"""Integration tests for tests/fixtures/field_names — field-name recovery for untyped replies."""
+
+import pytest
+from .conftest import deploy_example, call_canister, parse_candid_text, EXAMPLES_DIR
+import os
+
+EXAMPLE = "field_names"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_field_names_recovered_from_registry(canister):
+    keys = parse_candid_text(call_canister(canister, "get_profile_keys", example_dir=EXAMPLE_DIR)).split(",")
+    assert "service_map_name" in keys
+    assert "registered_name" in keys
+    assert "unknown_name" not in keys
+    assert len(keys) == 3
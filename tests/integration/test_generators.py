@@ -1,5 +1,6 @@
-"""Integration tests for tests/fixtures/generators — async generator (yield) patterns."""
+"""Integration tests for tests/fixtures/generators — async generator (yield) and async def patterns."""
 
+import time
 import pytest
 from .conftest import deploy_example, call_canister, EXAMPLES_DIR
 import os
@@ -29,3 +30,93 @@ def test_get_randomness_indirectly(canister):
 def test_get_randomness_super_indirectly(canister):
     raw = call_canister(canister, "get_randomness_super_indirectly", example_dir=EXAMPLE_DIR)
     assert "blob" in raw
+
+
+def test_get_randomness_awaited(canister):
+    raw = call_canister(canister, "get_randomness_awaited", example_dir=EXAMPLE_DIR)
+    assert "blob" in raw
+
+
+def test_get_randomness_awaited_indirectly(canister):
+    raw = call_canister(canister, "get_randomness_awaited_indirectly", example_dir=EXAMPLE_DIR)
+    assert "blob" in raw
+
+
+def test_get_randomness_from_coroutine(canister):
+    raw = call_canister(canister, "get_randomness_from_coroutine", example_dir=EXAMPLE_DIR)
+    assert "blob" in raw
+
+
+def test_async_timer_callback(canister):
+    call_canister(canister, "set_async_timer", example_dir=EXAMPLE_DIR)
+    for _ in range(10):
+        time.sleep(2)
+        raw = call_canister(canister, "get_timer_randomness_length", example_dir=EXAMPLE_DIR)
+        if "32" in raw:
+            break
+    assert "32" in raw
+
+
+def test_typed_reply_decodes_into_record_class(canister):
+    raw = call_canister(canister, "pair_reply_class", "(3)", example_dir=EXAMPLE_DIR)
+    assert "Pair 6" in raw
+
+
+def test_double_concurrently(canister):
+    raw = call_canister(canister, "double_concurrently", "(vec { 1; 2; 3; 4 })", example_dir=EXAMPLE_DIR)
+    assert "2; 4; 6; 8" in raw.replace(" : nat", "")
+
+
+def test_double_gathered(canister):
+    raw = call_canister(canister, "double_gathered", "(vec { 5; 6 })", example_dir=EXAMPLE_DIR)
+    assert "10; 12" in raw.replace(" : nat", "")
+
+
+def test_get_randomness_concurrently(canister):
+    raw = call_canister(canister, "get_randomness_concurrently", example_dir=EXAMPLE_DIR)
+    assert raw.count("blob") == 2
+
+
+def test_double_with_timeout(canister):
+    raw = call_canister(canister, "double_with_timeout", "(21)", example_dir=EXAMPLE_DIR)
+    assert "42" in raw
+
+
+def test_stall_with_timeout(canister):
+    raw = call_canister(canister, "stall_with_timeout", example_dir=EXAMPLE_DIR)
+    assert "timed out" in raw and "not timed out" not in raw
+
+
+def test_double_with_descriptor(canister):
+    raw = call_canister(canister, "double_with_descriptor", "(4)", example_dir=EXAMPLE_DIR)
+    assert "8" in raw
+
+
+def test_double_with_paid_descriptor(canister):
+    raw = call_canister(canister, "double_with_paid_descriptor", "(5)", example_dir=EXAMPLE_DIR)
+    assert "10" in raw
+
+
+def test_double_with_raw_descriptor(canister):
+    raw = call_canister(canister, "double_with_raw_descriptor", "(6)", example_dir=EXAMPLE_DIR)
+    assert "12" in raw
+
+
+def test_double_descriptors_concurrently(canister):
+    raw = call_canister(canister, "double_descriptors_concurrently", "(vec { 7; 8 })", example_dir=EXAMPLE_DIR)
+    assert "14; 16" in raw.replace(" : nat", "")
+
+
+def test_descriptor_encodes_typed_args(canister):
+    raw = call_canister(canister, "shift_with_descriptor", "(3 : nat64, 4 : nat8)", example_dir=EXAMPLE_DIR)
+    assert "48" in raw
+
+
+def test_paid_descriptor_encodes_typed_args(canister):
+    raw = call_canister(canister, "shift_with_paid_descriptor", "(5 : nat64, 1 : nat8)", example_dir=EXAMPLE_DIR)
+    assert "10" in raw
+
+
+def test_descriptor_reply_decodes_into_record_class(canister):
+    raw = call_canister(canister, "pair_with_descriptor", "(3)", example_dir=EXAMPLE_DIR)
+    assert "Pair 6" in raw
@@ -27,3 +27,9 @@ def test_get_reaction(canister):
 def test_get_owner(canister):
     raw = call_canister(canister, "get_owner", example_dir=EXAMPLE_DIR)
     assert "principal" in raw.lower() or len(raw) > 5
+
+
+def test_candid_interface_has_init_args(canister):
+    raw = call_canister(canister, "__get_candid_interface_tmp_hack", example_dir=EXAMPLE_DIR)
+    assert "service : (User, Reaction, principal) -> {" in raw
+    assert "type User = record { id : text };" in raw
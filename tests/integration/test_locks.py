@@ -0,0 +1,40 @@
+"""Integration tests for tests/fixtures/locks — basilisk.Lock held across awaits."""
+
+import pytest
+from .conftest import deploy_example, call_canister, call_canister_expect_trap, EXAMPLES_DIR
+import os
+
+EXAMPLE = "locks"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_lock_held_across_await(canister):
+    raw = call_canister(canister, "locked_double", "(21)", example_dir=EXAMPLE_DIR)
+    assert "42" in raw
+    assert "false" in call_canister(canister, "is_locked", '("double")', example_dir=EXAMPLE_DIR)
+
+
+def test_concurrent_call_is_rejected(canister):
+    raw = call_canister(canister, "contend", example_dir=EXAMPLE_DIR)
+    assert "ok 2" in raw
+    assert "err Lock 'double' is held by another call" in raw
+    assert "false" in call_canister(canister, "is_locked", '("double")', example_dir=EXAMPLE_DIR)
+
+
+def test_per_caller_lock(canister):
+    # Both calls come from the same caller (this canister)
+    raw = call_canister(canister, "contend_per_caller", example_dir=EXAMPLE_DIR)
+    assert "ok 2" in raw
+    assert "is held by another call" in raw
+
+
+def test_lock_released_when_method_traps(canister):
+    err = call_canister_expect_trap(canister, "trap_while_locked", example_dir=EXAMPLE_DIR)
+    assert "failed while holding the lock" in err
+    assert "false" in call_canister(canister, "is_locked", '("trap")', example_dir=EXAMPLE_DIR)
@@ -32,3 +32,33 @@ def test_get_head_with_elements(canister):
 def test_get_element_empty(canister):
     raw = call_canister(canister, "get_element", "(opt opt record { id = \"test\" })", example_dir=EXAMPLE_DIR)
     assert "test" in raw or "null" in raw
+
+
+def test_describe_nested_absent(canister):
+    raw = call_canister(canister, "describe_nested", "(null)", example_dir=EXAMPLE_DIR)
+    assert '"absent"' in raw
+
+
+def test_describe_nested_opt_null(canister):
+    raw = call_canister(canister, "describe_nested", "(opt null)", example_dir=EXAMPLE_DIR)
+    assert '"opt null"' in raw
+
+
+def test_describe_nested_value(canister):
+    raw = call_canister(canister, "describe_nested", "(opt opt 7)", example_dir=EXAMPLE_DIR)
+    assert '"opt opt 7"' in raw
+
+
+def test_echo_nested_keeps_opt_null(canister):
+    raw = call_canister(canister, "echo_nested", "(opt null)", example_dir=EXAMPLE_DIR)
+    assert "opt null" in raw
+
+
+def test_echo_nested_keeps_value(canister):
+    raw = call_canister(canister, "echo_nested", "(opt opt 7)", example_dir=EXAMPLE_DIR)
+    assert "opt opt (7 : nat)" in raw or "opt opt 7" in raw
+
+
+def test_describe_opt_null(canister):
+    assert '"opt null"' in call_canister(canister, "describe_opt_null", "(opt null)", example_dir=EXAMPLE_DIR)
+    assert '"absent"' in call_canister(canister, "describe_opt_null", "(null)", example_dir=EXAMPLE_DIR)
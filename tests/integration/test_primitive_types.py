@@ -45,6 +45,13 @@ def test_print_int(canister):
     assert result == 42
 
 
+def test_print_int_beyond_128_bits(canister):
+    result = parse_candid_text(
+        call_canister(canister, "print_int", "(-340_282_366_920_938_463_463_374_607_431_768_211_457 : int)", example_dir=EXAMPLE_DIR)
+    )
+    assert result == -340_282_366_920_938_463_463_374_607_431_768_211_457
+
+
 def test_get_int64(canister):
     result = parse_candid_text(call_canister(canister, "get_int64", example_dir=EXAMPLE_DIR))
     assert result == 9_223_372_036_854_775_807
@@ -70,6 +77,13 @@ def test_get_nat(canister):
     assert result == 340_282_366_920_938_463_463_374_607_431_768_211_455
 
 
+def test_print_nat_beyond_128_bits(canister):
+    result = parse_candid_text(
+        call_canister(canister, "print_nat", "(340_282_366_920_938_463_463_374_607_431_768_211_456 : nat)", example_dir=EXAMPLE_DIR)
+    )
+    assert result == 340_282_366_920_938_463_463_374_607_431_768_211_456
+
+
 def test_get_nat64(canister):
     result = parse_candid_text(call_canister(canister, "get_nat64", example_dir=EXAMPLE_DIR))
     assert result == 18_446_744_073_709_551_615
@@ -0,0 +1,78 @@
+"""Integration tests for tests/fixtures/recursive_types — self-referential Record/Variant types."""
+
+import pytest
+from .conftest import deploy_example, call_canister, parse_candid_text, EXAMPLES_DIR
+import os
+
+EXAMPLE = "recursive_types"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_make_tree(canister):
+    raw = call_canister(canister, "make_tree", "(2 : nat32)", example_dir=EXAMPLE_DIR)
+    assert raw.count("Node") == 2
+    assert raw.count("Leaf") == 3
+
+
+def test_sum_tree(canister):
+    tree = "variant { Node = record { left = variant { Node = record { left = variant { Leaf = 1 }; right = variant { Leaf = 2 } } }; right = variant { Leaf = 3 } } }"
+    raw = call_canister(canister, "sum_tree", f"({tree})", example_dir=EXAMPLE_DIR)
+    assert parse_candid_text(raw) == 6
+
+
+def test_deep_tree_roundtrip(canister):
+    # Deeper than the old 16-level limit on named type expansion
+    raw = call_canister(canister, "make_tree", "(40 : nat32)", example_dir=EXAMPLE_DIR)
+    assert raw.count("Node") == 40
+
+
+def test_make_list(canister):
+    raw = call_canister(canister, "make_list", "(3 : nat32)", example_dir=EXAMPLE_DIR)
+    assert raw.count("head") == 3
+
+
+def test_list_length_long(canister):
+    lst = "null"
+    for i in range(100):
+        lst = f"opt record {{ head = {i} : int64; tail = {lst} }}"
+    raw = call_canister(canister, "list_length", f"({lst})", example_dir=EXAMPLE_DIR)
+    assert parse_candid_text(raw) == 100
+
+
+def test_echo_json(canister):
+    value = 'variant { Obj = vec { record { "a"; variant { Arr = vec { variant { Num = 1.5 : float64 }; variant { Null }; variant { Str = "x" } } } } } }'
+    raw = call_canister(canister, "echo_json", f"({value})", example_dir=EXAMPLE_DIR)
+    assert '"a"' in raw
+    assert "1.5" in raw
+    assert '"x"' in raw
+
+
+def test_candid_interface(canister):
+    raw = call_canister(canister, "__get_candid_interface_tmp_hack", example_dir=EXAMPLE_DIR)
+    assert "type Tree = variant { Leaf : nat; Node : Node };" in raw
+    assert "make_tree" in raw and "(nat32) -> (Tree) query;" in raw
+    assert "service : {" in raw
+
+
+def test_decoded_values_are_record_and_variant_instances(canister):
+    tree = "variant { Node = record { left = variant { Node = record { left = variant { Leaf = 1 }; right = variant { Leaf = 2 } } }; right = variant { Leaf = 3 } } }"
+    raw = call_canister(canister, "sum_tree_typed", f"({tree})", example_dir=EXAMPLE_DIR)
+    assert parse_candid_text(raw) == 6
+
+
+def test_instances_encode_back(canister):
+    tree = "variant { Node = record { left = variant { Leaf = 1 }; right = variant { Leaf = 2 } } }"
+    raw = call_canister(canister, "mirror_tree", f"({tree})", example_dir=EXAMPLE_DIR)
+    assert raw.index("Leaf = 2") < raw.index("Leaf = 1")
+
+
+def test_nested_record_classes(canister):
+    lst = "record { head = 1 : int64; tail = opt record { head = 2 : int64; tail = null } }"
+    raw = call_canister(canister, "list_type_names", f"({lst})", example_dir=EXAMPLE_DIR)
+    assert parse_candid_text(raw) == "LinkedList/LinkedList"
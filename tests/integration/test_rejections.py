@@ -1,7 +1,7 @@
 """Integration tests for tests/fixtures/rejections — rejection code/message handling."""
 
 import pytest
-from .conftest import deploy_example, call_canister, EXAMPLES_DIR
+from .conftest import deploy_example, call_canister, call_canister_expect_trap, EXAMPLES_DIR
 import os
 
 EXAMPLE = "rejections"
@@ -22,3 +22,94 @@ def test_get_rejection_code_no_error(canister):
 def test_get_rejection_message(canister):
     raw = call_canister(canister, "get_rejection_message", '("test")', example_dir=EXAMPLE_DIR, update=True)
     assert len(raw) > 0
+
+
+def test_call_error_canister_reject(canister):
+    raw = call_canister(canister, "get_call_error_canister_reject", '("go away")', example_dir=EXAMPLE_DIR, update=True)
+    assert 'code_name = "CanisterReject"' in raw
+    assert "code_number = 4" in raw
+    assert 'reject_message = "go away"' in raw
+    assert "matches_reject_code = true" in raw
+    assert 'as_text = "Rejection code 4, go away"' in raw
+
+
+def test_call_error_canister_error(canister):
+    raw = call_canister(canister, "get_call_error_canister_error", example_dir=EXAMPLE_DIR, update=True)
+    assert 'code_name = "CanisterError"' in raw
+    assert "code_number = 5" in raw
+    assert "matches_reject_code = true" in raw
+
+
+def test_call_error_destination_invalid(canister):
+    raw = call_canister(canister, "get_call_error_destination_invalid", example_dir=EXAMPLE_DIR, update=True)
+    assert 'code_name = "DestinationInvalid"' in raw
+    assert "code_number = 3" in raw
+    assert "matches_reject_code = true" in raw
+
+
+def test_catch_rejection(canister):
+    raw = call_canister(canister, "catch_rejection", '("no thanks")', example_dir=EXAMPLE_DIR, update=True)
+    assert "caught CanisterReject: no thanks" in raw
+
+
+def test_catch_rejection_awaited(canister):
+    raw = call_canister(canister, "catch_rejection_awaited", '("not now")', example_dir=EXAMPLE_DIR, update=True)
+    assert "caught CanisterReject: not now" in raw
+
+
+def test_catch_gathered_rejection(canister):
+    raw = call_canister(canister, "catch_gathered_rejection", example_dir=EXAMPLE_DIR, update=True)
+    assert "caught second after True" in raw
+
+
+def test_uncaught_rejection_rejects_after_cleanup(canister):
+    err = call_canister_expect_trap(canister, "reject_after_cleanup", example_dir=EXAMPLE_DIR)
+    assert "CallRejected" in err
+    assert "downstream failed" in err
+    assert "trapped" not in err.lower()
+    assert "1" in call_canister(canister, "get_cleanups", example_dir=EXAMPLE_DIR)
+
+
+def test_raise_reject(canister):
+    err = call_canister_expect_trap(canister, "raise_reject", '("not allowed")', example_dir=EXAMPLE_DIR)
+    assert "not allowed" in err
+    assert "trapped" not in err.lower()
+
+
+def test_raise_reject_after_call(canister):
+    err = call_canister_expect_trap(canister, "raise_reject_after_call", '("too late")', example_dir=EXAMPLE_DIR)
+    assert "too late" in err
+    assert "trapped" not in err.lower()
+
+
+def test_raise_reject_subclass(canister):
+    err = call_canister_expect_trap(canister, "raise_reject_subclass", '("balance too low")', example_dir=EXAMPLE_DIR)
+    assert "balance too low" in err
+    assert "trapped" not in err.lower()
+
+
+def test_exception_named_reject_traps(canister):
+    err = call_canister_expect_trap(canister, "raise_lookalike_reject", '("impostor")', example_dir=EXAMPLE_DIR)
+    assert "Error calling 'raise_lookalike_reject'" in err
+    assert "Reject: impostor" in err
+
+
+def test_err_on_exception_replies_err_and_keeps_state(canister):
+    raw = call_canister(canister, "increment_then_fail", example_dir=EXAMPLE_DIR)
+    assert "Err" in raw
+    assert "ValueError: count is now 1" in raw
+    assert "1" in call_canister(canister, "get_count", example_dir=EXAMPLE_DIR)
+
+
+def test_exception_without_opt_in_traps_and_rolls_back(canister):
+    err = call_canister_expect_trap(canister, "increment_then_trap", example_dir=EXAMPLE_DIR)
+    assert "Error calling 'increment_then_trap'" in err
+    assert "ValueError: count is now 2" in err
+    assert "1" in call_canister(canister, "get_count", example_dir=EXAMPLE_DIR)
+
+
+def test_raise_on_reject_traps_before_first_await(canister):
+    err = call_canister_expect_trap(canister, "increment_then_fail_before_await", example_dir=EXAMPLE_DIR)
+    assert "Error in async method 'increment_then_fail_before_await'" in err
+    assert "ValueError: count is now 2" in err
+    assert "1" in call_canister(canister, "get_count", example_dir=EXAMPLE_DIR)
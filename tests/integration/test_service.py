@@ -98,3 +98,42 @@ def test_candid_encode_error_is_catchable(canisters):
         assert "candid_encode error" in str(e), (
             f"Expected candid_encode trap, got: {e}"
         )
+
+
+def test_func_reference_round_trip(canisters):
+    service_canister = canisters.get("service") or list(canisters.values())[0]
+    raw = call_canister(
+        service_canister, "make_callback", '(principal "aaaaa-aa")',
+        example_dir=EXAMPLE_DIR,
+    )
+    assert "aaaaa-aa" in raw and "on_event" in raw
+
+
+def test_func_reference_carries_signature(canisters):
+    service_canister = canisters.get("service") or list(canisters.values())[0]
+    raw = call_canister(
+        service_canister, "describe_callback", '(func "aaaaa-aa".on_event)',
+        example_dir=EXAMPLE_DIR,
+    )
+    assert parse_candid_text(raw) == "aaaaa-aa.on_event : (text) -> (text) query"
+
+
+def test_func_reference_is_callable(canisters):
+    service_canister = canisters.get("service") or list(canisters.values())[0]
+    some_service_id = canisters.get("some_service") or list(canisters.values())[-1]
+    raw = call_canister(
+        service_canister, "invoke_callback",
+        f'(func "{some_service_id}".on_event, "ping")',
+        example_dir=EXAMPLE_DIR,
+        update=True,
+    )
+    assert "handled ping" in raw
+
+
+def test_service_reference_gets_method_types(canisters):
+    service_canister = canisters.get("service") or list(canisters.values())[0]
+    raw = call_canister(
+        service_canister, "service_ref_arg_type", '(service "aaaaa-aa", "echo_text")',
+        example_dir=EXAMPLE_DIR,
+    )
+    assert parse_candid_text(raw) == "text"
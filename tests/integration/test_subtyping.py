@@ -0,0 +1,45 @@
+"""Integration tests for tests/fixtures/subtyping — decoding arguments with Candid subtyping.
+
+Each call is encoded against client.did, whose argument types differ from the
+ones the canister declares, so the canister sees other types on the wire.
+"""
+
+import pytest
+from .conftest import deploy_example, call_canister, call_canister_expect_trap, EXAMPLES_DIR
+import os
+
+EXAMPLE = "subtyping"
+EXAMPLE_DIR = os.path.join(EXAMPLES_DIR, EXAMPLE)
+CLIENT_DID = os.path.join(EXAMPLE_DIR, "client.did")
+
+
+@pytest.fixture(scope="module")
+def canister(replica):
+    ids = deploy_example(EXAMPLE)
+    return ids[list(ids.keys())[0]]
+
+
+def test_nat32_widens_to_nat64(canister):
+    raw = call_canister(canister, "widen", "(4_000_000_000 : nat32)", example_dir=EXAMPLE_DIR, candid=CLIENT_DID)
+    assert "4_000_000_000" in raw or "4000000000" in raw
+
+
+def test_missing_opt_field_becomes_none(canister):
+    raw = call_canister(canister, "nickname", '(record { name = "ada" })', example_dir=EXAMPLE_DIR, candid=CLIENT_DID)
+    assert '"none"' in raw
+
+
+def test_extra_record_fields_are_ignored(canister):
+    raw = call_canister(
+        canister,
+        "profile_name",
+        '(record { name = "ada"; nickname = opt "countess"; age = 36 })',
+        example_dir=EXAMPLE_DIR,
+        candid=CLIENT_DID,
+    )
+    assert '"ada"' in raw
+
+
+def test_type_mismatch_is_rejected_with_path(canister):
+    err = call_canister_expect_trap(canister, "count", '("seven")', example_dir=EXAMPLE_DIR, candid=CLIENT_DID)
+    assert "Invalid Candid argument 'n': expected nat32, found text" in err
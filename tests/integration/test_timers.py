@@ -1,5 +1,6 @@
 """Integration tests for tests/fixtures/timers — timer callback functionality."""
 
+import subprocess
 import time
 import pytest
 from .conftest import deploy_example, call_canister, EXAMPLES_DIR
@@ -31,3 +32,41 @@ def test_set_timers_and_verify(canister):
     # Check status
     raw = call_canister(canister, "status_report", example_dir=EXAMPLE_DIR)
     assert len(raw) > 0
+
+
+def test_failing_timers_are_recorded_and_handled(canister):
+    call_canister(canister, "set_failing_timers", "(1 : nat64)", example_dir=EXAMPLE_DIR, update=True)
+    time.sleep(5)
+
+    raw = call_canister(canister, "background_errors", example_dir=EXAMPLE_DIR)
+    assert "failing_timer_callback" in raw
+    assert "ValueError" in raw
+    assert "timer failed" in raw
+    # The generator failed in a later message, after its call returned
+    assert "failing_cross_canister_timer_callback" in raw
+    assert "KeyError" in raw
+
+    raw = call_canister(canister, "get_handled_errors", example_dir=EXAMPLE_DIR)
+    assert "failing_timer_callback: ValueError" in raw
+    assert "failing_cross_canister_timer_callback: KeyError" in raw
+
+
+def test_background_errors_survive_upgrade(canister):
+    # Relies on the failures recorded by the previous test
+    wasm_path = os.path.join(EXAMPLE_DIR, ".basilisk", "timers", "timers.wasm")
+    if not os.path.exists(wasm_path):
+        pytest.skip("WASM not available for upgrade test")
+
+    result = subprocess.run(
+        ["dfx", "canister", "install", "timers",
+         "--mode", "upgrade", "--wasm", wasm_path, "--upgrade-unchanged"],
+        cwd=EXAMPLE_DIR,
+        capture_output=True,
+        text=True,
+        timeout=120,
+    )
+    assert result.returncode == 0, f"Upgrade failed: {result.stderr}"
+
+    raw = call_canister(canister, "background_errors", example_dir=EXAMPLE_DIR)
+    assert "failing_timer_callback" in raw
+    assert "failing_cross_canister_timer_callback" in raw
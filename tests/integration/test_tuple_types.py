@@ -27,3 +27,24 @@ def test_two_tuple(canister):
 def test_three_tuple(canister):
     raw = call_canister(canister, "primitive_three_tuple_return_type", example_dir=EXAMPLE_DIR)
     assert "record" in raw
+
+
+def test_multiple_return_values(canister):
+    raw = call_canister(canister, "multiple_return_values", example_dir=EXAMPLE_DIR)
+    assert "record" not in raw
+    assert '"Hello"' in raw
+    assert "42 : nat64" in raw
+
+
+def test_multiple_return_values_from_tuple_param(canister):
+    raw = call_canister(
+        canister, "multiple_return_values_param", '(record { "Hi"; 7 : nat64 })', example_dir=EXAMPLE_DIR
+    )
+    assert "record" not in raw
+    assert '"Hi"' in raw
+    assert "7 : nat64" in raw
+
+
+def test_no_return_values(canister):
+    raw = call_canister(canister, "no_return_values", example_dir=EXAMPLE_DIR)
+    assert raw == "()"